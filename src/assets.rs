@@ -0,0 +1,68 @@
+// Decoding the files startup would otherwise block on, off the main thread
+//
+// Each file (or set of sprite layers) gets a thread of its own that reads
+// and decodes it into plain pixels or vertices. The GPU side, textures and
+// buffers and bind groups, needs the device, so `State` makes it on the
+// main thread once it polls a finished job.
+
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+
+use crate::model::{Model, ModelError};
+use crate::texture::{ArrayImages, LutImage, TextureError};
+
+/// A decoded file, or why it couldn't be.
+pub enum Asset {
+    Sprites(Result<ArrayImages, TextureError>),
+    Lut(Result<LutImage, TextureError>),
+    Model(PathBuf, Result<Model, ModelError>),
+}
+
+#[derive(Default)]
+pub struct AssetLoader {
+    jobs: Vec<JoinHandle<Asset>>,
+}
+
+impl AssetLoader {
+    pub fn sprites(&mut self, paths: Vec<PathBuf>) {
+        self.spawn(move || Asset::Sprites(ArrayImages::decode(&paths)));
+    }
+
+    pub fn lut(&mut self, path: PathBuf) {
+        self.spawn(move || Asset::Lut(LutImage::decode(path)));
+    }
+
+    pub fn model(&mut self, path: PathBuf) {
+        self.spawn(move || {
+            let model = Model::load_gltf(&path);
+            Asset::Model(path, model)
+        });
+    }
+
+    fn spawn(&mut self, job: impl FnOnce() -> Asset + Send + 'static) {
+        self.jobs.push(std::thread::spawn(job));
+    }
+
+    pub fn loading(&self) -> bool {
+        !self.jobs.is_empty()
+    }
+
+    /// The jobs that have finished since the last call, without waiting for
+    /// the rest. A job that panicked is logged and dropped.
+    pub fn finished(&mut self) -> Vec<Asset> {
+        let (done, pending) = std::mem::take(&mut self.jobs).into_iter().partition(JoinHandle::is_finished);
+        self.jobs = pending;
+        join(done)
+    }
+
+    /// Every job, blocking until the last one is done.
+    pub fn wait(&mut self) -> Vec<Asset> {
+        join(std::mem::take(&mut self.jobs))
+    }
+}
+
+fn join(jobs: Vec<JoinHandle<Asset>>) -> Vec<Asset> {
+    jobs.into_iter()
+        .filter_map(|job| job.join().inspect_err(|_| log::error!("an asset loading thread panicked")).ok())
+        .collect()
+}
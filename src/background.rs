@@ -0,0 +1,341 @@
+// Fullscreen backgrounds drawn into the scene pass before any geometry
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::error::GfxError;
+use crate::gfx::{create_shader_module, stage_write};
+use crate::resources::{ResourceKind, ResourceReport};
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BackgroundParams {
+    // rgb, padded to vec4 for uniform layout
+    color_a: [f32; 4],
+    color_b: [f32; 4],
+    tile_size: f32,
+    _pad: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SkyParams {
+    sky_top: [f32; 4],
+    sky_bottom: [f32; 4],
+    night_top: [f32; 4],
+    night_bottom: [f32; 4],
+    // 0 shows the configured colors, 1 the night preset
+    t: f32,
+    height: f32,
+    _pad: [f32; 2],
+}
+
+/// Stops a gradient can have; more are dropped.
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+/// Where a gradient color sits, 0 at the bottom of the window and 1 at the
+/// top, and the rgb it has there.
+pub type GradientStop = (f32, [f32; 3]);
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GradientParams {
+    // rgb, with the stop's position in w
+    stops: [[f32; 4]; MAX_GRADIENT_STOPS],
+    count: u32,
+    height: f32,
+    _pad: [f32; 2],
+}
+
+// What an auto-cycling sky fades to and back from
+const NIGHT_TOP: [f32; 3] = [0.01, 0.01, 0.04];
+const NIGHT_BOTTOM: [f32; 3] = [0.08, 0.05, 0.16];
+
+/// Two-color checkerboard, handy for judging semi-transparent geometry.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CheckerboardConfig {
+    pub colors: [[f32; 3]; 2],
+    /// Squares along the window's shorter side; their pixel size follows the window.
+    pub tiles: u32,
+}
+
+impl Default for CheckerboardConfig {
+    fn default() -> Self {
+        Self { colors: [[0.4, 0.4, 0.4], [0.25, 0.25, 0.25]], tiles: 12 }
+    }
+}
+
+/// Vertical two-stop gradient, optionally fading to night and back.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SkyConfig {
+    pub top: [f32; 3],
+    pub bottom: [f32; 3],
+    /// Seconds for a full day-night-day cycle; `None` holds the day colors.
+    pub cycle: Option<f32>,
+}
+
+impl Default for SkyConfig {
+    fn default() -> Self {
+        Self { top: [0.2, 0.45, 0.9], bottom: [0.75, 0.85, 1.0], cycle: None }
+    }
+}
+
+pub struct Background {
+    checkerboard_pipeline: wgpu::RenderPipeline,
+    sky_pipeline: wgpu::RenderPipeline,
+    params_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    sky_buffer: wgpu::Buffer,
+    sky_bind_group: wgpu::BindGroup,
+    gradient_pipeline: wgpu::RenderPipeline,
+    gradient_buffer: wgpu::Buffer,
+    gradient_bind_group: wgpu::BindGroup,
+    checkerboard: Option<CheckerboardConfig>,
+    sky: Option<SkyConfig>,
+    // empty for none
+    gradient: Vec<GradientStop>,
+    // window size the tile size was last computed for
+    size: (u32, u32),
+}
+
+impl Background {
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        output_scale: f64,
+    ) -> Result<Self, GfxError> {
+        let shader = create_shader_module(device, "Background Shader", include_str!("background.wgsl"))?;
+
+        // each mode has its own uniform block and binding
+        let uniform = |label, binding, size: usize| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size as u64),
+                    },
+                    count: None,
+                }],
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bgl,
+                entries: &[wgpu::BindGroupEntry { binding, resource: buffer.as_entire_binding() }],
+            });
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[&bgl],
+                push_constant_ranges: &[],
+            });
+            (buffer, bind_group, layout)
+        };
+        let (params_buffer, bind_group, checkerboard_layout) =
+            uniform("Background Params", 0, std::mem::size_of::<BackgroundParams>());
+        let (sky_buffer, sky_bind_group, sky_layout) = uniform("Sky Params", 1, std::mem::size_of::<SkyParams>());
+        let (gradient_buffer, gradient_bind_group, gradient_layout) =
+            uniform("Gradient Params", 2, std::mem::size_of::<GradientParams>());
+
+        let pipeline = |label, layout, fs_entry| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(fs_entry),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[("output_scale", output_scale)],
+                    ..Default::default()
+                },
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // shares the scene pass, but never occludes anything
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
+            multiview: None,
+            cache: None,
+        });
+        let checkerboard_pipeline = pipeline("Checkerboard Pipeline", &checkerboard_layout, "fs_checkerboard");
+        let sky_pipeline = pipeline("Sky Pipeline", &sky_layout, "fs_sky");
+        let gradient_pipeline = pipeline("Gradient Pipeline", &gradient_layout, "fs_gradient");
+
+        Ok(Self {
+            checkerboard_pipeline,
+            sky_pipeline,
+            params_buffer,
+            bind_group,
+            sky_buffer,
+            sky_bind_group,
+            gradient_pipeline,
+            gradient_buffer,
+            gradient_bind_group,
+            checkerboard: None,
+            sky: None,
+            gradient: Vec::new(),
+            size: (1, 1),
+        })
+    }
+
+    pub fn checkerboard(&self) -> Option<CheckerboardConfig> {
+        self.checkerboard
+    }
+
+    pub fn set_checkerboard(&mut self, queue: &wgpu::Queue, config: Option<CheckerboardConfig>) {
+        self.checkerboard = config;
+        self.write_params(queue);
+    }
+
+    pub fn sky(&self) -> Option<SkyConfig> {
+        self.sky
+    }
+
+    /// The checkerboard and the gradient still win while they're set.
+    pub fn set_sky(&mut self, queue: &wgpu::Queue, config: Option<SkyConfig>) {
+        self.sky = config;
+        self.write_sky(queue, 0.0);
+    }
+
+    pub fn gradient(&self) -> &[GradientStop] {
+        &self.gradient
+    }
+
+    /// Blends between `stops` from the bottom of the window to the top,
+    /// holding the end colors past the first and last; none turns it off.
+    /// Positions have to go up (or stay put, for a hard edge) within 0..=1,
+    /// and stops past `MAX_GRADIENT_STOPS` are dropped. The checkerboard
+    /// still wins while both are set.
+    pub fn set_gradient(&mut self, queue: &wgpu::Queue, stops: &[GradientStop]) -> Result<(), GfxError> {
+        let mut last = 0.0;
+        for (index, &(position, _)) in stops.iter().enumerate() {
+            if !(last..=1.0).contains(&position) {
+                return Err(GfxError::GradientStop { index, position });
+            }
+            last = position;
+        }
+        if stops.len() > MAX_GRADIENT_STOPS {
+            log::warn!("{} gradient stops, keeping the first {MAX_GRADIENT_STOPS}", stops.len());
+        }
+        self.gradient = stops[..stops.len().min(MAX_GRADIENT_STOPS)].to_vec();
+        self.write_gradient(queue);
+        Ok(())
+    }
+
+    /// Keeps the tile count along the shorter side as the window changes size.
+    pub fn resize(&mut self, queue: &wgpu::Queue, width: u32, height: u32) {
+        self.size = (width, height);
+        self.write_params(queue);
+        self.write_sky(queue, 0.0);
+        self.write_gradient(queue);
+    }
+
+    /// Records the sky's blend toward night for `time` seconds in, if it cycles.
+    pub fn update(
+        &self,
+        belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        time: f32,
+    ) {
+        let Some(SkyConfig { cycle: Some(period), .. }) = self.sky else { return };
+        // day at 0, night halfway through
+        let t = 0.5 - 0.5 * (time / period.max(1e-3) * std::f32::consts::TAU).cos();
+        if let Some(params) = self.sky_params(t) {
+            stage_write(belt, encoder, device, &self.sky_buffer, bytemuck::bytes_of(&params));
+        }
+    }
+
+    fn write_sky(&self, queue: &wgpu::Queue, t: f32) {
+        if let Some(params) = self.sky_params(t) {
+            queue.write_buffer(&self.sky_buffer, 0, bytemuck::bytes_of(&params));
+        }
+    }
+
+    fn sky_params(&self, t: f32) -> Option<SkyParams> {
+        let config = self.sky?;
+        let rgba = |c: [f32; 3]| [c[0], c[1], c[2], 1.0];
+        Some(SkyParams {
+            sky_top: rgba(config.top),
+            sky_bottom: rgba(config.bottom),
+            night_top: rgba(NIGHT_TOP),
+            night_bottom: rgba(NIGHT_BOTTOM),
+            t,
+            height: self.size.1.max(1) as f32,
+            _pad: [0.0; 2],
+        })
+    }
+
+    fn write_gradient(&self, queue: &wgpu::Queue) {
+        if self.gradient.is_empty() {
+            return;
+        }
+        let mut params = GradientParams::zeroed();
+        for (stop, &(position, [r, g, b])) in params.stops.iter_mut().zip(&self.gradient) {
+            *stop = [r, g, b, position];
+        }
+        params.count = self.gradient.len() as u32;
+        params.height = self.size.1.max(1) as f32;
+        queue.write_buffer(&self.gradient_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    fn write_params(&self, queue: &wgpu::Queue) {
+        let Some(config) = self.checkerboard else { return };
+        let [a, b] = config.colors;
+        let shorter = self.size.0.min(self.size.1).max(1);
+        let params = BackgroundParams {
+            color_a: [a[0], a[1], a[2], 1.0],
+            color_b: [b[0], b[1], b[2], 1.0],
+            tile_size: (shorter as f32 / config.tiles.max(1) as f32).max(1.0),
+            _pad: [0.0; 3],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    pub fn report_resources(&self, report: &mut ResourceReport) {
+        report.buffer(ResourceKind::UniformBuffer, &self.params_buffer);
+        report.buffer(ResourceKind::UniformBuffer, &self.sky_buffer);
+        report.buffer(ResourceKind::UniformBuffer, &self.gradient_buffer);
+    }
+
+    /// Covers the pass with the active background, if there is one.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        if self.checkerboard.is_some() {
+            render_pass.set_pipeline(&self.checkerboard_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        } else if !self.gradient.is_empty() {
+            render_pass.set_pipeline(&self.gradient_pipeline);
+            render_pass.set_bind_group(0, &self.gradient_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        } else if self.sky.is_some() {
+            render_pass.set_pipeline(&self.sky_pipeline);
+            render_pass.set_bind_group(0, &self.sky_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
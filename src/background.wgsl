@@ -0,0 +1,76 @@
+// Fullscreen backgrounds drawn under the scene geometry
+
+struct BackgroundParams {
+    color_a : vec4<f32>,
+    color_b : vec4<f32>,
+    // checkerboard square size in pixels
+    tile_size : f32,
+};
+
+// >1.0 on HDR swapchains, matching the scene shaders
+override output_scale: f32 = 1.0;
+
+struct SkyParams {
+    sky_top : vec4<f32>,
+    sky_bottom : vec4<f32>,
+    night_top : vec4<f32>,
+    night_bottom : vec4<f32>,
+    // blend from the sky colors (0) to the night ones (1)
+    t : f32,
+    // window height in pixels
+    height : f32,
+};
+
+struct GradientParams {
+    // rgb, with the stop's position up the window (0 to 1) in w
+    stops : array<vec4<f32>, 8>,
+    count : u32,
+    // window height in pixels
+    height : f32,
+};
+
+// each pipeline's layout only has the binding its mode reads
+@group(0) @binding(0)
+var<uniform> params : BackgroundParams;
+@group(0) @binding(1)
+var<uniform> sky : SkyParams;
+@group(0) @binding(2)
+var<uniform> gradient : GradientParams;
+
+// One oversized triangle covering the screen, no vertex buffer needed
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_checkerboard(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let tile = vec2<i32>(floor(position.xy / params.tile_size));
+    let color = select(params.color_b, params.color_a, (tile.x + tile.y) % 2 == 0);
+    return vec4<f32>(color.rgb * output_scale, 1.0);
+}
+
+@fragment
+fn fs_sky(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // position.y counts down from the top of the window
+    let up = 1.0 - position.y / sky.height;
+    let day = mix(sky.sky_bottom.rgb, sky.sky_top.rgb, up);
+    let night = mix(sky.night_bottom.rgb, sky.night_top.rgb, up);
+    return vec4<f32>(mix(day, night, sky.t) * output_scale, 1.0);
+}
+
+@fragment
+fn fs_gradient(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let up = 1.0 - position.y / gradient.height;
+    // below the first stop nothing replaces its color, and past the last
+    // the final segment clamps to the end
+    var color = gradient.stops[0].rgb;
+    for (var i = 1u; i < gradient.count; i++) {
+        let a = gradient.stops[i - 1u];
+        let b = gradient.stops[i];
+        let t = clamp((up - a.w) / max(b.w - a.w, 1e-6), 0.0, 1.0);
+        color = select(color, mix(a.rgb, b.rgb, t), up >= a.w);
+    }
+    return vec4<f32>(color * output_scale, 1.0);
+}
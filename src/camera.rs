@@ -0,0 +1,319 @@
+use std::fmt;
+
+use glam::{Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// How a camera maps its view volume onto the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectionMode {
+    /// Vertical field of view in radians
+    Perspective { fov: f32 },
+    /// World units visible from the bottom of the view to the top
+    Orthographic { height: f32 },
+}
+
+impl ProjectionMode {
+    /// World units visible top to bottom at `distance` in front of the eye.
+    pub fn visible_height(self, distance: f32) -> f32 {
+        match self {
+            Self::Perspective { fov } => 2.0 * distance * (fov * 0.5).tan(),
+            Self::Orthographic { height } => height,
+        }
+    }
+
+    /// The other mode, matched so whatever is `distance` away keeps its
+    /// size on screen.
+    pub fn toggled(self, distance: f32) -> Self {
+        match self {
+            Self::Perspective { .. } => Self::Orthographic { height: self.visible_height(distance) },
+            Self::Orthographic { height } => Self::Perspective { fov: 2.0 * (height * 0.5 / distance.max(1e-6)).atan() },
+        }
+    }
+}
+
+/// A camera looking at a fixed point.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    pub projection: ProjectionMode,
+    pub znear: f32,
+    pub zfar: f32,
+    /// Depth 1 at the near plane and 0 at the far one, for a `Greater` test.
+    pub reverse_z: bool,
+}
+
+impl Camera {
+    /// Looks at a bounding sphere from the +z side, far enough back that it
+    /// fits the vertical field of view.
+    pub fn framing(center: Vec3, radius: f32) -> Self {
+        let fovy = 45f32.to_radians();
+        let radius = radius.max(1e-3);
+        let distance = radius / (fovy * 0.5).sin();
+        Self {
+            eye: center + Vec3::new(0.0, radius * 0.5, distance),
+            target: center,
+            up: Vec3::Y,
+            projection: ProjectionMode::Perspective { fov: fovy },
+            znear: (distance - radius * 2.0).max(distance * 0.01),
+            zfar: distance + radius * 2.0,
+            reverse_z: false,
+        }
+    }
+
+    /// Moves the clip planes for the next `view_proj`. `near` has to be
+    /// positive and `far` beyond it; otherwise nothing changes.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), ClipPlaneError> {
+        // written so NaN fails too
+        if !(near > 0.0 && far > near && far.is_finite()) {
+            return Err(ClipPlaneError { near, far });
+        }
+        (self.znear, self.zfar) = (near, far);
+        Ok(())
+    }
+
+    /// World to clip space, with wgpu's 0..1 depth range.
+    pub fn view_proj(&self, aspect: f32) -> Mat4 {
+        let view = Mat4::look_at_rh(self.eye, self.target, self.up);
+        // swapping the planes is all reverse-Z takes
+        let (near, far) = if self.reverse_z { (self.zfar, self.znear) } else { (self.znear, self.zfar) };
+        let proj = match self.projection {
+            ProjectionMode::Perspective { fov } => Mat4::perspective_rh(fov, aspect, near, far),
+            ProjectionMode::Orthographic { height } => {
+                let (half_w, half_h) = (height * 0.5 * aspect, height * 0.5);
+                Mat4::orthographic_rh(-half_w, half_w, -half_h, half_h, near, far)
+            }
+        };
+        proj * view
+    }
+
+    /// Roughly how far apart in world units two things `distance` in front
+    /// of the eye have to be to land on different 32-bit float depth values,
+    /// which is where z-fighting starts.
+    pub fn depth_step(&self, distance: f32) -> f32 {
+        let (near, far, z) = (self.znear, self.zfar, distance);
+        // depth at z, and how fast it changes there
+        let (depth, slope) = match self.projection {
+            ProjectionMode::Perspective { .. } => {
+                let slope = near * far / (z * z * (far - near));
+                let depth = if self.reverse_z { near * (far - z) } else { far * (z - near) } / (z * (far - near));
+                (depth, slope)
+            }
+            ProjectionMode::Orthographic { .. } => {
+                let depth = if self.reverse_z { far - z } else { z - near } / (far - near);
+                (depth, 1.0 / (far - near))
+            }
+        };
+        // the gap down to the next float, normals only since GPUs flush the rest
+        let depth = depth.max(f32::MIN_POSITIVE);
+        (depth - f32::from_bits(depth.to_bits() - 1)) / slope
+    }
+
+    /// One line on how finely the depth buffer resolves the view.
+    pub fn depth_precision(&self) -> String {
+        let mid = (self.znear + self.zfar) * 0.5;
+        format!(
+            "clip planes {}..{} (far/near {:.0}, {}): depth steps of {:.1e} halfway and {:.1e} at the far plane",
+            self.znear,
+            self.zfar,
+            self.zfar / self.znear,
+            if self.reverse_z { "reverse-Z" } else { "standard Z" },
+            self.depth_step(mid),
+            self.depth_step(self.zfar)
+        )
+    }
+}
+
+/// Clip planes that can't make a projection.
+#[derive(Clone, Copy, Debug)]
+pub struct ClipPlaneError {
+    pub near: f32,
+    pub far: f32,
+}
+
+impl fmt::Display for ClipPlaneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "clip planes {}..{} won't do: near has to be above 0 and far beyond it", self.near, self.far)
+    }
+}
+
+impl std::error::Error for ClipPlaneError {}
+
+// Just short of straight up or down, where the view's up vector would flip
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// A camera circling `target` under mouse control, for model viewing.
+#[derive(Clone, Copy, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub distance: f32,
+    /// Radians around +y, 0 looking from +z.
+    pub yaw: f32,
+    /// Radians above the horizon, kept within just under a quarter turn.
+    pub pitch: f32,
+    pub projection: ProjectionMode,
+    pub reverse_z: bool,
+    // size of what's being looked at, to place the clip planes around it
+    radius: f32,
+    // set by `set_clip_planes` in place of fitting them to the model
+    clip_planes: Option<(f32, f32)>,
+}
+
+impl OrbitCamera {
+    /// Starts from the same view as `Camera::framing`.
+    pub fn framing(center: Vec3, radius: f32) -> Self {
+        let camera = Camera::framing(center, radius);
+        let offset = camera.eye - camera.target;
+        let distance = offset.length();
+        Self {
+            target: camera.target,
+            distance,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / distance).asin(),
+            projection: camera.projection,
+            reverse_z: false,
+            radius: radius.max(1e-3),
+            clip_planes: None,
+        }
+    }
+
+    /// Fixes the clip planes at `near` and `far` wherever the camera goes,
+    /// or with `None` goes back to fitting them around the model. Invalid
+    /// planes are refused and the current ones kept.
+    pub fn set_clip_planes(&mut self, planes: Option<(f32, f32)>) -> Result<(), ClipPlaneError> {
+        if let Some((near, far)) = planes {
+            self.camera().set_clip_planes(near, far)?;
+        }
+        self.clip_planes = planes;
+        Ok(())
+    }
+
+    pub fn clip_planes(&self) -> Option<(f32, f32)> {
+        self.clip_planes
+    }
+
+    /// Turns by `yaw` and `pitch` radians; positive yaw moves the eye to
+    /// the right around the target, positive pitch moves it up.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Scales the distance to the target, `factor` below 1 moving closer.
+    /// Orthographic views shrink along with it, since distance alone
+    /// changes nothing there.
+    pub fn zoom(&mut self, factor: f32) {
+        let distance = (self.distance * factor).max(self.radius * 0.05);
+        if let ProjectionMode::Orthographic { height } = &mut self.projection {
+            *height *= distance / self.distance;
+        }
+        self.distance = distance;
+    }
+
+    /// Switches between perspective and orthographic, keeping the target's
+    /// size on screen.
+    pub fn toggle_projection(&mut self) {
+        self.projection = self.projection.toggled(self.distance);
+    }
+
+    /// Slides the target across the view by a drag of `dx`, `dy` pixels,
+    /// so whatever was under the cursor stays under it at the target's depth.
+    pub fn pan(&mut self, dx: f32, dy: f32, viewport_height: u32) {
+        let camera = self.camera();
+        let forward = (camera.target - camera.eye).normalize();
+        let right = forward.cross(Vec3::Y).normalize();
+        let up = right.cross(forward);
+        let world_per_pixel = self.projection.visible_height(self.distance) / viewport_height.max(1) as f32;
+        self.target += (up * dy - right * dx) * world_per_pixel;
+    }
+
+    /// The equivalent fixed camera, with clip planes hugging the model
+    /// unless they've been set.
+    pub fn camera(&self) -> Camera {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let offset = Vec3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw) * self.distance;
+        let (znear, zfar) = self.clip_planes.unwrap_or((
+            (self.distance - self.radius * 2.0).max(self.distance * 0.01),
+            self.distance + self.radius * 2.0,
+        ));
+        Camera {
+            eye: self.target + offset,
+            target: self.target,
+            up: Vec3::Y,
+            projection: self.projection,
+            znear,
+            zfar,
+            reverse_z: self.reverse_z,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Looking down -z from the origin with the planes at 0.1 and 100
+    fn camera(projection: ProjectionMode, reverse_z: bool) -> Camera {
+        Camera { eye: Vec3::ZERO, target: -Vec3::Z, up: Vec3::Y, projection, znear: 0.1, zfar: 100.0, reverse_z }
+    }
+
+    const PERSPECTIVE: ProjectionMode = ProjectionMode::Perspective { fov: 1.0 };
+
+    #[test]
+    fn bad_clip_planes_are_refused_and_the_old_ones_kept() {
+        let mut camera = camera(PERSPECTIVE, false);
+        for (near, far) in [(0.0, 10.0), (-1.0, 10.0), (5.0, 5.0), (5.0, 1.0), (f32::NAN, 10.0), (1.0, f32::NAN), (1.0, f32::INFINITY)] {
+            assert!(camera.set_clip_planes(near, far).is_err(), "{near}..{far}");
+            assert_eq!((camera.znear, camera.zfar), (0.1, 100.0));
+        }
+        camera.set_clip_planes(0.5, 50.0).unwrap();
+        assert_eq!((camera.znear, camera.zfar), (0.5, 50.0));
+    }
+
+    #[test]
+    fn orbit_camera_keeps_fixed_planes_until_cleared() {
+        let mut orbit = OrbitCamera::framing(Vec3::ZERO, 1.0);
+        let fitted = orbit.camera();
+        assert!(orbit.set_clip_planes(Some((2.0, 1.0))).is_err());
+        assert_eq!(orbit.clip_planes(), None);
+
+        orbit.set_clip_planes(Some((0.25, 400.0))).unwrap();
+        orbit.distance *= 3.0;
+        assert_eq!((orbit.camera().znear, orbit.camera().zfar), (0.25, 400.0));
+
+        orbit.set_clip_planes(None).unwrap();
+        orbit.distance /= 3.0;
+        assert_eq!((orbit.camera().znear, orbit.camera().zfar), (fitted.znear, fitted.zfar));
+    }
+
+    // Depth the projection gives a point `distance` down the view axis
+    fn depth_at(camera: &Camera, distance: f32) -> f32 {
+        let clip = camera.view_proj(1.0) * (-Vec3::Z * distance).extend(1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn depth_step_is_where_the_depth_buffer_changes() {
+        for camera in [camera(PERSPECTIVE, false), camera(PERSPECTIVE, true), camera(ProjectionMode::Orthographic { height: 2.0 }, false)] {
+            for distance in [1.0, 50.0, 90.0] {
+                let step = camera.depth_step(distance);
+                assert!(step > 0.0 && step.is_finite(), "{camera:?} at {distance}");
+                // far out, reverse-Z resolves finer than the position itself can move
+                let further = distance + 4.0 * step;
+                if further > distance {
+                    assert_ne!(depth_at(&camera, distance), depth_at(&camera, further), "{camera:?} at {distance}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_z_resolves_the_distance_better() {
+        let (standard, reversed) = (camera(PERSPECTIVE, false), camera(PERSPECTIVE, true));
+        assert!(standard.depth_step(90.0) > standard.depth_step(10.0));
+        assert!(reversed.depth_step(90.0) * 100.0 < standard.depth_step(90.0));
+    }
+}
@@ -0,0 +1,131 @@
+// What the adapter can do beyond the baseline, checked once at startup
+
+use std::fmt;
+
+/// An optional device ability some code path depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Compute pipelines, for the compute-only render mode.
+    ComputeShaders,
+    /// Storage buffers read from the vertex stage, for vertex pulling.
+    VertexStorage,
+    /// MSAA counts other than 4, where the formats allow them.
+    AdapterSpecificSampleCounts,
+    /// Line and point polygon modes, for wireframes.
+    PolygonModeLine,
+    PushConstants,
+    /// Timestamp queries on passes, for GPU timing.
+    TimestampQuery,
+    /// `@builtin(primitive_index)`, for the triangle debug colors.
+    PrimitiveIndex,
+    /// Indirect draws, for culling the instances on the GPU.
+    IndirectDraw,
+    /// A 32-bit float depth format with stencil, for reverse-Z precision
+    /// alongside the stencil buffer.
+    Depth32FloatStencil8,
+}
+
+impl Capability {
+    const ALL: [Capability; 9] = [
+        Self::ComputeShaders,
+        Self::VertexStorage,
+        Self::AdapterSpecificSampleCounts,
+        Self::PolygonModeLine,
+        Self::PushConstants,
+        Self::TimestampQuery,
+        Self::PrimitiveIndex,
+        Self::IndirectDraw,
+        Self::Depth32FloatStencil8,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::ComputeShaders => "compute shaders",
+            Self::VertexStorage => "vertex storage buffers",
+            Self::AdapterSpecificSampleCounts => "adapter-specific sample counts",
+            Self::PolygonModeLine => "line polygon mode",
+            Self::PushConstants => "push constants",
+            Self::TimestampQuery => "timestamp queries",
+            Self::PrimitiveIndex => "primitive index",
+            Self::IndirectDraw => "indirect draws",
+            Self::Depth32FloatStencil8 => "32-bit float depth-stencil",
+        }
+    }
+
+    // The device feature to request for it, for the ones that are features
+    // rather than downlevel flags
+    fn feature(self) -> Option<wgpu::Features> {
+        match self {
+            Self::ComputeShaders | Self::VertexStorage | Self::IndirectDraw => None,
+            Self::AdapterSpecificSampleCounts => Some(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
+            Self::PolygonModeLine => Some(wgpu::Features::POLYGON_MODE_LINE),
+            Self::PushConstants => Some(wgpu::Features::PUSH_CONSTANTS),
+            Self::TimestampQuery => Some(wgpu::Features::TIMESTAMP_QUERY),
+            Self::PrimitiveIndex => Some(wgpu::Features::SHADER_PRIMITIVE_INDEX),
+            Self::Depth32FloatStencil8 => Some(wgpu::Features::DEPTH32FLOAT_STENCIL8),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Which `Capability`s the adapter has. Only those get requested from the
+/// device, so asking for an optional feature never fails device creation;
+/// the code that wants one checks here and falls back instead.
+#[derive(Clone, Copy, Debug)]
+pub struct Capabilities {
+    features: wgpu::Features,
+    downlevel: wgpu::DownlevelFlags,
+    max_vertex_storage_buffers: u32,
+}
+
+impl Capabilities {
+    pub fn new(adapter: &wgpu::Adapter) -> Self {
+        let wanted = Capability::ALL.into_iter().filter_map(Capability::feature).fold(wgpu::Features::empty(), |a, b| a | b);
+        Self {
+            features: adapter.features() & wanted,
+            downlevel: adapter.get_downlevel_capabilities().flags,
+            max_vertex_storage_buffers: adapter.limits().max_storage_buffers_per_shader_stage,
+        }
+    }
+
+    /// The features to put in the device descriptor.
+    pub fn required_features(&self) -> wgpu::Features {
+        self.features
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::ComputeShaders => self.downlevel.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+            Capability::IndirectDraw => self.downlevel.contains(wgpu::DownlevelFlags::INDIRECT_EXECUTION),
+            Capability::VertexStorage => {
+                self.downlevel.contains(wgpu::DownlevelFlags::VERTEX_STORAGE) && self.max_vertex_storage_buffers > 0
+            }
+            feature => feature.feature().is_some_and(|feature| self.features.contains(feature)),
+        }
+    }
+
+    /// `supports`, logging "feature X unavailable, falling back to ..." with
+    /// `fallback` when it's missing.
+    pub fn require(&self, capability: Capability, fallback: &str) -> bool {
+        let supported = self.supports(capability);
+        if !supported {
+            log::warn!("feature {capability} unavailable, falling back to {fallback}");
+        }
+        supported
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "capabilities:")?;
+        for capability in Capability::ALL {
+            write!(f, "\n  {:<32}{}", capability.name(), if self.supports(capability) { "yes" } else { "no" })?;
+        }
+        Ok(())
+    }
+}
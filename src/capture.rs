@@ -0,0 +1,176 @@
+// Reading rendered frames back to the CPU and writing them out
+
+use std::fmt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A frame read back from the GPU as tightly packed RGBA8 rows.
+pub struct Capture {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Readback only understands 8-bit RGBA/BGRA targets.
+    UnsupportedFormat(wgpu::TextureFormat),
+    Map(wgpu::BufferAsyncError),
+    /// A scaled capture would be bigger than the device's largest texture.
+    TooLarge { width: u64, height: u64, max: u32 },
+    Io(std::io::Error),
+    Gif(gif::EncodingError),
+    Png(image::ImageError),
+    /// None of the platform's clipboard tools took the image.
+    NoClipboard,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => write!(f, "can't read back {format:?} frames"),
+            Self::Map(e) => write!(f, "mapping the readback buffer failed: {e}"),
+            Self::TooLarge { width, height, max } => {
+                write!(f, "a {width}x{height} capture exceeds this GPU's {max}px texture limit")
+            }
+            Self::Io(e) => write!(f, "writing the capture failed: {e}"),
+            Self::Gif(e) => write!(f, "encoding the gif failed: {e}"),
+            Self::Png(e) => write!(f, "encoding the png failed: {e}"),
+            Self::NoClipboard => write!(f, "no clipboard tool could take the image"),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<gif::EncodingError> for CaptureError {
+    fn from(e: gif::EncodingError) -> Self {
+        Self::Gif(e)
+    }
+}
+
+impl From<image::ImageError> for CaptureError {
+    fn from(e: image::ImageError) -> Self {
+        Self::Png(e)
+    }
+}
+
+/// Copies must use rows padded to a multiple of 256 bytes.
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// Whether `format` stores channels as BGRA and needs swizzling to RGBA,
+/// or `None` if readback can't handle it at all.
+pub fn is_bgra(format: wgpu::TextureFormat) -> Option<bool> {
+    use wgpu::TextureFormat::*;
+    match format {
+        Rgba8Unorm | Rgba8UnormSrgb => Some(false),
+        Bgra8Unorm | Bgra8UnormSrgb => Some(true),
+        _ => None,
+    }
+}
+
+/// Strips the row padding from a mapped readback buffer, swizzling to RGBA.
+pub fn unpad(data: &[u8], width: u32, height: u32, bgra: bool) -> Vec<u8> {
+    let row_bytes = width as usize * 4;
+    let padded = padded_bytes_per_row(width) as usize;
+    let mut rgba = Vec::with_capacity(row_bytes * height as usize);
+    for row in data.chunks(padded).take(height as usize) {
+        rgba.extend_from_slice(&row[..row_bytes]);
+    }
+    if bgra {
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    rgba
+}
+
+/// Encodes `frames` as an endlessly looping gif; each frame is quantized to
+/// its own 256-color palette.
+pub fn write_gif(path: &Path, mut frames: Vec<Capture>, fps: u32) -> Result<(), CaptureError> {
+    let Some(first) = frames.first() else { return Ok(()) };
+    let (width, height) = (first.width as u16, first.height as u16);
+
+    let file = std::fs::File::create(path)?;
+    let mut encoder = gif::Encoder::new(std::io::BufWriter::new(file), width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    // gif delays are in hundredths of a second
+    let delay = (100 / fps.max(1)) as u16;
+    for capture in &mut frames {
+        // speed 10 is the crate's recommended quality/speed tradeoff for NeuQuant
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut capture.rgba, 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+/// Writes a single capture as an RGBA png.
+pub fn write_png(path: &Path, capture: &Capture) -> Result<(), CaptureError> {
+    image::save_buffer_with_format(
+        path,
+        &capture.rgba,
+        capture.width,
+        capture.height,
+        image::ExtendedColorType::Rgba8,
+        image::ImageFormat::Png,
+    )?;
+    Ok(())
+}
+
+/// Puts the png at `path` on the system clipboard, returning the tool that
+/// did it. There's no clipboard crate in the dependency tree, so this runs
+/// whichever of the platform's own tools is installed: wl-copy or xclip,
+/// osascript, or PowerShell.
+pub fn copy_png_to_clipboard(path: &Path) -> Result<&'static str, CaptureError> {
+    for (tool, mut command) in clipboard_commands(path) {
+        command.stdout(Stdio::null()).stderr(Stdio::null());
+        match command.status() {
+            Ok(status) if status.success() => return Ok(tool),
+            Ok(status) => log::debug!("{tool} couldn't take the image ({status})"),
+            Err(e) => log::debug!("couldn't run {tool}: {e}"),
+        }
+    }
+    Err(CaptureError::NoClipboard)
+}
+
+// Commands that copy the png at `path` to the clipboard, most likely to work first
+fn clipboard_commands(path: &Path) -> Vec<(&'static str, Command)> {
+    let mut commands = Vec::new();
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        let script = format!("set the clipboard to (read (POSIX file {:?}) as «class PNGf»)", path.display().to_string());
+        command.args(["-e", &script]);
+        commands.push(("osascript", command));
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms, System.Drawing; \
+             [System.Windows.Forms.Clipboard]::SetImage([System.Drawing.Image]::FromFile('{}'))",
+            path.display().to_string().replace('\'', "''")
+        );
+        command.args(["-NoProfile", "-STA", "-Command", &script]);
+        commands.push(("powershell", command));
+    } else {
+        // Wayland first, since xclip can also succeed through XWayland
+        // without reaching native Wayland apps
+        if let Ok(file) = std::fs::File::open(path) {
+            let mut command = Command::new("wl-copy");
+            command.args(["--type", "image/png"]).stdin(file);
+            commands.push(("wl-copy", command));
+        }
+        let mut command = Command::new("xclip");
+        command.args(["-selection", "clipboard", "-t", "image/png", "-i"]).arg(path);
+        commands.push(("xclip", command));
+    }
+    commands
+}
@@ -0,0 +1,285 @@
+// Command line flags, parsed by hand to keep dependencies down
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::gfx::{Easing, ScaleMode};
+use crate::mesh::Spiral;
+
+/// How the event loop idles between events.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    /// Sleep until something happens. Animation keeps going because every
+    /// frame requests the next redraw, and vsync paces them; in between
+    /// the thread sleeps, so the CPU cost is just the frames themselves.
+    #[default]
+    Wait,
+    /// Spin through the loop without sleeping, requesting redraws at the
+    /// monitor's refresh rate. Events are never held up behind a sleep,
+    /// but a core stays busy the whole time.
+    Poll,
+}
+
+impl FromStr for LoopMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wait" => Ok(Self::Wait),
+            "poll" => Ok(Self::Poll),
+            _ => Err(format!("unknown loop mode `{s}`, expected wait or poll")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub hdr: bool,
+    /// Samples per pixel for the scene; 1 is no MSAA
+    pub msaa: u32,
+    /// Load depth/stencil from the previous frame instead of clearing them
+    pub keep_depth: bool,
+    pub keep_stencil: bool,
+    pub split_vertices: bool,
+    pub pull_vertices: bool,
+    /// Store vertex colors as 8-bit unorm instead of floats
+    pub unorm_colors: bool,
+    pub frame_latency: Option<u32>,
+    /// Index into the adapter list logged at startup
+    pub adapter: Option<usize>,
+    pub world_extent: f32,
+    /// Width over height the 2D content is authored for, and how it's fit
+    /// into a window of another shape
+    pub content_aspect: Option<f32>,
+    pub scale_mode: ScaleMode,
+    /// Simulation seconds per wall-clock second; 0 freezes, below 0 runs backwards
+    pub time_scale: f32,
+    /// How the square eases into a quarter turn when Shift+R stops the spin
+    pub easing: Easing,
+    /// Where the G key writes its recording, and how many frames it takes
+    pub gif_path: PathBuf,
+    pub gif_frames: u32,
+    /// Where the P key writes a screenshot, and at what multiple of the window size
+    pub screenshot_path: PathBuf,
+    pub screenshot_scale: u32,
+    /// Set by `--screenshot`: a run cut short by `--run-frames` or
+    /// `--run-seconds` saves one just before exiting
+    pub screenshot_on_exit: bool,
+    /// Exit after this many frames or seconds, whichever comes first
+    pub run_frames: Option<u64>,
+    pub run_seconds: Option<f32>,
+    pub model: Option<PathBuf>,
+    /// The model camera's clip planes, instead of fitting them to the model;
+    /// only used when both are given
+    pub near: Option<f32>,
+    pub far: Option<f32>,
+    /// WGSL file to use instead of the embedded shader
+    pub shader: Option<PathBuf>,
+    /// Pie slice mesh: start angle and sweep in degrees, and wedge count
+    pub pie_start: f32,
+    pub pie_sweep: f32,
+    pub pie_segments: u16,
+    /// Binary vertex dump to add as an extra mesh
+    pub vertices: Option<PathBuf>,
+    /// Scatter this many instances at random instead of the grid; the seed
+    /// makes the layout reproducible
+    pub scatter_count: Option<u32>,
+    pub scatter_seed: u64,
+    /// Spiral drawn over the scene from the start; `--spiral` or any of the
+    /// `--spiral-*` parameters turns it on
+    pub spiral: Option<Spiral>,
+    /// LUT strip image to color grade the output through
+    pub lut: Option<PathBuf>,
+    /// Images for the sprite array's layers, one `--sprite` each, in order
+    pub sprites: Vec<PathBuf>,
+    /// Key bindings file overriding the defaults; see `keys.rs` for the format
+    pub keys: Option<PathBuf>,
+    /// Warn about frames that take longer than this many milliseconds
+    pub frame_budget_ms: Option<f32>,
+    /// Print which optional device features are available and exit
+    pub capabilities: bool,
+    /// Time this many uniform uploads per method, print the results and exit
+    pub bench_uploads: Option<u32>,
+    /// Time this many instanced frames culled on the CPU and on the GPU,
+    /// print the results and exit
+    pub bench_culling: Option<u32>,
+    /// Time this many frames of the densest wave with each vertex color
+    /// format, print the results and exit
+    pub bench_vertex_colors: Option<u32>,
+    /// Seconds the step key advances the simulation by while paused
+    pub step_dt: f32,
+    /// `--loop-mode`, or `GFX_1_LOOP_MODE` when the flag isn't given
+    pub loop_mode: LoopMode,
+    /// Write every input event and frame time step here; see `replay.rs`
+    pub record: Option<PathBuf>,
+    /// Play a recording back in place of live input until it runs out
+    pub replay: Option<PathBuf>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            hdr: false,
+            msaa: 1,
+            keep_depth: false,
+            keep_stencil: false,
+            split_vertices: false,
+            pull_vertices: false,
+            unorm_colors: false,
+            frame_latency: None,
+            adapter: None,
+            world_extent: 1.0,
+            content_aspect: None,
+            scale_mode: ScaleMode::default(),
+            time_scale: 1.0,
+            easing: Easing::default(),
+            gif_path: PathBuf::from("recording.gif"),
+            gif_frames: 100,
+            screenshot_path: PathBuf::from("screenshot.png"),
+            screenshot_scale: 2,
+            screenshot_on_exit: false,
+            run_frames: None,
+            run_seconds: None,
+            model: None,
+            near: None,
+            far: None,
+            shader: None,
+            pie_start: 0.0,
+            pie_sweep: 270.0,
+            pie_segments: 24,
+            vertices: None,
+            scatter_count: None,
+            scatter_seed: 0,
+            spiral: None,
+            lut: None,
+            sprites: Vec::new(),
+            keys: None,
+            frame_budget_ms: None,
+            capabilities: false,
+            bench_uploads: None,
+            bench_culling: None,
+            bench_vertex_colors: None,
+            step_dt: 1.0 / 60.0,
+            loop_mode: LoopMode::default(),
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut args = Self::default();
+        if let Ok(mode) = std::env::var("GFX_1_LOOP_MODE") {
+            parse_value("GFX_1_LOOP_MODE", Some(mode), &mut args.loop_mode);
+        }
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--hdr" => args.hdr = true,
+                "--msaa" => parse_value(&arg, iter.next(), &mut args.msaa),
+                "--keep-depth" => args.keep_depth = true,
+                "--keep-stencil" => args.keep_stencil = true,
+                "--split-vertices" => args.split_vertices = true,
+                "--pull-vertices" => args.pull_vertices = true,
+                "--unorm-colors" => args.unorm_colors = true,
+                "--frame-latency" => {
+                    let mut frames = 0;
+                    parse_value(&arg, iter.next(), &mut frames);
+                    args.frame_latency = (frames > 0).then_some(frames);
+                }
+                "--adapter" => {
+                    let value = iter.next();
+                    args.adapter = value.as_deref().and_then(|v| v.parse().ok());
+                    if args.adapter.is_none() {
+                        log::warn!("`{arg}` expects an adapter index, got {value:?}");
+                    }
+                }
+                "--world-extent" => parse_value(&arg, iter.next(), &mut args.world_extent),
+                "--content-aspect" => {
+                    let mut aspect = 0.0;
+                    parse_value(&arg, iter.next(), &mut aspect);
+                    args.content_aspect = (aspect > 0.0).then_some(aspect);
+                }
+                "--scale-mode" => parse_value(&arg, iter.next(), &mut args.scale_mode),
+                "--time-scale" => parse_value(&arg, iter.next(), &mut args.time_scale),
+                "--easing" => parse_value(&arg, iter.next(), &mut args.easing),
+                "--gif" => parse_value(&arg, iter.next(), &mut args.gif_path),
+                "--gif-frames" => parse_value(&arg, iter.next(), &mut args.gif_frames),
+                "--screenshot" => {
+                    parse_value(&arg, iter.next(), &mut args.screenshot_path);
+                    args.screenshot_on_exit = true;
+                }
+                "--screenshot-scale" => parse_value(&arg, iter.next(), &mut args.screenshot_scale),
+                "--model" => args.model = iter.next().map(PathBuf::from),
+                "--near" => parse_value(&arg, iter.next(), args.near.get_or_insert_default()),
+                "--far" => parse_value(&arg, iter.next(), args.far.get_or_insert_default()),
+                "--shader" => args.shader = iter.next().map(PathBuf::from),
+                "--pie-start" => parse_value(&arg, iter.next(), &mut args.pie_start),
+                "--pie-sweep" => parse_value(&arg, iter.next(), &mut args.pie_sweep),
+                "--pie-segments" => parse_value(&arg, iter.next(), &mut args.pie_segments),
+                "--vertices" => args.vertices = iter.next().map(PathBuf::from),
+                "--scatter" => {
+                    let mut count = 0;
+                    parse_value(&arg, iter.next(), &mut count);
+                    args.scatter_count = Some(count);
+                }
+                "--scatter-seed" => parse_value(&arg, iter.next(), &mut args.scatter_seed),
+                "--spiral" => _ = args.spiral.get_or_insert_default(),
+                "--spiral-a" => parse_value(&arg, iter.next(), &mut args.spiral.get_or_insert_default().a),
+                "--spiral-b" => parse_value(&arg, iter.next(), &mut args.spiral.get_or_insert_default().b),
+                "--spiral-turns" => parse_value(&arg, iter.next(), &mut args.spiral.get_or_insert_default().turns),
+                "--spiral-points" => parse_value(&arg, iter.next(), &mut args.spiral.get_or_insert_default().points),
+                "--lut" => args.lut = iter.next().map(PathBuf::from),
+                "--sprite" => args.sprites.extend(iter.next().map(PathBuf::from)),
+                "--keys" => args.keys = iter.next().map(PathBuf::from),
+                "--frame-budget" => {
+                    let mut ms = 0.0;
+                    parse_value(&arg, iter.next(), &mut ms);
+                    args.frame_budget_ms = (ms > 0.0).then_some(ms);
+                }
+                "--step-dt" => parse_value(&arg, iter.next(), &mut args.step_dt),
+                "--loop-mode" => parse_value(&arg, iter.next(), &mut args.loop_mode),
+                "--run-frames" => {
+                    let mut frames = 0;
+                    parse_value(&arg, iter.next(), &mut frames);
+                    args.run_frames = (frames > 0).then_some(frames);
+                }
+                "--run-seconds" => {
+                    let mut seconds = 0.0;
+                    parse_value(&arg, iter.next(), &mut seconds);
+                    args.run_seconds = (seconds > 0.0).then_some(seconds);
+                }
+                "--record" => args.record = iter.next().map(PathBuf::from),
+                "--replay" => args.replay = iter.next().map(PathBuf::from),
+                "--capabilities" => args.capabilities = true,
+                "--bench-uploads" => {
+                    let mut frames = 0;
+                    parse_value(&arg, iter.next(), &mut frames);
+                    args.bench_uploads = (frames > 0).then_some(frames);
+                }
+                "--bench-culling" => {
+                    let mut frames = 0;
+                    parse_value(&arg, iter.next(), &mut frames);
+                    args.bench_culling = (frames > 0).then_some(frames);
+                }
+                "--bench-vertex-colors" => {
+                    let mut frames = 0;
+                    parse_value(&arg, iter.next(), &mut frames);
+                    args.bench_vertex_colors = (frames > 0).then_some(frames);
+                }
+                other => log::warn!("ignoring unknown argument `{other}`"),
+            }
+        }
+        args
+    }
+}
+
+// Overwrites `target` with the flag's parsed value, keeping the default and
+// warning if it's missing or malformed
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>, target: &mut T) {
+    match value.as_deref().map(str::parse) {
+        Some(Ok(v)) => *target = v,
+        _ => log::warn!("`{flag}` expects a value, got {value:?}"),
+    }
+}
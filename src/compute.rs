@@ -0,0 +1,127 @@
+// A standalone compute workload for frames that draw nothing
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::error::GfxError;
+use crate::gfx::{create_shader_module, stage_write};
+use crate::resources::{ResourceKind, ResourceReport};
+
+// Enough points that a dispatch is measurable, small enough to set up instantly
+const POINTS: u32 = 1 << 18;
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ComputeParams {
+    angle: f32,
+    count: u32,
+    _pad: [u32; 2],
+}
+
+/// Rotates a buffer of points on the GPU; nothing reads them back.
+pub struct SpinCompute {
+    pipeline: wgpu::ComputePipeline,
+    params_buffer: wgpu::Buffer,
+    // only reached through the bind group; kept for the resource report
+    points_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl SpinCompute {
+    pub fn new(device: &wgpu::Device) -> Result<Self, GfxError> {
+        let shader = create_shader_module(device, "Compute Shader", include_str!("compute.wgsl"))?;
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compute Params"),
+            size: std::mem::size_of::<ComputeParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // a ring of points, so spinning them keeps them in place as a whole
+        let points: Vec<[f32; 2]> = (0..POINTS)
+            .map(|i| {
+                let a = i as f32 / POINTS as f32 * std::f32::consts::TAU;
+                [a.cos(), a.sin()]
+            })
+            .collect();
+        let points_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Compute Points"),
+            contents: bytemuck::cast_slice(&points),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Compute BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ComputeParams>() as u64),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compute BG"),
+            layout: &bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: points_buffer.as_entire_binding() },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Compute Pipeline Layout"),
+            bind_group_layouts: &[&bgl],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Spin Pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: Some("cs_spin"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Ok(Self { pipeline, params_buffer, points_buffer, bind_group })
+    }
+
+    pub fn report_resources(&self, report: &mut ResourceReport) {
+        report.buffer(ResourceKind::UniformBuffer, &self.params_buffer);
+        report.buffer(ResourceKind::StorageBuffer, &self.points_buffer);
+    }
+
+    /// Records one dispatch over every point, turning them by `angle`.
+    pub fn encode(
+        &self,
+        belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        angle: f32,
+    ) {
+        let params = ComputeParams { angle, count: POINTS, _pad: [0; 2] };
+        stage_write(belt, encoder, device, &self.params_buffer, bytemuck::bytes_of(&params));
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Spin Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(POINTS.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+}
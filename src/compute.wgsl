@@ -0,0 +1,24 @@
+// Busywork for compute-only frames: spin a cloud of points about the origin
+
+struct ComputeParams {
+    // radians to turn each point by this dispatch
+    angle : f32,
+    count : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> params : ComputeParams;
+
+@group(0) @binding(1)
+var<storage, read_write> points : array<vec2<f32>>;
+
+@compute @workgroup_size(64)
+fn cs_spin(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.count) {
+        return;
+    }
+    let p = points[id.x];
+    let c = cos(params.angle);
+    let s = sin(params.angle);
+    points[id.x] = vec2<f32>(p.x * c - p.y * s, p.x * s + p.y * c);
+}
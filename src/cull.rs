@@ -0,0 +1,248 @@
+// Instance culling in a compute pass, feeding an indirect draw
+
+use std::fmt;
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::{DeviceExt, DrawIndexedIndirectArgs};
+
+use crate::error::GfxError;
+use crate::gfx::{create_shader_module, stage_write};
+use crate::resources::{ResourceKind, ResourceReport};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct CullParams {
+    half_size: [f32; 2],
+    radius: f32,
+    count: u32,
+}
+
+/// Every instance kept on the GPU, and each frame the ones that can touch
+/// the view compacted into a second buffer along with the arguments to
+/// draw them indirectly. Neither the survivors nor their count come back
+/// to the CPU, and their order changes from frame to frame.
+pub struct GpuCull {
+    pipeline: wgpu::ComputePipeline,
+    bgl: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    instances_buffer: wgpu::Buffer,
+    // also the instance vertex buffer for the draw
+    visible_buffer: wgpu::Buffer,
+    args_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    count: u32,
+}
+
+impl GpuCull {
+    pub fn new<T: Pod>(device: &wgpu::Device, instances: &[T]) -> Result<Self, GfxError> {
+        let shader = create_shader_module(device, "Cull Shader", include_str!("cull.wgsl"))?;
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cull Params"),
+            size: std::mem::size_of::<CullParams>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let args_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cull Draw Args"),
+            contents: DrawIndexedIndirectArgs { index_count: 0, instance_count: 0, first_index: 0, base_vertex: 0, first_instance: 0 }
+                .as_bytes(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_DST,
+        });
+        let (instances_buffer, visible_buffer) = create_instance_buffers(device, instances);
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cull BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CullParams>() as u64),
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+                storage(3, false),
+            ],
+        });
+        let bind_group = create_bind_group(device, &bgl, &params_buffer, &instances_buffer, &visible_buffer, &args_buffer);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Cull Pipeline Layout"),
+            bind_group_layouts: &[&bgl],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Cull Pipeline"),
+            layout: Some(&layout),
+            module: &shader,
+            entry_point: Some("cs_cull"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Ok(Self {
+            pipeline,
+            bgl,
+            params_buffer,
+            instances_buffer,
+            visible_buffer,
+            args_buffer,
+            bind_group,
+            count: instances.len() as u32,
+        })
+    }
+
+    /// Replaces the instances, growing both buffers if they don't fit.
+    pub fn set_instances<T: Pod>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[T]) {
+        let data: &[u8] = bytemuck::cast_slice(instances);
+        if data.len() as wgpu::BufferAddress > self.instances_buffer.size() {
+            (self.instances_buffer, self.visible_buffer) = create_instance_buffers(device, instances);
+            self.bind_group = create_bind_group(
+                device, &self.bgl, &self.params_buffer, &self.instances_buffer, &self.visible_buffer, &self.args_buffer,
+            );
+        } else {
+            queue.write_buffer(&self.instances_buffer, 0, data);
+        }
+        self.count = instances.len() as u32;
+    }
+
+    pub fn report_resources(&self, report: &mut ResourceReport) {
+        report.buffer(ResourceKind::UniformBuffer, &self.params_buffer);
+        report.buffer(ResourceKind::InstanceBuffer, &self.instances_buffer);
+        report.buffer(ResourceKind::InstanceBuffer, &self.visible_buffer);
+        report.buffer(ResourceKind::IndirectBuffer, &self.args_buffer);
+    }
+
+    /// Records the culling dispatch: instances whose `radius` circle (scaled
+    /// with them) reaches into the `half_size` view around the origin are
+    /// kept, for a draw of `index_count` indices each.
+    pub fn encode(
+        &self,
+        belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        half_size: [f32; 2],
+        radius: f32,
+        index_count: u32,
+    ) {
+        let params = CullParams { half_size, radius, count: self.count };
+        stage_write(belt, encoder, device, &self.params_buffer, bytemuck::bytes_of(&params));
+        // the shader counts the instances up from 0
+        let args = DrawIndexedIndirectArgs { index_count, instance_count: 0, first_index: 0, base_vertex: 0, first_instance: 0 };
+        stage_write(belt, encoder, device, &self.args_buffer, args.as_bytes());
+        if self.count == 0 {
+            return;
+        }
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Cull Pass"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(self.count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Draws what the last `encode` kept, with the survivors bound as the
+    /// instance vertex buffer at `slot`. The mesh and pipeline are the caller's.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>, slot: u32) {
+        render_pass.set_vertex_buffer(slot, self.visible_buffer.slice(..));
+        render_pass.draw_indexed_indirect(&self.args_buffer, 0);
+    }
+}
+
+// The full set and the room for the survivors, at least one instance each
+// so the bindings are never empty
+fn create_instance_buffers<T: Pod>(device: &wgpu::Device, instances: &[T]) -> (wgpu::Buffer, wgpu::Buffer) {
+    let size = (instances.len().max(1) * std::mem::size_of::<T>()) as wgpu::BufferAddress;
+    let all = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Cull Instances"),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: true,
+    });
+    let data: &[u8] = bytemuck::cast_slice(instances);
+    all.slice(..).get_mapped_range_mut()[..data.len()].copy_from_slice(data);
+    all.unmap();
+    let visible = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Visible Instances"),
+        size,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+        mapped_at_creation: false,
+    });
+    (all, visible)
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    bgl: &wgpu::BindGroupLayout,
+    params: &wgpu::Buffer,
+    instances: &wgpu::Buffer,
+    visible: &wgpu::Buffer,
+    args: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Cull BG"),
+        layout: bgl,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: instances.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: visible.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: args.as_entire_binding() },
+        ],
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CullMethod {
+    /// Filtered on the CPU and the survivors uploaded every frame.
+    Cpu,
+    /// `GpuCull`, with the instances uploaded once.
+    Gpu,
+}
+
+pub struct CullTiming {
+    pub method: CullMethod,
+    pub instances: usize,
+    pub frames: u32,
+    /// Wall time for encoding and submitting every frame plus waiting for
+    /// the GPU at the end.
+    pub total: Duration,
+}
+
+impl CullTiming {
+    pub fn per_frame(&self) -> Duration {
+        self.total / self.frames.max(1)
+    }
+}
+
+impl fmt::Display for CullTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let per_second = self.instances as f64 * self.frames as f64 / self.total.as_secs_f64().max(1e-9);
+        write!(
+            f,
+            "{:?}: {:.2} ms/frame, {:.1}M instances/s ({} frames of {} instances)",
+            self.method,
+            self.per_frame().as_secs_f64() * 1e3,
+            per_second / 1e6,
+            self.frames,
+            self.instances
+        )
+    }
+}
@@ -0,0 +1,55 @@
+// Instance culling on the GPU: every instance that can touch the view is
+// appended to `visible` and counted into the indirect draw's arguments
+
+struct CullParams {
+    // the view's half extent in world units, around the origin
+    half_size : vec2<f32>,
+    // the mesh's bounding radius before instance scaling
+    radius : f32,
+    count : u32,
+};
+
+// Same layout as `Instance` on the CPU side; arrays rather than vectors so
+// nothing gets padded
+struct Instance {
+    offset : array<f32, 2>,
+    scale : f32,
+    depth : f32,
+    phase : f32,
+    tint : array<f32, 4>,
+};
+
+// `wgpu::util::DrawIndexedIndirectArgs`, with the instance count bumped here
+struct DrawArgs {
+    index_count : u32,
+    instance_count : atomic<u32>,
+    first_index : u32,
+    base_vertex : i32,
+    first_instance : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> params : CullParams;
+
+@group(0) @binding(1)
+var<storage, read> instances : array<Instance>;
+
+@group(0) @binding(2)
+var<storage, read_write> visible : array<Instance>;
+
+@group(0) @binding(3)
+var<storage, read_write> args : DrawArgs;
+
+// The same bounding-circle test as the CPU path
+@compute @workgroup_size(64)
+fn cs_cull(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.count) {
+        return;
+    }
+    let instance = instances[id.x];
+    let r = params.radius * instance.scale;
+    if (abs(instance.offset[0]) - r > params.half_size.x || abs(instance.offset[1]) - r > params.half_size.y) {
+        return;
+    }
+    visible[atomicAdd(&args.instance_count, 1u)] = instance;
+}
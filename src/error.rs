@@ -0,0 +1,109 @@
+// One error type for everything the renderer can fail at
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::capture::CaptureError;
+use crate::texture::TextureError;
+
+#[derive(Debug)]
+pub enum GfxError {
+    Init(StateInitError),
+    Surface(wgpu::SurfaceError),
+    Capture(CaptureError),
+    Texture(TextureError),
+    /// WGSL that failed validation, with the label of the module it came from.
+    Shader { label: String, message: String },
+    /// A shader file that couldn't be read.
+    ShaderFile { path: PathBuf, error: std::io::Error },
+    /// New vertex colors that don't line up one-to-one with the mesh's vertices.
+    ColorCount { expected: u32, got: usize },
+    /// A gradient stop out of 0..=1 or below the one before it.
+    GradientStop { index: usize, position: f32 },
+}
+
+/// Failures while bringing up the device and surface.
+#[derive(Debug)]
+pub enum StateInitError {
+    Surface(wgpu::CreateSurfaceError),
+    Adapter(wgpu::RequestAdapterError),
+    Device(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for GfxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Init(e) => write!(f, "couldn't start the renderer: {e}"),
+            Self::Surface(e) => write!(f, "couldn't get a frame from the window: {e}"),
+            Self::Capture(e) => write!(f, "{e}"),
+            Self::Texture(e) => write!(f, "{e}"),
+            Self::Shader { label, message } => write!(f, "shader `{label}` failed to compile:\n{message}"),
+            Self::ShaderFile { path, error } => write!(f, "couldn't read shader {}: {error}", path.display()),
+            Self::ColorCount { expected, got } => write!(f, "got {got} vertex colors for a mesh with {expected} vertices"),
+            Self::GradientStop { index, position } => {
+                write!(f, "gradient stop {index} is at {position}, but stops have to go up from 0 to 1")
+            }
+        }
+    }
+}
+
+impl fmt::Display for StateInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Surface(e) => write!(
+                f,
+                "can't draw to this window ({e}); try another backend with WGPU_BACKEND=vulkan, gl, metal \
+                 or dx12, or on Wayland fall back to X11 by unsetting WAYLAND_DISPLAY"
+            ),
+            Self::Adapter(e) => write!(f, "no compatible GPU found ({e}); check your graphics drivers"),
+            Self::Device(e) => write!(f, "the GPU refused to open a device ({e})"),
+        }
+    }
+}
+
+impl std::error::Error for GfxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Init(e) => Some(e),
+            Self::Surface(e) => Some(e),
+            Self::Capture(e) => Some(e),
+            Self::Texture(e) => Some(e),
+            Self::ShaderFile { error, .. } => Some(error),
+            Self::Shader { .. } | Self::ColorCount { .. } | Self::GradientStop { .. } => None,
+        }
+    }
+}
+
+impl std::error::Error for StateInitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Surface(e) => Some(e),
+            Self::Adapter(e) => Some(e),
+            Self::Device(e) => Some(e),
+        }
+    }
+}
+
+impl From<StateInitError> for GfxError {
+    fn from(e: StateInitError) -> Self {
+        Self::Init(e)
+    }
+}
+
+impl From<wgpu::SurfaceError> for GfxError {
+    fn from(e: wgpu::SurfaceError) -> Self {
+        Self::Surface(e)
+    }
+}
+
+impl From<CaptureError> for GfxError {
+    fn from(e: CaptureError) -> Self {
+        Self::Capture(e)
+    }
+}
+
+impl From<TextureError> for GfxError {
+    fn from(e: TextureError) -> Self {
+        Self::Texture(e)
+    }
+}
@@ -1,230 +1,5515 @@
 use wgpu::{self, util::DeviceExt};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use winit::window::Window;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use bytemuck::{Pod, Zeroable};
+use glam::Affine2;
+
+use crate::assets::{Asset, AssetLoader};
+use crate::background::{Background, CheckerboardConfig, GradientStop, SkyConfig};
+use crate::camera::{Camera, ClipPlaneError, ProjectionMode};
+use crate::capabilities::{Capabilities, Capability};
+use crate::capture::{self, Capture, CaptureError};
+use crate::compute::SpinCompute;
+use crate::cull::{CullMethod, CullTiming, GpuCull};
+use crate::error::{GfxError, StateInitError};
+use crate::mesh::{self, LineMesh, LineVertex, Mesh, Spiral, Vertex};
+use crate::model::ModelRenderer;
+use crate::texture::{self, Lut, Texture, TextureArray};
+use crate::upload_bench::{self, UploadTiming};
+use crate::vertex_file::load_vertices_bin;
+use crate::resources::{ResourceKind, ResourceReport};
+use crate::rng::SplitMix64;
+use crate::scene::SceneNode;
+use crate::snapshot::{CameraSnapshot, SnapshotError, UniformSnapshot};
+use crate::preset::{self, PresetError, Settings};
+use crate::post::{self, BloomSettings, BoxBlurSettings, ColorGradeSettings, DepthRange, PostProcess, VignetteSettings};
+
+// In extended-range output 1.0 maps to SDR white, so HDR scales the shader's
+// output past it to actually use the extra headroom
+const HDR_OUTPUT_SCALE: f64 = 2.0;
+
+/// Sane bounds for desired_maximum_frame_latency
+pub const MIN_FRAME_LATENCY: u32 = 1;
+pub const MAX_FRAME_LATENCY: u32 = 3;
+/// Most frames the CPU may record ahead of the GPU
+pub const MAX_FRAMES_IN_FLIGHT: u32 = 4;
+
+// Staging chunk size for per-frame uploads; comfortably above a frame's worth
+// of uniforms and instance data, so the belt settles into reusing the same few chunks
+const UPLOAD_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
+
+// Instanced mode: a square grid of small copies of the current mesh, reaching
+// well past the default view so culling has something to do
+const INSTANCE_GRID: i32 = 20;
+const INSTANCE_SPACING: f32 = 0.4;
+const INSTANCE_SCALE: f32 = 0.2;
+// Most instances `scatter_instances` will place
+const MAX_SCATTERED_INSTANCES: u32 = 100_000;
+
+// Translucent quads as (offset, z, with z = 0 lying on the shapes), listed
+// front to back so drawing them unsorted visibly gets the blending wrong
+const TRANSLUCENT_QUADS: [([f32; 2], f32); 3] = [([0.3, -0.2], -0.6), ([0.0, 0.2], -0.4), ([-0.3, -0.2], -0.2)];
+const TRANSLUCENT_SCALE: f32 = 0.6;
+const TRANSLUCENT_ALPHA: f32 = 0.5;
+
+// Quads the batch buffer starts out holding; it doubles from there as needed
+const QUAD_BATCH_CAPACITY: usize = 256;
+const VERTICES_PER_QUAD: usize = 6;
+// The same for the debug lines
+const DEBUG_LINE_CAPACITY: usize = 256;
+const VERTICES_PER_LINE: usize = 2;
+
+// Spiral sample counts `set_spiral` allows
+const MIN_SPIRAL_POINTS: u32 = 2;
+const MAX_SPIRAL_POINTS: u32 = 1 << 16;
+
+// The square mesh, which is what gets rounded corners, and its side length
+const SQUARE_MESH: usize = 0;
+const SQUARE_SIZE: [f32; 2] = [1.0, 1.0];
+
+// The Sierpinski mesh's slot among the preloaded meshes, and its recursion
+// depths; 3^depth triangles, which switch to 32-bit indices past depth 9
+const SIERPINSKI_MESH: usize = 5;
+const MAX_SIERPINSKI_DEPTH: u32 = 10;
+
+// Scene nodes with a transform slot in the node buffer; deeper or wider
+// trees have the rest skipped
+const MAX_SCENE_NODES: usize = 256;
+
+// Blur taps grow with the radius, so keep it bounded
+const MAX_BLOOM_RADIUS: f32 = 16.0;
+// What the scene is cleared to, and all a frame shows while loading
+const CLEAR_COLOR: wgpu::Color = wgpu::Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 };
+
+// Sampler filters there are texture bind groups for, in `filter_index` order
+const TEXTURE_FILTERS: [wgpu::FilterMode; 2] = [wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest];
+
+// One sprite array bind group per filter, in `TEXTURE_FILTERS` order
+fn sprite_bind_groups(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, sheet: &TextureArray) -> [wgpu::BindGroup; 2] {
+    TEXTURE_FILTERS.map(|filter| {
+        let sampler = texture::create_sampler(device, filter, false);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite BG"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&sampler) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&sheet.view) },
+            ],
+        })
+    })
+}
+
+fn filter_index(filter: wgpu::FilterMode) -> usize {
+    match filter {
+        wgpu::FilterMode::Linear => 0,
+        wgpu::FilterMode::Nearest => 1,
+    }
+}
+
+// Layers per second while the sprite array animates
+const SPRITE_FPS: f32 = 8.0;
+// Tries at getting a surface texture before a timed-out frame is skipped
+const ACQUIRE_ATTEMPTS: u32 = 3;
+
+// Frames over the budget are warned about at most this often, with a count
+// of the ones in between
+const FRAME_BUDGET_WARNING_INTERVAL: Duration = Duration::from_secs(1);
+// Each box blur pass costs 2 * radius + 1 full-resolution taps per pixel
+const MAX_BOX_BLUR_RADIUS: u32 = 16;
+const MAX_BOX_BLUR_ITERATIONS: u32 = 8;
+// Largest pixelation block, in output pixels
+const MAX_PIXELATION: u32 = 64;
+
+// Orbit camera mouse sensitivity: radians per pixel dragged, and the distance
+// factor per scroll-wheel line
+const ORBIT_SPEED: f32 = 0.01;
+// Where the screen shake's offsets start, fixed so runs repeat
+const SHAKE_SEED: u64 = 0x5ea5_1c4e;
+// Furthest the exploded view pushes triangles, in model bounding radii
+const MAX_EXPLODE: f32 = 1.0;
+// Fastest `set_time_scale` runs the simulation clock, either way
+const MAX_TIME_SCALE: f32 = 64.0;
+const ZOOM_PER_LINE: f32 = 0.9;
+
+// The subdivided quad the wave shader displaces: side length in world
+// units, and a cap on cells per side ((n + 1)^2 vertices, 6n^2 indices)
+const WAVE_SIZE: f32 = 1.2;
+const MAX_WAVE_SUBDIVISIONS: u32 = 1024;
+
+// Grid overlay layout in world units
+const GRID_HALF_EXTENT: f32 = 4.0;
+const GRID_SPACING: f32 = 0.25;
+const GRID_LINE_WIDTH: f32 = 0.01;
+
+// The scene's depth-stencil format: float depth where the adapter pairs it
+// with stencil, since reverse-Z relies on it, else the always-available one
+fn depth_format(capabilities: &Capabilities) -> wgpu::TextureFormat {
+    if capabilities.require(Capability::Depth32FloatStencil8, "24-bit depth") {
+        wgpu::TextureFormat::Depth32FloatStencil8
+    } else {
+        wgpu::TextureFormat::Depth24PlusStencil8
+    }
+}
+
+// Default bias for the grid overlay: negative pulls it toward the camera,
+// past the coplanar shapes it's drawn over
+const GRID_DEPTH_BIAS: wgpu::DepthBiasState = wgpu::DepthBiasState { constant: -8, slope_scale: -1.0, clamp: 0.0 };
+
+// Winding the pipeline treats as front-facing; generated meshes are wound to match
+const FRONT_FACE: wgpu::FrontFace = wgpu::FrontFace::Ccw;
+const CULL_MODE: Option<wgpu::Face> = Some(wgpu::Face::Back);
+
+// Translucent pipelines keep the usual alpha for the scene target's own alpha
+const OVER_ALPHA: wgpu::BlendComponent = wgpu::BlendComponent {
+    src_factor: wgpu::BlendFactor::One,
+    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+    operation: wgpu::BlendOperation::Add,
+};
+
+/// Adds each color, weighted by its alpha, onto what's behind it, so
+/// overlaps get brighter.
+pub const ADDITIVE_BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::SrcAlpha,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: OVER_ALPHA,
+};
+
+/// Multiplies what's behind by each color, so overlaps get darker. Alpha
+/// doesn't weaken it; the shaders' colors aren't premultiplied.
+pub const MULTIPLY_BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Dst,
+        dst_factor: wgpu::BlendFactor::Zero,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: OVER_ALPHA,
+};
+
+/// `1 - (1 - src)(1 - dst)`: brightens like additive without going past
+/// white. Ignores alpha like multiply.
+pub const SCREEN_BLEND: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::OneMinusSrc,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: OVER_ALPHA,
+};
+
+/// Run by `update` and `step` before the frame renders, with the seconds the
+/// simulation advanced (0 while paused).
+pub type UpdateCallback = Box<dyn FnMut(&mut State, f32)>;
+
+/// Run inside the scene pass after the built-in draws, so an embedding app
+/// can add its own. Whatever pipelines it sets have to match the pass: an
+/// Rgba16Float color target, Depth32FloatStencil8 depth if the capabilities
+/// have it and Depth24PlusStencil8 if not, and the MSAA sample count the
+/// state was built with (1 if the adapter refused it, which is logged).
+pub type RenderCallback = Box<dyn FnMut(&State, &mut wgpu::RenderPass<'_>)>;
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct AngleUniform {
     angle: f32,
-    _pad: [f32; 3],
+    prev_angle: f32,
+    ghost_alpha: f32,
+    layer: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct RoundedRectUniform {
+    size: [f32; 2],
+    corner_radius: f32,
+    // 0 draws no border
+    border_width: f32,
+    border_color: [f32; 4],
+}
+
+impl RoundedRectUniform {
+    fn new(corner_radius: f32, border: Option<(f32, [f32; 4])>) -> Self {
+        let (border_width, border_color) = border.unwrap_or_default();
+        Self { size: SQUARE_SIZE, corner_radius, border_width, border_color }
+    }
+}
+
+// Straight RGBA for the triangles facing away, while back faces are shown
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BackFaceUniform {
+    color: [f32; 4],
+}
+
+// The dash pattern for the grid and debug lines, in pixels of the viewport
+// the projection maps onto; a 0 dash draws them solid
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LineDashUniform {
+    viewport: [f32; 2],
+    dash: f32,
+    gap: f32,
+}
+
+impl LineDashUniform {
+    fn new(dash: Option<(f32, f32)>, [.., width, height]: [f32; 4]) -> Self {
+        let (dash, gap) = dash.unwrap_or_default();
+        Self { viewport: [width, height], dash, gap }
+    }
+}
+
+// A scene node's world transform, one per dynamic offset into the node buffer
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct NodeUniform {
+    model: [[f32; 4]; 4],
+}
+
+impl NodeUniform {
+    fn new(world: Affine2) -> Self {
+        let [x, y, t] = [world.matrix2.x_axis, world.matrix2.y_axis, world.translation];
+        Self { model: [[x.x, x.y, 0.0, 0.0], [y.x, y.y, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0], [t.x, t.y, 0.0, 1.0]] }
+    }
+}
+
+/// How vertex attributes are laid out in GPU buffers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexLayout {
+    /// One buffer of `Vertex` structs, position and color side by side.
+    #[default]
+    Interleaved,
+    /// Positions in one buffer (slot 0) and colors in another (slot 1), so
+    /// either can be updated without touching the other.
+    Split,
+    /// `Vertex` structs in a storage buffer the vertex shader indexes by
+    /// `vertex_index` itself, with no vertex buffer layout at all. Needs
+    /// vertex-stage storage buffers; falls back to `Interleaved` without them.
+    Pulled,
+}
+
+/// What `State::render` does each frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Draw the scene and present it.
+    #[default]
+    Graphics,
+    /// Dispatch the compute workload and submit, never acquiring or
+    /// presenting a surface texture; the window keeps its last image.
+    ComputeOnly,
+}
+
+/// How vertex colors are stored in GPU buffers. The shaders see floats either
+/// way, since `Unorm8x4` is normalized on fetch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexColors {
+    /// Four `f32`s, 16 bytes per vertex.
+    #[default]
+    Float,
+    /// Four bytes, quantized to 1/255 steps. Not used with `VertexLayout::Pulled`,
+    /// whose shader reads raw floats.
+    Unorm8,
+}
+
+impl VertexColors {
+    pub const ALL: [VertexColors; 2] = [Self::Float, Self::Unorm8];
+}
+
+/// How fast the wave drew with one vertex color format; see
+/// `State::bench_vertex_colors`.
+pub struct VertexColorTiming {
+    pub colors: VertexColors,
+    /// Size of the wave's vertex buffer, which every frame reads all of.
+    pub vertex_bytes: u64,
+    pub frames: u32,
+    /// Wall time for encoding and submitting every frame plus waiting for
+    /// the GPU at the end.
+    pub total: Duration,
+}
+
+impl VertexColorTiming {
+    pub fn per_frame(&self) -> Duration {
+        self.total / self.frames.max(1)
+    }
+}
+
+impl std::fmt::Display for VertexColorTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let per_second = self.vertex_bytes as f64 * self.frames as f64 / self.total.as_secs_f64().max(1e-9);
+        write!(
+            f,
+            "{:?}: {:.2} ms/frame, {:.1} MiB of vertices, {:.2} GiB/s of vertex data ({} frames)",
+            self.colors,
+            self.per_frame().as_secs_f64() * 1e3,
+            self.vertex_bytes as f64 / (1 << 20) as f64,
+            per_second / (1u64 << 30) as f64,
+            self.frames
+        )
+    }
+}
+
+// `Vertex` with its color packed to bytes
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PackedVertex {
+    position: [f32; 2],
+    color: [u8; 4],
+}
+
+fn pack_color(color: [f32; 4]) -> [u8; 4] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+enum VertexBuffers {
+    Interleaved(wgpu::Buffer),
+    Split { positions: wgpu::Buffer, colors: wgpu::Buffer },
+    // also usable as an interleaved vertex buffer, for pipelines that don't pull
+    Pulled { buffer: wgpu::Buffer, bind_group: wgpu::BindGroup },
+}
+
+impl VertexBuffers {
+    // for meshes that are always uploaded interleaved
+    fn interleaved(&self) -> &wgpu::Buffer {
+        match self {
+            Self::Interleaved(buffer) => buffer,
+            Self::Split { .. } | Self::Pulled { .. } => unreachable!("mesh wasn't uploaded interleaved"),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ProjectionUniform {
+    ortho: [[f32; 4]; 4],
+}
+
+impl ProjectionUniform {
+    /// Maps the world region `half_size` units either side of `center` onto
+    /// NDC.
+    fn new([half_w, half_h]: [f32; 2], [x, y]: [f32; 2]) -> Self {
+        Self { ortho: ortho(x - half_w, x + half_w, y - half_h, y + half_h) }
+    }
+}
+
+/// How a world region of a fixed aspect ratio (see
+/// `StateBuilder::content_aspect`) is put into a window of another one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    /// All of it, as large as fits, with bars of the clear color along the
+    /// sides or the top and bottom.
+    #[default]
+    Fit,
+    /// Covering the whole window, cropped along one axis.
+    Fill,
+    /// Squeezed to the window's shape, so world units stop being square.
+    Stretch,
+}
+
+impl ScaleMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Fit => Self::Fill,
+            Self::Fill => Self::Stretch,
+            Self::Stretch => Self::Fit,
+        }
+    }
+}
+
+impl std::str::FromStr for ScaleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fit" => Ok(Self::Fit),
+            "fill" => Ok(Self::Fill),
+            "stretch" => Ok(Self::Stretch),
+            _ => Err(format!("unknown scale mode `{s}`, expected fit, fill or stretch")),
+        }
+    }
+}
+
+/// How `State::rotate_to` gets from one angle to the other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// At a constant speed, starting and stopping abruptly.
+    Linear,
+    /// Speeding up away from the start and slowing down into the target.
+    #[default]
+    EaseInOut,
+    /// Overshooting the target and swinging back around it, each swing
+    /// smaller, until it comes to rest there.
+    Spring,
+}
+
+impl Easing {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Linear => Self::EaseInOut,
+            Self::EaseInOut => Self::Spring,
+            Self::Spring => Self::Linear,
+        }
+    }
+
+    // Progress for `t` from 0 to 1 of the way through, exactly 0 and 1 at
+    // the ends so a rotation starts and stops without a jump
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            // a decaying oscillation, also scaled down by the time left so
+            // it's fully damped at the end: about 16% overshoot near t = 0.27
+            Self::Spring => 1.0 - (1.0 - t) * (-5.0 * t).exp() * (3.0 * std::f32::consts::PI * t).cos(),
+        }
+    }
+}
+
+impl std::str::FromStr for Easing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "ease-in-out" => Ok(Self::EaseInOut),
+            "spring" => Ok(Self::Spring),
+            _ => Err(format!("unknown easing `{s}`, expected linear, ease-in-out or spring")),
+        }
+    }
+}
+
+// A `rotate_to` in progress, timed on the simulation clock like `Shake`
+#[derive(Clone, Copy)]
+struct Rotation {
+    from: f32,
+    to: f32,
+    start: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+// A screen shake in progress, timed on the simulation clock so pausing
+// holds it and stepping replays it
+#[derive(Clone, Copy)]
+struct Shake {
+    intensity: f32,
+    duration: f32,
+    start: f32,
+    // the offset drawn for the frame at `drawn_at`, kept while time stands still
+    offset: [f32; 2],
+    drawn_at: f32,
+}
+
+fn valid_aspect(aspect: f32) -> bool {
+    aspect.is_finite() && aspect > 0.0
+}
+
+fn valid_line_dash((dash, gap): (f32, f32)) -> bool {
+    dash.is_finite() && dash > 0.0 && gap.is_finite() && gap >= 0.0
+}
+
+// Converts a position in pixels of a `width` x `height` target (origin
+// top-left, y down) to NDC (origin at the center, y up). The corners map to
+// ±1, so pixel centers land just inside them.
+fn pixel_to_ndc(x: f64, y: f64, width: u32, height: u32) -> [f32; 2] {
+    let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+    [(x / width * 2.0 - 1.0) as f32, (1.0 - y / height * 2.0) as f32]
+}
+
+// Half width and height of a region of `aspect` that's `extent` from the
+// center along its shorter axis
+fn half_size(extent: f32, aspect: f32) -> [f32; 2] {
+    if aspect >= 1.0 { [extent * aspect, extent] } else { [extent, extent / aspect] }
+}
+
+// Half width and height of the visible world region, and the viewport of a
+// `width` x `height` target it's drawn into (x, y, width, height in pixels).
+// Without a content aspect the region takes the target's, so every mode
+// shows the same thing: `extent` along the shorter axis and more along the
+// longer one.
+fn view_layout(extent: f32, content_aspect: Option<f32>, mode: ScaleMode, width: u32, height: u32) -> ([f32; 2], [f32; 4]) {
+    let (w, h) = (width.max(1) as f32, height.max(1) as f32);
+    let window = w / h;
+    let aspect = content_aspect.unwrap_or(window);
+    let content = half_size(extent, aspect);
+    let full = [0.0, 0.0, w, h];
+    match mode {
+        ScaleMode::Stretch => (content, full),
+        // whole pixels, so rounding never pushes the viewport off the target
+        ScaleMode::Fit if window > aspect => {
+            let fit = (h * aspect).round().clamp(1.0, w);
+            (content, [((w - fit) / 2.0).floor(), 0.0, fit, h])
+        }
+        ScaleMode::Fit => {
+            let fit = (w / aspect).round().clamp(1.0, h);
+            (content, [0.0, ((h - fit) / 2.0).floor(), w, fit])
+        }
+        ScaleMode::Fill if window > aspect => ([content[0], content[0] / window], full),
+        ScaleMode::Fill => ([content[1] * window, content[1]], full),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GradientUniform {
+    // rgb, padded to vec4 for uniform layout
+    inner: [f32; 4],
+    outer: [f32; 4],
+}
+
+impl GradientUniform {
+    fn new(inner: [f32; 3], outer: [f32; 3]) -> Self {
+        Self { inner: [inner[0], inner[1], inner[2], 0.0], outer: [outer[0], outer[1], outer[2], 0.0] }
+    }
+}
+
+/// Per-instance data for the instanced draw.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Instance {
+    offset: [f32; 2],
+    scale: f32,
+    // clip-space z before projection; 0 for everything but the translucent quads
+    depth: f32,
+    // added to the spin angle; 0 unless scattered
+    phase: f32,
+    // multiplies the instanced shape's colors; straight alpha, like vertex colors
+    tint: [f32; 4],
+}
+
+// Column-major orthographic projection of [l, r] x [b, t] onto NDC. z in
+// [-1, 1] maps to depth [0, 1], so the z = 0 plane sits mid-range and a depth
+// bias has room to move things either way.
+fn ortho(l: f32, r: f32, b: f32, t: f32) -> [[f32; 4]; 4] {
+    [
+        [2.0 / (r - l), 0.0, 0.0, 0.0],
+        [0.0, 2.0 / (t - b), 0.0, 0.0],
+        [0.0, 0.0, 0.5, 0.0],
+        [-(r + l) / (r - l), -(t + b) / (t - b), 0.5, 1.0],
+    ]
+}
+
+// Vertex buffer layouts: a single interleaved stream, or one stream per attribute
+const POSITION_ATTRIBUTE: wgpu::VertexAttribute = wgpu::VertexAttribute {
+    offset: 0,
+    shader_location: 0,
+    format: wgpu::VertexFormat::Float32x2,
+};
+const COLOR_ATTRIBUTE: wgpu::VertexAttribute = wgpu::VertexAttribute {
+    offset: 0,
+    shader_location: 1,
+    format: wgpu::VertexFormat::Float32x4,
+};
+const INTERLEAVED_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[
+        POSITION_ATTRIBUTE,
+        wgpu::VertexAttribute { offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress, ..COLOR_ATTRIBUTE },
+    ],
+};
+const POSITION_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[POSITION_ATTRIBUTE],
+};
+const COLOR_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[COLOR_ATTRIBUTE],
+};
+// The same with `VertexColors::Unorm8`
+const PACKED_COLOR_ATTRIBUTE: wgpu::VertexAttribute =
+    wgpu::VertexAttribute { format: wgpu::VertexFormat::Unorm8x4, ..COLOR_ATTRIBUTE };
+const PACKED_INTERLEAVED_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<PackedVertex>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[
+        POSITION_ATTRIBUTE,
+        wgpu::VertexAttribute { offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress, ..PACKED_COLOR_ATTRIBUTE },
+    ],
+};
+const PACKED_COLOR_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<[u8; 4]>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[PACKED_COLOR_ATTRIBUTE],
+};
+// Instance data follows the mesh's own vertex buffers
+const INSTANCE_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Instance,
+    attributes: &wgpu::vertex_attr_array![2 => Float32x2, 3 => Float32, 4 => Float32, 5 => Float32, 6 => Float32x4],
+};
+// Dashable lines, always interleaved
+const LINE_STREAM: wgpu::VertexBufferLayout = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4, 2 => Float32x2],
+};
+const INTERLEAVED_LAYOUT: [wgpu::VertexBufferLayout; 1] = [INTERLEAVED_STREAM];
+const LINE_LAYOUT: [wgpu::VertexBufferLayout; 1] = [LINE_STREAM];
+const SPLIT_LAYOUT: [wgpu::VertexBufferLayout; 2] = [POSITION_STREAM, COLOR_STREAM];
+const INTERLEAVED_INSTANCED_LAYOUT: [wgpu::VertexBufferLayout; 2] = [INTERLEAVED_STREAM, INSTANCE_STREAM];
+const SPLIT_INSTANCED_LAYOUT: [wgpu::VertexBufferLayout; 3] = [POSITION_STREAM, COLOR_STREAM, INSTANCE_STREAM];
+const PACKED_INTERLEAVED_LAYOUT: [wgpu::VertexBufferLayout; 1] = [PACKED_INTERLEAVED_STREAM];
+const PACKED_SPLIT_LAYOUT: [wgpu::VertexBufferLayout; 2] = [POSITION_STREAM, PACKED_COLOR_STREAM];
+const PACKED_INTERLEAVED_INSTANCED_LAYOUT: [wgpu::VertexBufferLayout; 2] = [PACKED_INTERLEAVED_STREAM, INSTANCE_STREAM];
+const PACKED_SPLIT_INSTANCED_LAYOUT: [wgpu::VertexBufferLayout; 3] = [POSITION_STREAM, PACKED_COLOR_STREAM, INSTANCE_STREAM];
+
+// Pulled meshes are still plain interleaved buffers to the pipelines that
+// don't pull
+fn vertex_buffer_layouts(layout: VertexLayout, colors: VertexColors) -> &'static [wgpu::VertexBufferLayout<'static>] {
+    match (layout, colors) {
+        (VertexLayout::Interleaved | VertexLayout::Pulled, VertexColors::Float) => &INTERLEAVED_LAYOUT,
+        (VertexLayout::Interleaved | VertexLayout::Pulled, VertexColors::Unorm8) => &PACKED_INTERLEAVED_LAYOUT,
+        (VertexLayout::Split, VertexColors::Float) => &SPLIT_LAYOUT,
+        (VertexLayout::Split, VertexColors::Unorm8) => &PACKED_SPLIT_LAYOUT,
+    }
+}
+
+fn instanced_buffer_layouts(layout: VertexLayout, colors: VertexColors) -> &'static [wgpu::VertexBufferLayout<'static>] {
+    match (layout, colors) {
+        (VertexLayout::Interleaved | VertexLayout::Pulled, VertexColors::Float) => &INTERLEAVED_INSTANCED_LAYOUT,
+        (VertexLayout::Interleaved | VertexLayout::Pulled, VertexColors::Unorm8) => &PACKED_INTERLEAVED_INSTANCED_LAYOUT,
+        (VertexLayout::Split, VertexColors::Float) => &SPLIT_INSTANCED_LAYOUT,
+        (VertexLayout::Split, VertexColors::Unorm8) => &PACKED_SPLIT_INSTANCED_LAYOUT,
+    }
+}
+
+// A mesh uploaded to the GPU
+struct GpuMesh {
+    vertex_buffers: VertexBuffers,
+    index_buffer: wgpu::Buffer,
+    // 16-bit unless there are more vertices than that reaches
+    index_format: wgpu::IndexFormat,
+    num_indices: u32,
+    num_vertices: u32,
+    colors: VertexColors,
+    // distance of the farthest vertex from the origin, for culling
+    radius: f32,
+}
+
+impl GpuMesh {
+    // `pull_bgl` is only needed for `VertexLayout::Pulled`, which always has
+    // float colors
+    fn new(
+        device: &wgpu::Device,
+        mesh: &Mesh,
+        layout: VertexLayout,
+        colors: VertexColors,
+        pull_bgl: Option<&wgpu::BindGroupLayout>,
+    ) -> Self {
+        let buffer = |label, contents: &[u8]| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            // COPY_DST so write_colors can patch them in place
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        let vertex_buffers = match (layout, colors) {
+            (VertexLayout::Interleaved, VertexColors::Float) => {
+                VertexBuffers::Interleaved(buffer("Vertex Buffer", bytemuck::cast_slice(&mesh.vertices)))
+            }
+            (VertexLayout::Interleaved, VertexColors::Unorm8) => {
+                let packed: Vec<_> = mesh
+                    .vertices
+                    .iter()
+                    .map(|v| PackedVertex { position: v.position, color: pack_color(v.color) })
+                    .collect();
+                VertexBuffers::Interleaved(buffer("Vertex Buffer", bytemuck::cast_slice(&packed)))
+            }
+            (VertexLayout::Split, colors) => {
+                let positions: Vec<_> = mesh.vertices.iter().map(|v| v.position).collect();
+                let color_buffer = match colors {
+                    VertexColors::Float => {
+                        let floats: Vec<_> = mesh.vertices.iter().map(|v| v.color).collect();
+                        buffer("Color Buffer", bytemuck::cast_slice(&floats))
+                    }
+                    VertexColors::Unorm8 => {
+                        let bytes: Vec<_> = mesh.vertices.iter().map(|v| pack_color(v.color)).collect();
+                        buffer("Color Buffer", bytemuck::cast_slice(&bytes))
+                    }
+                };
+                VertexBuffers::Split { positions: buffer("Position Buffer", bytemuck::cast_slice(&positions)), colors: color_buffer }
+            }
+            (VertexLayout::Pulled, _) => {
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Vertex Storage Buffer"),
+                    contents: bytemuck::cast_slice(&mesh.vertices),
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Vertex Storage BG"),
+                    layout: pull_bgl.expect("pulled meshes need the vertex storage layout"),
+                    entries: &[wgpu::BindGroupEntry { binding: 2, resource: buffer.as_entire_binding() }],
+                });
+                VertexBuffers::Pulled { buffer, bind_group }
+            }
+        };
+        let index_buffer = |contents: &[u8]| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents,
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let (index_buffer, index_format) = if mesh.vertices.len() <= u16::MAX as usize + 1 {
+            let short: Vec<u16> = mesh.indices.iter().map(|&i| i as u16).collect();
+            (index_buffer(bytemuck::cast_slice(&short)), wgpu::IndexFormat::Uint16)
+        } else {
+            (index_buffer(bytemuck::cast_slice(&mesh.indices)), wgpu::IndexFormat::Uint32)
+        };
+        let radius = mesh.vertices.iter().map(|v| v.position[0].hypot(v.position[1])).fold(0.0, f32::max);
+        Self {
+            vertex_buffers,
+            index_buffer,
+            index_format,
+            num_indices: mesh.indices.len() as u32,
+            num_vertices: mesh.vertices.len() as u32,
+            // pulled meshes are always uploaded as float `Vertex`es
+            colors: if layout == VertexLayout::Pulled { VertexColors::Float } else { colors },
+            radius,
+        }
+    }
+
+    // Overwrites each vertex's rgb where its layout keeps it, one small write
+    // per vertex since the colors sit between positions. Float alpha is left
+    // alone; packed colors share a 4-byte write, so they come out opaque.
+    fn write_colors(&self, queue: &wgpu::Queue, colors: &[[f32; 3]]) -> Result<(), GfxError> {
+        if colors.len() != self.num_vertices as usize {
+            return Err(GfxError::ColorCount { expected: self.num_vertices, got: colors.len() });
+        }
+        let (buffer, offset, stride) = match (&self.vertex_buffers, self.colors) {
+            (VertexBuffers::Interleaved(buffer) | VertexBuffers::Pulled { buffer, .. }, VertexColors::Float) => {
+                (buffer, std::mem::offset_of!(Vertex, color), std::mem::size_of::<Vertex>())
+            }
+            (VertexBuffers::Interleaved(buffer) | VertexBuffers::Pulled { buffer, .. }, VertexColors::Unorm8) => {
+                (buffer, std::mem::offset_of!(PackedVertex, color), std::mem::size_of::<PackedVertex>())
+            }
+            (VertexBuffers::Split { colors, .. }, VertexColors::Float) => (colors, 0, std::mem::size_of::<[f32; 4]>()),
+            (VertexBuffers::Split { colors, .. }, VertexColors::Unorm8) => (colors, 0, std::mem::size_of::<[u8; 4]>()),
+        };
+        for (i, &[r, g, b]) in colors.iter().enumerate() {
+            let at = (offset + i * stride) as wgpu::BufferAddress;
+            match self.colors {
+                VertexColors::Float => queue.write_buffer(buffer, at, bytemuck::cast_slice(&[r, g, b])),
+                VertexColors::Unorm8 => queue.write_buffer(buffer, at, &pack_color([r, g, b, 1.0])),
+            }
+        }
+        Ok(())
+    }
+
+    // Binds the vertex streams as `vertex_buffer_layouts` describes them,
+    // pulled meshes included
+    fn set_vertex_buffers(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        match &self.vertex_buffers {
+            VertexBuffers::Interleaved(buffer) | VertexBuffers::Pulled { buffer, .. } => {
+                render_pass.set_vertex_buffer(0, buffer.slice(..));
+            }
+            VertexBuffers::Split { positions, colors } => {
+                render_pass.set_vertex_buffer(0, positions.slice(..));
+                render_pass.set_vertex_buffer(1, colors.slice(..));
+            }
+        }
+    }
+
+    fn report_resources(&self, report: &mut ResourceReport) {
+        match &self.vertex_buffers {
+            VertexBuffers::Interleaved(buffer) | VertexBuffers::Pulled { buffer, .. } => {
+                report.buffer(ResourceKind::VertexBuffer, buffer);
+            }
+            VertexBuffers::Split { positions, colors } => {
+                report.buffer(ResourceKind::VertexBuffer, positions);
+                report.buffer(ResourceKind::VertexBuffer, colors);
+            }
+        }
+        report.buffer(ResourceKind::IndexBuffer, &self.index_buffer);
+    }
+
+    // for the built-in meshes that don't follow the configured layout
+    fn interleaved(device: &wgpu::Device, mesh: &Mesh) -> Self {
+        Self::new(device, mesh, VertexLayout::Interleaved, VertexColors::Float, None)
+    }
+}
+
+// A `LineMesh` uploaded to the GPU
+struct GpuLines {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+}
+
+impl GpuLines {
+    fn new(device: &wgpu::Device, lines: &LineMesh) -> Self {
+        let buffer = |label, contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: Some(label), contents, usage })
+        };
+        Self {
+            vertex_buffer: buffer("Line Vertex Buffer", bytemuck::cast_slice(&lines.vertices), wgpu::BufferUsages::VERTEX),
+            index_buffer: buffer("Line Index Buffer", bytemuck::cast_slice(&lines.indices), wgpu::BufferUsages::INDEX),
+            num_indices: lines.indices.len() as u32,
+        }
+    }
+
+    fn report_resources(&self, report: &mut ResourceReport) {
+        report.buffer(ResourceKind::VertexBuffer, &self.vertex_buffer);
+        report.buffer(ResourceKind::IndexBuffer, &self.index_buffer);
+    }
+}
+
+/// Options that have to be known before the device and surface exist, plus
+/// the starting values of runtime settings that `State::reset` goes back to.
+#[derive(Clone, Debug)]
+pub struct StateBuilder {
+    hdr: bool,
+    msaa_samples: u32,
+    vertex_layout: VertexLayout,
+    vertex_colors: VertexColors,
+    world_extent: f32,
+    content_aspect: Option<f32>,
+    scale_mode: ScaleMode,
+    time_scale: f32,
+    model: Option<PathBuf>,
+    clip_planes: Option<(f32, f32)>,
+    shader: Option<PathBuf>,
+    vertices: Option<PathBuf>,
+    adapter: Option<usize>,
+    // start angle and sweep in radians
+    pie_angles: (f32, f32),
+    pie_segments: u16,
+    max_frame_latency: u32,
+    max_frames_in_flight: u32,
+    frame_budget_ms: Option<f32>,
+    bloom: BloomSettings,
+    box_blur: BoxBlurSettings,
+    vignette: VignetteSettings,
+    lut: Option<PathBuf>,
+    color_grade: ColorGradeSettings,
+    dither: bool,
+    channel_mask: [bool; 4],
+    pixelation: Option<u32>,
+    triangle_debug_colors: bool,
+    show_grid: bool,
+    line_dash: Option<(f32, f32)>,
+    show_depth: bool,
+    depth_bias: wgpu::DepthBiasState,
+    depth_compare: wgpu::CompareFunction,
+    blend: wgpu::BlendState,
+    corner_radius: f32,
+    border: Option<(f32, [f32; 4])>,
+    back_face_color: Option<[f32; 4]>,
+    srgb_vertex_colors: bool,
+    cutout: bool,
+    alpha_to_coverage: bool,
+    spiral: Option<Spiral>,
+    debug_markers: bool,
+    angle_snap: Option<f32>,
+    easing: Easing,
+    clear_each_frame: bool,
+    clear_depth: bool,
+    clear_stencil: bool,
+    textured: bool,
+    mipmaps: bool,
+    sprite_images: Vec<PathBuf>,
+    filter: wgpu::FilterMode,
+    scatter: Option<(u32, u64)>,
+    sprites: bool,
+    sprite_animation: bool,
+    radial_gradient: Option<([f32; 3], [f32; 3])>,
+    checkerboard: Option<CheckerboardConfig>,
+    sky: Option<SkyConfig>,
+    gradient_stops: Vec<GradientStop>,
+    instancing: bool,
+    instance_culling: bool,
+    gpu_culling: bool,
+    translucent_quads: bool,
+    transparency_sort: bool,
+    ghost: Option<f32>,
+    wave: bool,
+    wave_subdivisions: u32,
+    sierpinski_depth: u32,
+    render_mode: RenderMode,
+}
+
+impl Default for StateBuilder {
+    fn default() -> Self {
+        Self {
+            hdr: false,
+            msaa_samples: 1,
+            vertex_layout: VertexLayout::default(),
+            vertex_colors: VertexColors::default(),
+            world_extent: 1.0,
+            content_aspect: None,
+            scale_mode: ScaleMode::default(),
+            time_scale: 1.0,
+            model: None,
+            clip_planes: None,
+            shader: None,
+            vertices: None,
+            adapter: None,
+            pie_angles: (0.0, 1.5 * std::f32::consts::PI),
+            pie_segments: 24,
+            max_frame_latency: 2,
+            max_frames_in_flight: 2,
+            frame_budget_ms: None,
+            bloom: BloomSettings::default(),
+            box_blur: BoxBlurSettings::default(),
+            vignette: VignetteSettings::default(),
+            lut: None,
+            color_grade: ColorGradeSettings::default(),
+            dither: false,
+            channel_mask: [true; 4],
+            pixelation: None,
+            triangle_debug_colors: false,
+            show_grid: false,
+            line_dash: None,
+            show_depth: false,
+            depth_bias: GRID_DEPTH_BIAS,
+            depth_compare: wgpu::CompareFunction::Less,
+            blend: wgpu::BlendState::ALPHA_BLENDING,
+            corner_radius: 0.0,
+            border: None,
+            back_face_color: None,
+            srgb_vertex_colors: false,
+            cutout: false,
+            alpha_to_coverage: false,
+            spiral: None,
+            debug_markers: cfg!(debug_assertions),
+            angle_snap: None,
+            easing: Easing::default(),
+            clear_each_frame: true,
+            clear_depth: true,
+            clear_stencil: true,
+            textured: false,
+            mipmaps: true,
+            sprite_images: Vec::new(),
+            filter: wgpu::FilterMode::Linear,
+            scatter: None,
+            sprites: false,
+            sprite_animation: true,
+            radial_gradient: None,
+            checkerboard: None,
+            sky: None,
+            gradient_stops: Vec::new(),
+            instancing: false,
+            instance_culling: true,
+            gpu_culling: false,
+            translucent_quads: false,
+            transparency_sort: true,
+            ghost: None,
+            wave: false,
+            wave_subdivisions: 32,
+            sierpinski_depth: 5,
+            render_mode: RenderMode::Graphics,
+        }
+    }
+}
+
+impl StateBuilder {
+    /// Request an extended-range float swapchain; falls back to SDR when the
+    /// surface doesn't offer one.
+    pub fn hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    /// Samples per pixel for the scene pass; 1 turns MSAA off. Counts the
+    /// device can't do for the scene and depth formats fall back to 1.
+    pub fn msaa(mut self, samples: u32) -> Self {
+        self.msaa_samples = samples.max(1);
+        self
+    }
+
+    pub fn vertex_layout(mut self, layout: VertexLayout) -> Self {
+        self.vertex_layout = layout;
+        self
+    }
+
+    pub fn vertex_colors(mut self, colors: VertexColors) -> Self {
+        self.vertex_colors = colors;
+        self
+    }
+
+    /// Half-size of the visible world region along the window's shorter
+    /// axis; geometry is authored in these units instead of NDC.
+    pub fn world_extent(mut self, extent: f32) -> Self {
+        self.world_extent = extent;
+        self
+    }
+
+    /// Width over height of the world region content is authored for; see
+    /// `State::set_content_aspect`.
+    pub fn content_aspect(mut self, aspect: Option<f32>) -> Self {
+        self.content_aspect = aspect;
+        self
+    }
+
+    pub fn scale_mode(mut self, mode: ScaleMode) -> Self {
+        self.scale_mode = mode;
+        self
+    }
+
+    /// The curve `State::rotate_to` follows.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// How fast the simulation clock runs against wall-clock time; see
+    /// `State::set_time_scale`.
+    pub fn time_scale(mut self, scale: f32) -> Self {
+        self.time_scale = scale;
+        self
+    }
+
+    /// A `.gltf`/`.glb` to draw in 3D instead of the 2D meshes. Like the LUT
+    /// and sprite images it's decoded after `build` returns; see
+    /// `State::loading`.
+    pub fn model(mut self, path: Option<PathBuf>) -> Self {
+        self.model = path;
+        self
+    }
+
+    /// Near and far planes for the model camera; see `State::set_clip_planes`.
+    pub fn clip_planes(mut self, planes: Option<(f32, f32)>) -> Self {
+        self.clip_planes = planes;
+        self
+    }
+
+    /// WGSL to read at startup in place of the embedded `shader.wgsl`,
+    /// relative to the working directory unless absolute. It needs every
+    /// entry point the 2D pipelines use and the `output_scale` override;
+    /// if it can't be read or doesn't validate, the embedded one is used.
+    pub fn shader(mut self, path: Option<PathBuf>) -> Self {
+        self.shader = path;
+        self
+    }
+
+    /// A LUT strip to grade the output through, with grading switched on;
+    /// see `State::set_lut`.
+    pub fn lut(mut self, path: Option<PathBuf>) -> Self {
+        self.color_grade.enabled |= path.is_some();
+        self.lut = path;
+        self
+    }
+
+    /// Images for the sprite array's layers, all the same size and format;
+    /// empty uses a generated animation.
+    pub fn sprite_images(mut self, paths: Vec<PathBuf>) -> Self {
+        self.sprite_images = paths;
+        self
+    }
+
+    /// Starts the instancing demo from `scatter_instances(count, seed)`
+    /// instead of the grid.
+    pub fn scatter(mut self, scatter: Option<(u32, u64)>) -> Self {
+        self.scatter = scatter;
+        self
+    }
+
+    /// A binary vertex dump (see `vertex_file`) added after the built-in meshes.
+    pub fn vertices(mut self, path: Option<PathBuf>) -> Self {
+        self.vertices = path;
+        self
+    }
+
+    /// Index into the adapter list logged at startup; `None` lets wgpu pick.
+    pub fn adapter(mut self, index: Option<usize>) -> Self {
+        self.adapter = index;
+        self
+    }
+
+    /// Warns about frames slower than this; see `State::set_frame_budget`.
+    pub fn frame_budget(mut self, ms: Option<f32>) -> Self {
+        self.frame_budget_ms = ms;
+        self
+    }
+
+    /// Starts with `spiral` drawn over the scene; see `State::set_spiral`.
+    pub fn spiral(mut self, spiral: Option<Spiral>) -> Self {
+        self.spiral = spiral;
+        self
+    }
+
+    /// Shape of the selectable pie slice, angles in radians; see `Mesh::pie`.
+    pub fn pie(mut self, start_angle: f32, sweep: f32, segments: u16) -> Self {
+        self.pie_angles = (start_angle, sweep);
+        self.pie_segments = segments.clamp(1, u16::MAX - 1);
+        self
+    }
+
+    /// See `State::set_clear_depth`.
+    pub fn clear_depth(mut self, clear: bool) -> Self {
+        self.clear_depth = clear;
+        self
+    }
+
+    /// Whether the scene pass clears stencil each frame; otherwise it keeps
+    /// the previous frame's, like `clear_depth`.
+    pub fn clear_stencil(mut self, clear: bool) -> Self {
+        self.clear_stencil = clear;
+        self
+    }
+
+    /// See `State::set_max_frame_latency`.
+    pub fn max_frame_latency(mut self, frames: u32) -> Self {
+        self.max_frame_latency = frames.clamp(MIN_FRAME_LATENCY, MAX_FRAME_LATENCY);
+        self
+    }
+
+    pub async fn build(self, window: Arc<Window>) -> Result<State, GfxError> {
+        State::new(window, self).await
+    }
+}
+
+/// Records a copy of `data` into `target` through the staging belt. Used for
+/// uploads that happen every frame, where `Queue::write_buffer` would grab
+/// fresh staging memory each time; the belt recycles its chunks instead.
+pub fn stage_write(
+    belt: &mut wgpu::util::StagingBelt,
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    target: &wgpu::Buffer,
+    data: &[u8],
+) {
+    let size = wgpu::BufferSize::new(data.len() as u64).expect("empty upload");
+    belt.write_buffer(encoder, target, 0, size, device).copy_from_slice(data);
+}
+
+/// Compiles WGSL inside a validation error scope, so a bad shader comes back
+/// as an error instead of hitting wgpu's uncaptured-error panic.
+pub fn create_shader_module(device: &wgpu::Device, label: &str, source: &str) -> Result<wgpu::ShaderModule, GfxError> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    // native backends resolve the scope immediately
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(e) => Err(GfxError::Shader { label: label.to_owned(), message: e.to_string() }),
+        None => Ok(module),
+    }
+}
+
+// Reads and compiles a WGSL file, labelled with where it came from
+fn load_shader(device: &wgpu::Device, path: &Path) -> Result<wgpu::ShaderModule, GfxError> {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+    let source = std::fs::read_to_string(&path).map_err(|error| GfxError::ShaderFile { path: path.clone(), error })?;
+    let module = create_shader_module(device, &path.display().to_string(), &source)?;
+    log::info!("using shader {}", path.display());
+    Ok(module)
+}
+
+// Backends tried one at a time when the WGPU_BACKEND set can't make a surface
+const FALLBACK_BACKENDS: [wgpu::Backends; 4] =
+    [wgpu::Backends::VULKAN, wgpu::Backends::METAL, wgpu::Backends::DX12, wgpu::Backends::GL];
+
+// An instance on the backends WGPU_BACKEND names (all of them by default)
+// and the window's surface from it. If that set can't make one, the other
+// backends get a try each before the first error is returned
+fn create_surface(window: &Arc<Window>) -> Result<(wgpu::Instance, wgpu::Surface<'static>), StateInitError> {
+    use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+
+    let descriptor = wgpu::InstanceDescriptor::from_env_or_default();
+    let tried = descriptor.backends;
+    let instance = wgpu::Instance::new(&descriptor);
+    let error = match instance.create_surface(window.clone()) {
+        Ok(surface) => return Ok((instance, surface)),
+        Err(e) => e,
+    };
+    log::warn!("couldn't create a surface on {tried:?}: {error}");
+    // the usual cause on unusual window systems; no backend gets past it
+    if let Err(e) = window.window_handle() {
+        log::error!("winit has no window handle to give: {e}");
+        return Err(StateInitError::Surface(error));
+    }
+    if let Err(e) = window.display_handle() {
+        log::error!("winit has no display handle to give: {e}");
+        return Err(StateInitError::Surface(error));
+    }
+    for backends in FALLBACK_BACKENDS.into_iter().filter(|backends| !tried.contains(*backends)) {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor { backends, ..descriptor.clone() });
+        match instance.create_surface(window.clone()) {
+            Ok(surface) => {
+                log::info!("falling back to {backends:?} for the surface");
+                return Ok((instance, surface));
+            }
+            Err(e) => log::warn!("couldn't create a surface on {backends:?} either: {e}"),
+        }
+    }
+    Err(StateInitError::Surface(error))
+}
+
+pub struct State {
+    surface: wgpu::Surface<'static>,
+    device:  wgpu::Device,
+    queue:   wgpu::Queue,
+    config:  wgpu::SurfaceConfiguration,
+    // what the surface can be configured with, for `set_surface_format`
+    surface_formats: Vec<wgpu::TextureFormat>,
+    pipeline_context: PipelineContext,
+    // what `pipelines` was last built with
+    pipeline_params: PipelineParams,
+    pipelines: Pipelines,
+    // only sampled through the bind groups; held for `resource_report`
+    checkerboard_texture: Texture,
+    // indexed by `filter_index`, then by `mipmaps`: base level only, then the full chain
+    texture_bind_groups: [[wgpu::BindGroup; 2]; 2],
+    textured: bool,
+    mipmaps: bool,
+    // indexed like the first level of `texture_bind_groups`
+    sprite_bind_groups: [wgpu::BindGroup; 2],
+    // for rebuilding `sprite_bind_groups` when the sprite images load
+    sprite_bgl: wgpu::BindGroupLayout,
+    filter: wgpu::FilterMode,
+    sprite_sheet: TextureArray,
+    // where the post chain's LUT came from, `None` for the identity one
+    lut_path: Option<PathBuf>,
+    // the preset last saved or loaded, which the next one comes after
+    preset: Option<String>,
+    // the files given at startup that are still decoding
+    assets: AssetLoader,
+    // shown while not animating
+    sprite_layer: u32,
+    sprites: bool,
+    sprite_animation: bool,
+    gradient_buffer: wgpu::Buffer,
+    // inner and outer color; `None` draws vertex colors
+    radial_gradient: Option<([f32; 3], [f32; 3])>,
+    // draws the current mesh as an alpha-tested cutout
+    cutout: bool,
+    rounded_rect_buffer: wgpu::Buffer,
+    // world units; 0 draws the square with sharp corners
+    corner_radius: f32,
+    // width and straight RGBA of the square's inset outline
+    border: Option<(f32, [f32; 4])>,
+    // set while the plain shapes show their back faces instead of culling them
+    back_face_color: Option<[f32; 4]>,
+    back_face_buffer: wgpu::Buffer,
+    // alpha of the copy drawn at last frame's angle; `None` draws no ghost
+    ghost: Option<f32>,
+    // the angle uploaded last frame
+    prev_angle: f32,
+    // drawn instead of the current mesh while `wave` is set
+    wave_mesh: GpuMesh,
+    wave: bool,
+    wave_subdivisions: u32,
+    // every instance; the visible ones are uploaded each frame
+    instances: Vec<Instance>,
+    instance_buffer: wgpu::Buffer,
+    // what the instances were scattered with; `None` is the regular grid
+    scatter_seed: Option<u64>,
+    instancing: bool,
+    instance_culling: bool,
+    visible_instances: u32,
+    // the instances again, culled in a compute pass while `gpu_culling` is
+    // set; `None` where the device can't run that or draw indirectly
+    gpu_cull: Option<GpuCull>,
+    gpu_culling: bool,
+    translucent_mesh: GpuMesh,
+    translucent_buffer: wgpu::Buffer,
+    translucent_quads: bool,
+    transparency_sort: bool,
+    // this frame's `push_quad`s, as unindexed triangles
+    quad_vertices: Vec<Vertex>,
+    quad_buffer: wgpu::Buffer,
+    debug_line_vertices: Vec<LineVertex>,
+    debug_line_buffer: wgpu::Buffer,
+    grid: GpuLines,
+    show_grid: bool,
+    // dash and gap in pixels for the grid and debug lines
+    line_dash: Option<(f32, f32)>,
+    line_dash_buffer: wgpu::Buffer,
+    // the depth buffer in grayscale instead of the post chain's output
+    show_depth: bool,
+    // the MSAA coverage heatmap instead, below the depth view
+    msaa_debug: bool,
+    // drawn over the shapes while set
+    spiral: Option<Spiral>,
+    spiral_buffer: wgpu::Buffer,
+    spiral_points: u32,
+    // drawn instead of the current mesh while set
+    scene: Option<SceneNode>,
+    // this frame's nodes as (mesh, world transform), in node buffer order
+    scene_nodes: Vec<(usize, Affine2)>,
+    node_buffer: wgpu::Buffer,
+    node_bind_group: wgpu::BindGroup,
+    // bytes between node slots, rounded up to the offset alignment
+    node_stride: u32,
+    post: PostProcess,
+    background: Background,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    model: Option<ModelRenderer>,
+    // handed to the model camera once it's loaded
+    clip_planes: Option<(f32, f32)>,
+    meshes: Vec<GpuMesh>,
+    // `meshes` with baked triangle colors, on devices without `primitive_index`
+    triangle_id_meshes: Option<Vec<GpuMesh>>,
+    triangle_debug_colors: bool,
+    current_mesh: usize,
+    sierpinski_depth: u32,
+    // for re-uploading the Sierpinski mesh when its depth changes
+    pull_bgl: wgpu::BindGroupLayout,
+    angle_buffer: wgpu::Buffer,
+    // what `angle_buffer` holds, so an unchanged frame skips the write;
+    // None once something else has written it
+    angle_uploaded: Option<AngleUniform>,
+    projection_buffer: wgpu::Buffer,
+    world_extent: f32,
+    content_aspect: Option<f32>,
+    scale_mode: ScaleMode,
+    shake: Option<Shake>,
+    shake_rng: SplitMix64,
+    // the view offset the projection buffer holds
+    shake_offset: [f32; 2],
+    // set together by `set_shared_bind_groups`
+    frame_bind_group: wgpu::BindGroup,
+    view_bind_group: wgpu::BindGroup,
+    // simulation clock, only advanced by `update`/`step`
+    time: f32,
+    // simulation seconds per wall-clock second in `update`
+    time_scale: f32,
+    // display the angle rounded to multiples of this many radians
+    angle_snap: Option<f32>,
+    // the spin is the clock plus this, so it can carry on from a held angle
+    spin_offset: f32,
+    rotation: Option<Rotation>,
+    // where the last `rotate_to` stopped; the spin waits there until resumed
+    held_angle: Option<f32>,
+    easing: Easing,
+    last_frame: Instant,
+    paused: bool,
+    frame_count: u64,
+    debug_markers: bool,
+    // while set, the scene isn't redrawn and the last one shows under a banner
+    error: Option<String>,
+    // when false the scene target keeps last frame's pixels, leaving trails;
+    // it's offscreen, which is what makes loading it legal on every backend
+    clear_each_frame: bool,
+    // cleared independently of color and of each other; loading keeps last
+    // frame's values, so multi-pass effects can reuse them
+    clear_depth: bool,
+    clear_stencil: bool,
+    // the scene target is new (startup/resize) and has to be cleared once anyway
+    scene_needs_clear: bool,
+    // when the last input arrived, cleared once a frame reflecting it is presented
+    input_at: Option<Instant>,
+    // submissions the GPU may still be working on, oldest first
+    in_flight: VecDeque<wgpu::SubmissionIndex>,
+    max_frames_in_flight: u32,
+    frame_budget_ms: Option<f32>,
+    // frames over the budget since the last warning, which was at `budget_warned_at`
+    frames_over_budget: u32,
+    budget_warned_at: Option<Instant>,
+    // per-frame uniform uploads; finished in `encode_frame`, recalled after each submit
+    belt: wgpu::util::StagingBelt,
+    capabilities: Capabilities,
+    render_mode: RenderMode,
+    // `None` on devices without compute shaders
+    compute: Option<SpinCompute>,
+    // dispatches since `compute_stats_at`, logged about once a second
+    compute_dispatches: u32,
+    compute_stats_at: Instant,
+    // taken out while they run, so they can borrow the state they're given
+    update_callback: Option<UpdateCallback>,
+    render_callback: Option<RenderCallback>,
+    // what `reset` restores
+    defaults: StateBuilder,
+}
+
+impl State {
+    async fn new(window: Arc<Window>, options: StateBuilder) -> Result<Self, GfxError> {
+        // creating instance and surface
+        let (instance, surface) = create_surface(&window)?;
+        // list every adapter so one can be picked with --adapter
+        let mut adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        for (index, adapter) in adapters.iter().enumerate() {
+            let info = adapter.get_info();
+            log::info!(
+                "adapter {index}: {} ({:?}, {:?}){}",
+                info.name,
+                info.device_type,
+                info.backend,
+                if adapter.is_surface_supported(&surface) { "" } else { ", can't present to this window" }
+            );
+        }
+        let chosen = match options.adapter {
+            Some(index) if index >= adapters.len() => {
+                log::warn!("no adapter {index} (have {}), using the default", adapters.len());
+                None
+            }
+            Some(index) if !adapters[index].is_surface_supported(&surface) => {
+                log::warn!("adapter {index} can't present to this window, using the default");
+                None
+            }
+            Some(index) => Some(adapters.swap_remove(index)),
+            None => None,
+        };
+        // get an adapter using adapteroptions
+        let adapter = match chosen {
+            Some(adapter) => adapter,
+            None => instance
+            .request_adapter(
+                &wgpu::RequestAdapterOptions {
+                    compatible_surface: Some(&surface),
+                    ..Default::default()
+                }
+            ).await.map_err(StateInitError::Adapter)?,
+        };
+        log::info!("using adapter {}", adapter.get_info().name);
+        let capabilities = Capabilities::new(&adapter);
+        log::info!("{capabilities}");
+
+        // everything the adapter offers, rather than the defaults, so large
+        // offscreen targets only fail where the hardware can't do them; the
+        // optional features are only the ones it actually has
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: capabilities.required_features(),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            }
+        ).await.map_err(StateInitError::Device)?;
+        let limits = device.limits();
+        log::info!(
+            "device limits: {}px textures, {} byte buffers",
+            limits.max_texture_dimension_2d, limits.max_buffer_size
+        );
+        // configure the surface
+        let formats = surface.get_capabilities(&adapter).formats;
+        let hdr_format = formats.iter().copied().find(|f| *f == wgpu::TextureFormat::Rgba16Float);
+        let format = match (options.hdr, hdr_format) {
+            (true, Some(format)) => {
+                log::info!("using HDR swapchain format {format:?}");
+                format
+            }
+            (true, None) => {
+                log::warn!("no HDR surface format available (have {formats:?}), falling back to SDR");
+                formats[0]
+            }
+            (false, _) => formats[0],
+        };
+        let output_scale = if format == wgpu::TextureFormat::Rgba16Float { HDR_OUTPUT_SCALE } else { 1.0 };
+
+        let size = window.inner_size();
+        let (width, height) = clamp_texture_size(&device, "surface", size.width, size.height);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: options.max_frame_latency,
+        };
+        surface.configure(&device, &config);
+
+        let depth_format = depth_format(&capabilities);
+        // 4x is always allowed; other counts depend on adapter-specific format features
+        let samples = options.msaa_samples;
+        let sample_count_supported = |format| adapter.get_texture_format_features(format).flags.sample_count_supported(samples);
+        let sample_count = if samples != 1
+            && samples != 4
+            && !capabilities.require(Capability::AdapterSpecificSampleCounts, "rendering without MSAA")
+        {
+            1
+        } else if samples == 1 || sample_count_supported(post::SCENE_FORMAT) && sample_count_supported(depth_format) {
+            samples
+        } else {
+            log::warn!("{samples}x MSAA isn't supported for the scene, rendering without it");
+            1
+        };
+
+        // the scene renders offscreen, resolving there with MSAA on; post
+        // passes turn it into the surface image
+        let mut post = PostProcess::new(&device, &queue, config.format, width, height, sample_count)?;
+        post.set_bloom(&queue, options.bloom);
+        post.set_box_blur(&queue, options.box_blur);
+        post.set_vignette(&queue, options.vignette);
+        post.set_color_grade(&queue, options.color_grade);
+        post.set_dither(&queue, options.dither);
+        post.set_channel_mask(&queue, options.channel_mask);
+        post.set_pixelation(&queue, options.pixelation);
+        // files are decoded on other threads and swapped in as they finish,
+        // so the window can start drawing straight away
+        let mut assets = AssetLoader::default();
+        if let Some(path) = &options.lut {
+            assets.lut(path.clone());
+        }
+        if let Some(path) = &options.model {
+            assets.model(path.clone());
+        }
+        if !options.sprite_images.is_empty() {
+            assets.sprites(options.sprite_images.clone());
+        }
+        let (depth_texture, depth_view) = create_depth_target(&device, depth_format, width, height, sample_count);
+        post.set_depth_source(&device, &depth_texture);
+        let mut background = Background::new(&device, post::SCENE_FORMAT, depth_format, sample_count, output_scale)?;
+        background.resize(&queue, width, height);
+        background.set_checkerboard(&queue, options.checkerboard);
+        background.set_sky(&queue, options.sky);
+        background.set_gradient(&queue, &options.gradient_stops)?;
+        let compute = if capabilities.supports(Capability::ComputeShaders) {
+            Some(SpinCompute::new(&device)?)
+        } else {
+            None
+        };
+        let render_mode = match options.render_mode {
+            RenderMode::ComputeOnly if !capabilities.require(Capability::ComputeShaders, "rendering normally") => RenderMode::Graphics,
+            mode => mode,
+        };
+
+        // Vertex pulling reads a storage buffer from the vertex stage, which
+        // downlevel (e.g. GLES) devices may not allow
+        let vertex_layout = match options.vertex_layout {
+            VertexLayout::Pulled if !capabilities.require(Capability::VertexStorage, "interleaved vertex buffers") => VertexLayout::Interleaved,
+            layout => layout,
+        };
+        let vertex_colors = match (vertex_layout, options.vertex_colors) {
+            (VertexLayout::Pulled, VertexColors::Unorm8) => {
+                log::warn!("pulled vertices are read as raw floats, keeping float colors");
+                VertexColors::Float
+            }
+            (_, colors) => colors,
+        };
+        let layouts = SceneBindGroupLayouts::new(&device);
+
+        // Preload the selectable meshes, wound to match the pipeline's front face
+        let mut meshes = vec![
+            ("square".to_owned(), Mesh::square()),
+            ("triangle".to_owned(), Mesh::ngon(3, 0.6)),
+            ("hexagon".to_owned(), Mesh::ngon(6, 0.6)),
+            ("32-gon".to_owned(), Mesh::ngon(32, 0.6)),
+            ("pie".to_owned(), Mesh::pie(options.pie_angles.0, options.pie_angles.1, options.pie_segments, 0.6)),
+        ];
+        let sierpinski_depth = options.sierpinski_depth.min(MAX_SIERPINSKI_DEPTH);
+        meshes.push(("sierpinski".to_owned(), Mesh::sierpinski(sierpinski_depth, 0.6)));
+        debug_assert_eq!(meshes[SIERPINSKI_MESH].0, "sierpinski");
+        if let Some(path) = &options.vertices {
+            match load_vertices_bin(path) {
+                Ok((data, stride)) => match Mesh::from_floats(&data, stride) {
+                    Some(mesh) => {
+                        log::info!("loaded {} ({} vertices)", path.display(), mesh.vertices.len());
+                        meshes.push((path.display().to_string(), mesh));
+                    }
+                    None => log::error!(
+                        "{}: need 6 floats per vertex (x, y, r, g, b, a), or 5 without alpha, and a whole number of triangles, got stride {stride} and {} floats",
+                        path.display(),
+                        data.len()
+                    ),
+                },
+                Err(e) => log::error!("{}: {e}", path.display()),
+            }
+        }
+        let color_bytes = match vertex_colors {
+            VertexColors::Float => std::mem::size_of::<[f32; 4]>(),
+            VertexColors::Unorm8 => std::mem::size_of::<[u8; 4]>(),
+        };
+        let vertex_count: usize = meshes.iter().map(|(_, mesh)| mesh.vertices.len()).sum();
+        log::info!(
+            "{vertex_count} mesh vertices, {} bytes of vertex data with {vertex_colors:?} colors",
+            vertex_count * (std::mem::size_of::<[f32; 2]>() + color_bytes)
+        );
+        for (name, mesh) in &mut meshes {
+            // keep the slot, so the keys still line up with the mesh names
+            if let Err(e) = mesh.validate() {
+                log::warn!("{name}: {e}; drawing the square in its place");
+                *mesh = Mesh::square();
+            }
+            check_winding(name, mesh);
+        }
+        let meshes: Vec<Mesh> = meshes.into_iter().map(|(_, mesh)| mesh.with_winding(FRONT_FACE)).collect();
+        let upload = |mesh: &Mesh| GpuMesh::new(&device, mesh, vertex_layout, vertex_colors, Some(&layouts.pull));
+        let triangle_id_meshes = (!capabilities.supports(Capability::PrimitiveIndex))
+            .then(|| meshes.iter().map(|mesh| upload(&mesh.with_triangle_colors())).collect());
+        let meshes = meshes.iter().map(upload).collect();
+
+        // init angle: 
+        let angle_init = AngleUniform::zeroed();
+
+        // Create angle buffer
+        let angle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Angle UBO"),
+            contents: bytemuck::bytes_of(&angle_init),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create projection buffer, rewritten on resize
+        let content_aspect = options.content_aspect.filter(|aspect| valid_aspect(*aspect));
+        let (half_size, viewport) = view_layout(options.world_extent, content_aspect, options.scale_mode, width, height);
+        let projection_init = ProjectionUniform::new(half_size, [0.0; 2]);
+        let projection_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Projection UBO"),
+            contents: bytemuck::bytes_of(&projection_init),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // The line dash pattern, and the viewport size it's measured in
+        let line_dash = options.line_dash.filter(|&dash| valid_line_dash(dash));
+        let line_dash_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Line Dash UBO"),
+            contents: bytemuck::bytes_of(&LineDashUniform::new(line_dash, viewport)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Radial gradient colors, only read by the gradient fragment shader
+        let (inner, outer) = options.radial_gradient.unwrap_or_default();
+        let gradient_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Gradient UBO"),
+            contents: bytemuck::bytes_of(&GradientUniform::new(inner, outer)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Rounded corners and the border for the square, only read by the
+        // rounded-rect fragment shader
+        let corner_radius = options.corner_radius.clamp(0.0, max_corner_radius());
+        let border = options.border.map(clamp_border);
+        let rounded_rect_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rounded Rect UBO"),
+            contents: bytemuck::bytes_of(&RoundedRectUniform::new(corner_radius, border)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // What back faces are filled with, where the plain shapes show them
+        let back_face_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Back Face UBO"),
+            contents: bytemuck::bytes_of(&BackFaceUniform { color: options.back_face_color.unwrap_or_default() }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let frame_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Per-Frame BG"),
+            layout: &layouts.frame,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: angle_buffer.as_entire_binding() }],
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("View BG"),
+            layout: &layouts.view,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: projection_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: gradient_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: rounded_rect_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: back_face_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: line_dash_buffer.as_entire_binding() },
+            ],
+        });
+
+        let embedded_shader = || create_shader_module(&device, "Shader", include_str!("shader.wgsl"));
+        let shader = match &options.shader {
+            Some(path) => load_shader(&device, path).or_else(|e| {
+                log::error!("{e}; using the embedded shader");
+                embedded_shader()
+            })?,
+            None => embedded_shader()?,
+        };
+        // without the feature the colors are baked into copies of the meshes
+        let triangle_id_shader = capabilities
+            .supports(Capability::PrimitiveIndex)
+            .then(|| create_shader_module(&device, "Triangle ID Shader", include_str!("triangle_ids.wgsl")))
+            .transpose()?;
+
+        // Checkerboard for the textured mode; deliberately not a power of two
+        let checkerboard = Texture::checkerboard(&device, &queue, 384, 240, 8)?;
+        let texture_bind_groups = TEXTURE_FILTERS.map(|filter| [false, true].map(|mipmaps| {
+            let sampler = texture::create_sampler(&device, filter, mipmaps);
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Texture BG"),
+                layout: &layouts.texture,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&checkerboard.view) },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&sampler) },
+                ],
+            })
+        }));
+        // The sprite array takes the texture's place in group 2, with the
+        // built-in frames until any images given have loaded
+        let sprite_sheet = TextureArray::sprite_frames(&device, &queue)?;
+        let sprite_bind_groups = sprite_bind_groups(&device, &layouts.sprite, &sprite_sheet);
+        // Scene node transforms, one aligned slot per node picked by dynamic offset
+        let node_stride = (std::mem::size_of::<NodeUniform>() as u32)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment);
+        let node_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scene Node UBO"),
+            size: (node_stride as usize * MAX_SCENE_NODES) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let node_size = wgpu::BufferSize::new(std::mem::size_of::<NodeUniform>() as u64);
+        let node_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Node BG"),
+            layout: &layouts.node,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding { buffer: &node_buffer, offset: 0, size: node_size }),
+            }],
+        });
+        let mut pipeline_context =
+            PipelineContext::new(&device, &layouts, shader, triangle_id_shader, vertex_layout, vertex_colors, output_scale);
+        let pipeline_params = PipelineParams {
+            sample_count,
+            depth_format,
+            depth_compare: options.depth_compare,
+            grid_bias: options.depth_bias,
+            blend: options.blend,
+            srgb_vertex_colors: options.srgb_vertex_colors,
+            alpha_to_coverage: options.alpha_to_coverage && sample_count > 1,
+        };
+        // a shader from disk might lack an entry point or the override, which
+        // only shows up once pipelines are built from it
+        let pipelines = if let Some(path) = &options.shader {
+            match pipeline_context.build_checked(&device, &pipeline_params, &path.display().to_string()) {
+                Ok(pipelines) => pipelines,
+                Err(e) => {
+                    log::error!("{e}; using the embedded shader");
+                    pipeline_context.shader = embedded_shader()?;
+                    pipeline_context.build(&device, &pipeline_params)
+                }
+            }
+        } else {
+            pipeline_context.build(&device, &pipeline_params)
+        };
+        let wave_subdivisions = options.wave_subdivisions.clamp(1, MAX_WAVE_SUBDIVISIONS);
+        let wave_mesh = build_wave_mesh(&device, wave_subdivisions, vertex_colors);
+
+        let instances = match options.scatter {
+            Some((count, seed)) => scattered_instances(count.min(MAX_SCATTERED_INSTANCES), seed),
+            None => instance_grid(),
+        };
+        let instance_buffer = create_instance_buffer(&device, instances.len());
+        let gpu_cull = if capabilities.supports(Capability::ComputeShaders) && capabilities.supports(Capability::IndirectDraw) {
+            Some(GpuCull::new(&device, &instances)?)
+        } else {
+            None
+        };
+
+        // Overlapping translucent squares in front of the shapes
+        let translucent_square = Mesh::square().with_alpha(TRANSLUCENT_ALPHA).with_winding(FRONT_FACE);
+        let translucent_mesh = GpuMesh::interleaved(&device, &translucent_square);
+        let translucent_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Translucent Instance Buffer"),
+            size: std::mem::size_of::<[Instance; TRANSLUCENT_QUADS.len()]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Immediate-mode quads, refilled every frame
+        let quad_buffer = create_quad_buffer(&device, QUAD_BATCH_CAPACITY);
+        let debug_line_buffer = create_debug_line_buffer(&device, DEBUG_LINE_CAPACITY);
+
+        // Spiral line strip, rewritten whenever its parameters change
+        let spiral = options.spiral.map(clamp_spiral);
+        let spiral_vertices = spiral.map(|spiral| spiral.vertices()).unwrap_or_default();
+        let spiral_buffer = create_spiral_buffer(&device, spiral_vertices.len());
+        queue.write_buffer(&spiral_buffer, 0, bytemuck::cast_slice(&spiral_vertices));
+
+        // Grid overlay, coplanar with the shapes and biased in front of them
+        let grid = GpuLines::new(&device, &LineMesh::grid(GRID_HALF_EXTENT, GRID_SPACING, GRID_LINE_WIDTH, [0.6, 0.6, 0.6]));
+
+        Ok(Self {
+            surface,
+            device,
+            queue,
+            config,
+            surface_formats: formats,
+            pipeline_context,
+            pipeline_params,
+            pipelines,
+            checkerboard_texture: checkerboard,
+            texture_bind_groups,
+            textured: options.textured,
+            mipmaps: options.mipmaps,
+            sprite_bind_groups,
+            sprite_bgl: layouts.sprite,
+            filter: options.filter,
+            sprite_sheet,
+            lut_path: None,
+            preset: None,
+            assets,
+            sprite_layer: 0,
+            sprites: options.sprites,
+            sprite_animation: options.sprite_animation,
+            gradient_buffer,
+            radial_gradient: options.radial_gradient,
+            cutout: options.cutout,
+            rounded_rect_buffer,
+            corner_radius,
+            border,
+            back_face_color: options.back_face_color,
+            back_face_buffer,
+            ghost: options.ghost,
+            prev_angle: 0.0,
+            wave_mesh,
+            wave: options.wave,
+            wave_subdivisions,
+            instances,
+            instance_buffer,
+            scatter_seed: options.scatter.map(|(_, seed)| seed),
+            instancing: options.instancing,
+            instance_culling: options.instance_culling,
+            visible_instances: 0,
+            gpu_cull,
+            gpu_culling: options.gpu_culling,
+            translucent_mesh,
+            translucent_buffer,
+            translucent_quads: options.translucent_quads,
+            transparency_sort: options.transparency_sort,
+            quad_vertices: Vec::with_capacity(QUAD_BATCH_CAPACITY * VERTICES_PER_QUAD),
+            quad_buffer,
+            debug_line_vertices: Vec::with_capacity(DEBUG_LINE_CAPACITY * VERTICES_PER_LINE),
+            debug_line_buffer,
+            grid,
+            show_grid: options.show_grid,
+            line_dash,
+            line_dash_buffer,
+            show_depth: options.show_depth,
+            msaa_debug: false,
+            spiral,
+            spiral_buffer,
+            spiral_points: spiral_vertices.len() as u32,
+            scene: None,
+            scene_nodes: Vec::new(),
+            node_buffer,
+            node_bind_group,
+            node_stride,
+            post,
+            background,
+            depth_texture,
+            depth_view,
+            model: None,
+            clip_planes: options.clip_planes,
+            meshes,
+            triangle_id_meshes,
+            triangle_debug_colors: options.triangle_debug_colors,
+            current_mesh: 0,
+            sierpinski_depth,
+            pull_bgl: layouts.pull,
+            angle_buffer,
+            angle_uploaded: Some(angle_init),
+            projection_buffer,
+            world_extent: options.world_extent,
+            content_aspect,
+            scale_mode: options.scale_mode,
+            shake: None,
+            shake_rng: SplitMix64::new(SHAKE_SEED),
+            shake_offset: [0.0; 2],
+            frame_bind_group,
+            view_bind_group,
+            time: 0.0,
+            time_scale: clamp_time_scale(options.time_scale),
+            angle_snap: options.angle_snap,
+            spin_offset: 0.0,
+            rotation: None,
+            held_angle: None,
+            easing: options.easing,
+            last_frame: Instant::now(),
+            paused: false,
+            frame_count: 0,
+            debug_markers: options.debug_markers,
+            error: None,
+            clear_each_frame: options.clear_each_frame,
+            clear_depth: options.clear_depth,
+            clear_stencil: options.clear_stencil,
+            scene_needs_clear: true,
+            input_at: None,
+            in_flight: VecDeque::new(),
+            max_frames_in_flight: options.max_frames_in_flight,
+            frame_budget_ms: options.frame_budget_ms,
+            frames_over_budget: 0,
+            budget_warned_at: None,
+            belt: wgpu::util::StagingBelt::new(UPLOAD_CHUNK_SIZE),
+            capabilities,
+            render_mode,
+            compute,
+            compute_dispatches: 0,
+            compute_stats_at: Instant::now(),
+            update_callback: None,
+            render_callback: None,
+            defaults: options,
+        })
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        // a window spanning several large monitors can outgrow the device
+        let (width, height) = clamp_texture_size(&self.device, "surface", width, height);
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(&self.device, &self.config);
+        self.resize_targets(width, height);
+        if let Some(message) = &self.error {
+            self.post.set_error_text(&self.queue, message, width);
+        }
+
+        self.shake_offset = [0.0; 2];
+        self.write_projection();
+    }
+
+    // The projection for the current view settings and shake offset, and
+    // the viewport size the line dashes are measured in
+    fn write_projection(&self) {
+        let (half_size, _) = self.view_layout_for_target();
+        let projection = ProjectionUniform::new(half_size, self.shake_offset);
+        self.queue.write_buffer(&self.projection_buffer, 0, bytemuck::bytes_of(&projection));
+        self.write_line_dash();
+    }
+
+    fn view_layout(&self, width: u32, height: u32) -> ([f32; 2], [f32; 4]) {
+        view_layout(self.world_extent, self.content_aspect, self.scale_mode, width, height)
+    }
+
+    // The layout for whatever the offscreen targets are sized for: the
+    // window, or the texture `render_to` was last given
+    fn view_layout_for_target(&self) -> ([f32; 2], [f32; 4]) {
+        self.view_layout(self.depth_texture.width(), self.depth_texture.height())
+    }
+
+    /// Gives the world region a fixed width-over-height `aspect`, `extent`
+    /// units from the center along its shorter axis, for content authored
+    /// at that shape; how it meets the window is `set_scale_mode`'s. `None`
+    /// follows the window's aspect instead, so no mode crops or letterboxes.
+    /// Aspects that aren't positive and finite are ignored.
+    pub fn set_content_aspect(&mut self, aspect: Option<f32>) {
+        if aspect.is_some_and(|aspect| !valid_aspect(aspect)) {
+            log::warn!("ignoring content aspect {aspect:?}, expected a positive width over height");
+            return;
+        }
+        self.content_aspect = aspect;
+        self.scene_needs_clear = true;
+        self.write_projection();
+    }
+
+    pub fn content_aspect(&self) -> Option<f32> {
+        self.content_aspect
+    }
+
+    /// Letterboxes, crops or stretches the world region into the window;
+    /// see `ScaleMode`.
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        self.scale_mode = mode;
+        self.scene_needs_clear = true;
+        self.write_projection();
+    }
+
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.scale_mode
+    }
+
+    /// Shakes the 2D view for `duration` seconds of simulation time: each
+    /// frame it's moved by a fresh random offset of up to `intensity` world
+    /// units along each axis, the bound easing out to zero by the end.
+    /// Offsets don't add up, so it always settles exactly where it started.
+    /// A new shake replaces one in progress; a non-positive intensity or
+    /// duration stops it. The offsets come from a fixed seed, so the same
+    /// shakes on the same frames come out the same.
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+        self.shake = (intensity > 0.0 && duration > 0.0).then_some(Shake {
+            intensity,
+            duration,
+            start: self.time,
+            offset: [0.0; 2],
+            drawn_at: f32::NAN,
+        });
+    }
+
+    // Draws this frame's shake offset and uploads the projection if it moved
+    fn update_shake(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let offset = match &mut self.shake {
+            // running the clock back past its start ends it too
+            Some(shake) if !(0.0..shake.duration).contains(&(self.time - shake.start)) => {
+                self.shake = None;
+                [0.0; 2]
+            }
+            Some(shake) => {
+                if shake.drawn_at != self.time {
+                    let remaining = 1.0 - (self.time - shake.start) / shake.duration;
+                    let bound = shake.intensity * remaining * remaining;
+                    shake.offset = [self.shake_rng.range(-bound, bound), self.shake_rng.range(-bound, bound)];
+                    shake.drawn_at = self.time;
+                }
+                shake.offset
+            }
+            None => [0.0; 2],
+        };
+        if offset != self.shake_offset {
+            let (half_size, _) = self.view_layout_for_target();
+            let projection = ProjectionUniform::new(half_size, offset);
+            stage_write(&mut self.belt, encoder, &self.device, &self.projection_buffer, bytemuck::bytes_of(&projection));
+            self.shake_offset = offset;
+        }
+    }
+
+    /// Sets how many frames the presentation engine may queue ahead of the
+    /// display, then reconfigures the surface.
+    ///
+    /// 1 gives the lowest input-to-photon latency but the CPU and GPU stop
+    /// overlapping, so a slow frame costs a missed vsync. Higher values smooth
+    /// out throughput at the price of a frame of latency each. Clamped to 1..=3.
+    pub fn set_max_frame_latency(&mut self, frames: u32) {
+        let frames = frames.clamp(MIN_FRAME_LATENCY, MAX_FRAME_LATENCY);
+        log::info!("max frame latency {} -> {}", self.config.desired_maximum_frame_latency, frames);
+        self.config.desired_maximum_frame_latency = frames;
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    pub fn max_frame_latency(&self) -> u32 {
+        self.config.desired_maximum_frame_latency
+    }
+
+    /// Caps how many submitted frames the GPU may be behind by; `render`
+    /// blocks on the oldest one once the cap is reached, before acquiring the
+    /// next surface texture.
+    ///
+    /// This gates the CPU directly, so unlike the surface's frame latency it
+    /// holds on every backend. 1 keeps input as fresh as possible but leaves
+    /// the CPU idle while the GPU works; more overlaps the two. Clamped to 1..=4.
+    pub fn set_max_frames_in_flight(&mut self, frames: u32) {
+        let frames = frames.clamp(1, MAX_FRAMES_IN_FLIGHT);
+        log::info!("max frames in flight {} -> {}", self.max_frames_in_flight, frames);
+        self.max_frames_in_flight = frames;
+    }
+
+    pub fn max_frames_in_flight(&self) -> u32 {
+        self.max_frames_in_flight
+    }
+
+    /// Logs a warning when a frame takes longer than `ms` milliseconds, at
+    /// most once a second. What counts is the CPU time spent recording,
+    /// submitting and presenting it, or the time spent waiting for the GPU
+    /// to free a frame in flight, whichever is longer; waiting for the
+    /// surface texture doesn't count, since vsync alone would blow most
+    /// budgets. `None` stops checking.
+    pub fn set_frame_budget(&mut self, ms: Option<f32>) {
+        self.frame_budget_ms = ms;
+        self.frames_over_budget = 0;
+        self.budget_warned_at = None;
+    }
+
+    pub fn frame_budget(&self) -> Option<f32> {
+        self.frame_budget_ms
+    }
+
+    fn check_frame_budget(&mut self, cpu: Duration, gpu_wait: Duration) {
+        let Some(budget) = self.frame_budget_ms else { return };
+        let (cpu_ms, gpu_ms) = (cpu.as_secs_f32() * 1000.0, gpu_wait.as_secs_f32() * 1000.0);
+        if cpu_ms.max(gpu_ms) <= budget {
+            return;
+        }
+        self.frames_over_budget += 1;
+        if self.budget_warned_at.is_some_and(|at| at.elapsed() < FRAME_BUDGET_WARNING_INTERVAL) {
+            return;
+        }
+        log::warn!(
+            "frame over the {budget:.1} ms budget: {cpu_ms:.1} ms on the CPU, {gpu_ms:.1} ms waiting on the GPU ({} over since the last warning)",
+            self.frames_over_budget
+        );
+        self.frames_over_budget = 0;
+        self.budget_warned_at = Some(Instant::now());
+    }
+
+    // Blocks until fewer than `max_frames_in_flight` submissions are pending,
+    // returning how long that took
+    fn wait_for_frames_in_flight(&mut self) -> Duration {
+        let start = Instant::now();
+        while self.in_flight.len() >= self.max_frames_in_flight as usize {
+            let oldest = self.in_flight.pop_front().unwrap();
+            if let Err(e) = self.device.poll(wgpu::PollType::WaitForSubmissionIndex(oldest)) {
+                log::warn!("waiting for an in-flight frame failed: {e}");
+            }
+        }
+        start.elapsed()
+    }
+
+    /// Waits for every submitted frame to finish on the GPU, so nothing is
+    /// still in flight when the state is dropped at exit.
+    pub fn shutdown(&mut self) {
+        self.in_flight.clear();
+        if let Err(e) = self.device.poll(wgpu::PollType::Wait) {
+            log::warn!("waiting for the GPU to finish failed: {e}");
+        }
+        log::info!("rendered {} frames", self.frame_count);
+    }
+
+    /// Converts a position in physical window pixels (origin top-left, y down,
+    /// as winit reports the cursor) to NDC (origin at the center, y up); see
+    /// the free `pixel_to_ndc`.
+    pub fn pixel_to_ndc(&self, x: f64, y: f64) -> [f32; 2] {
+        pixel_to_ndc(x, y, self.config.width, self.config.height)
+    }
+
+    /// Turns the glTF model's orbit camera by a drag of `dx`, `dy` pixels:
+    /// the model follows the cursor sideways, and dragging down looks from
+    /// higher up. Does nothing without a model.
+    pub fn orbit_camera(&mut self, dx: f32, dy: f32) {
+        if let Some(model) = &mut self.model {
+            model.camera_mut().orbit(-dx * ORBIT_SPEED, dy * ORBIT_SPEED);
+        }
+    }
+
+    /// Moves the orbit camera toward the model for positive `lines` of
+    /// scrolling, away for negative.
+    pub fn zoom_camera(&mut self, lines: f32) {
+        if let Some(model) = &mut self.model {
+            model.camera_mut().zoom(ZOOM_PER_LINE.powf(lines));
+        }
+    }
+
+    /// Vertices in the shape being drawn, which is how many colors
+    /// `set_vertex_colors` takes.
+    pub fn vertex_count(&self) -> u32 {
+        self.meshes[self.current_mesh].num_vertices
+    }
+
+    /// Recolors the current shape in place, one rgb per vertex. Nothing is
+    /// regenerated, so the colors last until the meshes are next rebuilt
+    /// (a layout switch or reset).
+    pub fn set_vertex_colors(&mut self, colors: &[[f32; 3]]) -> Result<(), GfxError> {
+        self.meshes[self.current_mesh].write_colors(&self.queue, colors)
+    }
+
+    /// Fixes the model camera's near and far planes, which otherwise hug the
+    /// model wherever the camera goes; `None` goes back to that. Planes that
+    /// aren't `0 < near < far` are refused. A model that hasn't loaded yet
+    /// takes them up when it does.
+    pub fn set_clip_planes(&mut self, planes: Option<(f32, f32)>) -> Result<(), ClipPlaneError> {
+        match (&mut self.model, planes) {
+            (Some(model), _) => {
+                let camera = model.camera_mut();
+                camera.set_clip_planes(planes)?;
+                log::info!("{}", camera.camera().depth_precision());
+            }
+            // checked the same way with nothing to apply them to yet
+            (None, Some((near, far))) => Camera::framing(glam::Vec3::ZERO, 1.0).set_clip_planes(near, far)?,
+            (None, None) => {}
+        }
+        self.clip_planes = planes;
+        Ok(())
+    }
+
+    /// Flips the model camera between perspective and orthographic.
+    pub fn toggle_projection(&mut self) {
+        if let Some(model) = &mut self.model {
+            let camera = model.camera_mut();
+            camera.toggle_projection();
+            log::info!("projection: {:?}", camera.projection);
+        }
+    }
+
+    /// Pushes the glTF model's triangles apart along their face normals, by
+    /// `amount` times its bounding radius, clamped to 0..=1. The 2D shapes
+    /// are flat and share vertices between triangles, so there's nothing to
+    /// explode without a model.
+    pub fn set_explode(&mut self, amount: f32) {
+        match &mut self.model {
+            Some(model) => model.set_explode(amount.clamp(0.0, MAX_EXPLODE)),
+            None => log::info!("the exploded view needs a model (--model)"),
+        }
+    }
+
+    pub fn explode(&self) -> f32 {
+        self.model.as_ref().map_or(0.0, ModelRenderer::explode)
+    }
+
+    /// Drags the orbit camera's target along with the cursor.
+    pub fn pan_camera(&mut self, dx: f32, dy: f32) {
+        let height = self.config.height;
+        if let Some(model) = &mut self.model {
+            model.camera_mut().pan(dx, dy, height);
+        }
+    }
+
+    /// Records that input just arrived; the next present logs how long it
+    /// took to get on screen.
+    pub fn note_input(&mut self) {
+        self.input_at.get_or_insert_with(Instant::now);
+    }
+
+    pub fn bloom(&self) -> BloomSettings {
+        self.post.bloom()
+    }
+
+    /// Applies new bloom settings; values are clamped to usable ranges.
+    pub fn set_bloom(&mut self, mut bloom: BloomSettings) {
+        bloom.threshold = bloom.threshold.max(0.0);
+        bloom.intensity = bloom.intensity.max(0.0);
+        bloom.radius = bloom.radius.clamp(0.0, MAX_BLOOM_RADIUS);
+        self.post.set_bloom(&self.queue, bloom);
+    }
+
+    pub fn box_blur(&self) -> BoxBlurSettings {
+        self.post.box_blur()
+    }
+
+    /// Applies new box blur settings, clamping the radius to 0..=16 and the
+    /// iterations to 0..=8.
+    pub fn set_box_blur(&mut self, mut box_blur: BoxBlurSettings) {
+        box_blur.radius = box_blur.radius.min(MAX_BOX_BLUR_RADIUS);
+        box_blur.iterations = box_blur.iterations.min(MAX_BOX_BLUR_ITERATIONS);
+        self.post.set_box_blur(&self.queue, box_blur);
+    }
+
+    pub fn vignette(&self) -> VignetteSettings {
+        self.post.vignette()
+    }
+
+    /// Applies new vignette settings, clamping the intensity to 0..=1 and
+    /// keeping the radius non-negative.
+    pub fn set_vignette(&mut self, mut vignette: VignetteSettings) {
+        vignette.intensity = vignette.intensity.clamp(0.0, 1.0);
+        vignette.radius = vignette.radius.max(0.0);
+        self.post.set_vignette(&self.queue, vignette);
+    }
+
+    pub fn color_grade(&self) -> ColorGradeSettings {
+        self.post.color_grade()
+    }
+
+    /// Applies new color grade settings, clamping the intensity to 0..=1.
+    pub fn set_color_grade(&mut self, mut color_grade: ColorGradeSettings) {
+        color_grade.intensity = color_grade.intensity.clamp(0.0, 1.0);
+        self.post.set_color_grade(&self.queue, color_grade);
+    }
+
+    pub fn dither(&self) -> bool {
+        self.post.dither()
+    }
+
+    /// Dithers the final output to hide banding in smooth gradients; see
+    /// `PostProcess::set_dither`.
+    pub fn set_dither(&mut self, dither: bool) {
+        self.post.set_dither(&self.queue, dither);
+    }
+
+    pub fn channel_mask(&self) -> [bool; 4] {
+        self.post.channel_mask()
+    }
+
+    /// Shows only the output channels (red, green, blue, alpha) that are
+    /// set in `mask`; see `PostProcess::set_channel_mask`.
+    pub fn set_channel_mask(&mut self, mask: [bool; 4]) {
+        self.post.set_channel_mask(&self.queue, mask);
+    }
+
+    pub fn pixelation(&self) -> Option<u32> {
+        self.post.pixelation()
+    }
+
+    /// Draws the output as blocks of `pixelation` by `pixelation` pixels,
+    /// each one flat in the color of the scene at its middle; see
+    /// `PostProcess::set_pixelation`. Block sizes are clamped to 64, and
+    /// 0 or 1 turns it off like `None`.
+    pub fn set_pixelation(&mut self, pixelation: Option<u32>) {
+        let pixelation = pixelation.filter(|&size| size > 1).map(|size| size.min(MAX_PIXELATION));
+        self.post.set_pixelation(&self.queue, pixelation);
+    }
+
+    /// Loads a LUT strip (see `Lut`) for the color grade to remap through,
+    /// or goes back to one that maps every color to itself. The grade's
+    /// settings stay as they are. On error the old LUT is kept.
+    pub fn set_lut(&mut self, path: Option<&Path>) -> Result<(), GfxError> {
+        let lut = match path {
+            Some(path) => {
+                let lut = Lut::load(&self.device, &self.queue, path)?;
+                log::info!("loaded a {0}x{0}x{0} LUT from {1}", lut.size, path.display());
+                Some(lut)
+            }
+            None => None,
+        };
+        self.post.set_lut(&self.device, &self.queue, lut);
+        self.lut_path = path.map(Path::to_owned);
+        Ok(())
+    }
+
+    /// Where the current LUT was loaded from.
+    pub fn lut(&self) -> Option<&Path> {
+        self.lut_path.as_deref()
+    }
+
+    pub fn toggle_grid(&mut self) {
+        self.show_grid = !self.show_grid;
+    }
+
+    /// Dashes the grid and debug lines: `dash` pixels drawn, then `gap`
+    /// pixels skipped, from the start of each line. Pixels are the render
+    /// target's, so the pattern keeps its spacing however long a line is
+    /// or whatever the window's size; `None` draws them solid.
+    pub fn set_line_dash(&mut self, dash: Option<(f32, f32)>) {
+        if dash.is_some_and(|dash| !valid_line_dash(dash)) {
+            log::warn!("ignoring line dash {dash:?}, expected a positive dash and a gap of 0 or more");
+            return;
+        }
+        self.line_dash = dash;
+        self.write_line_dash();
+    }
+
+    pub fn line_dash(&self) -> Option<(f32, f32)> {
+        self.line_dash
+    }
+
+    fn write_line_dash(&self) {
+        let (_, viewport) = self.view_layout_for_target();
+        let uniform = LineDashUniform::new(self.line_dash, viewport);
+        self.queue.write_buffer(&self.line_dash_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Shows the depth buffer instead of the scene, black at the near plane
+    /// and white at the far one. Perspective depth is linearized with the
+    /// model camera's planes first, so the grays are even in distance.
+    pub fn toggle_depth_view(&mut self) {
+        self.show_depth = !self.show_depth;
+        log::info!("depth view {}", if self.show_depth { "on" } else { "off" });
+    }
+
+    pub fn msaa_debug(&self) -> bool {
+        self.msaa_debug
+    }
+
+    /// Shows which pixels MSAA splits instead of the scene: edge pixels
+    /// whose samples disagree are colored from blue (one sample differs) to
+    /// red (all do), over the fully covered ones in dim gray. Without MSAA
+    /// every pixel is a single sample, so it stays off and this logs why.
+    /// The depth view takes precedence while both are on.
+    pub fn set_msaa_debug(&mut self, enabled: bool) {
+        if enabled && self.post.sample_count() == 1 {
+            log::warn!("the MSAA coverage view needs MSAA; run with --msaa 4");
+            return;
+        }
+        self.msaa_debug = enabled;
+    }
+
+    /// Draws `spiral` over the shapes as a line strip, or with `None` stops.
+    /// Its point count is clamped to 2..=65536 and turns to positive values.
+    pub fn set_spiral(&mut self, spiral: Option<Spiral>) {
+        let spiral = spiral.map(clamp_spiral);
+        if let Some(spiral) = spiral {
+            let vertices = spiral.vertices();
+            if self.spiral_buffer.size() < std::mem::size_of_val(vertices.as_slice()) as wgpu::BufferAddress {
+                self.spiral_buffer = create_spiral_buffer(&self.device, vertices.len());
+            }
+            self.queue.write_buffer(&self.spiral_buffer, 0, bytemuck::cast_slice(&vertices));
+            self.spiral_points = vertices.len() as u32;
+        }
+        self.spiral = spiral;
+    }
+
+    pub fn spiral(&self) -> Option<Spiral> {
+        self.spiral
+    }
+
+    pub fn depth_bias(&self) -> wgpu::DepthBiasState {
+        self.pipeline_params.grid_bias
+    }
+
+    /// Sets the grid overlay's depth bias and rebuilds the pipelines. Negative
+    /// values pull it toward the camera; `constant` is in depth-format units,
+    /// `slope_scale` scales with the surface's depth slope.
+    pub fn set_depth_bias(&mut self, constant: i32, slope_scale: f32) {
+        let grid_bias = wgpu::DepthBiasState { constant, slope_scale, clamp: 0.0 };
+        self.rebuild_pipelines(PipelineParams { grid_bias, ..self.pipeline_params });
+        log::info!("grid depth bias: constant {constant}, slope scale {slope_scale}");
+    }
+
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        self.pipeline_params.depth_compare
+    }
+
+    /// Rebuilds the 2D pipelines with a new depth test. `Greater` and
+    /// `GreaterEqual` are treated as reverse-Z: depth clears to 0 instead of
+    /// 1 and the grid's bias flips sign so it still lands in front. The glTF
+    /// model follows with `Greater` and its camera's clip planes swapped,
+    /// and `Less` otherwise.
+    pub fn set_depth_compare(&mut self, compare: wgpu::CompareFunction) {
+        self.rebuild_pipelines(PipelineParams { depth_compare: compare, ..self.pipeline_params });
+        log::info!("depth compare {compare:?}");
+    }
+
+    pub fn blend(&self) -> wgpu::BlendState {
+        self.pipeline_params.blend
+    }
+
+    /// Rebuilds the pipelines with `blend` for everything translucent: the
+    /// translucent squares, the ghost and pushed quads. Color and alpha are
+    /// blended separately; see `ADDITIVE_BLEND` and friends for presets.
+    /// Opaque shapes still replace what's behind them.
+    pub fn set_blend(&mut self, blend: wgpu::BlendState) {
+        self.rebuild_pipelines(PipelineParams { blend, ..self.pipeline_params });
+        log::info!("blend: color {:?}, alpha {:?}", blend.color, blend.alpha);
+    }
+
+    /// Draws the current mesh cut out to a leaf shape by its alpha, the way
+    /// foliage is drawn; see `set_alpha_to_coverage` for its edges.
+    pub fn toggle_cutout(&mut self) {
+        self.cutout = !self.cutout;
+    }
+
+    pub fn alpha_to_coverage(&self) -> bool {
+        self.pipeline_params.alpha_to_coverage
+    }
+
+    /// With MSAA on, turns the cutout's alpha into per-sample coverage so
+    /// its edges come out smooth instead of stair-stepped; without MSAA
+    /// there are no samples to spread it over, so it stays off and this
+    /// logs why. Rebuilds the pipelines.
+    pub fn set_alpha_to_coverage(&mut self, enabled: bool) {
+        if enabled && self.pipeline_params.sample_count == 1 {
+            log::warn!("alpha-to-coverage needs MSAA; run with --msaa 4");
+            return;
+        }
+        self.rebuild_pipelines(PipelineParams { alpha_to_coverage: enabled, ..self.pipeline_params });
+    }
+
+    pub fn vertex_colors_srgb(&self) -> bool {
+        self.pipeline_params.srgb_vertex_colors
+    }
+
+    /// Treats vertex colors as sRGB-encoded, decoding them to linear in the
+    /// vertex shader so they're interpolated and blended in linear space the
+    /// way texture colors are; off, they're taken as linear already. This
+    /// covers the meshes drawn in their own colors: the translucent quads,
+    /// pushed quads, grid, wave and scene nodes. Output is encoded back to
+    /// sRGB by the surface when its format is an sRGB one. Rebuilds the
+    /// pipelines.
+    pub fn set_vertex_colors_srgb(&mut self, srgb: bool) {
+        self.rebuild_pipelines(PipelineParams { srgb_vertex_colors: srgb, ..self.pipeline_params });
+        if srgb && !self.config.format.is_srgb() {
+            log::warn!("surface format {:?} isn't sRGB, so the linear output isn't encoded back", self.config.format);
+        }
+    }
+
+    // Every 2D pipeline for `params`, from the shader and layouts made at startup
+    fn build_pipelines(&self, params: &PipelineParams) -> Pipelines {
+        self.pipeline_context.build(&self.device, params)
+    }
+
+    // Swaps in pipelines built for `params`; the one path every
+    // pipeline-affecting setting goes through
+    fn rebuild_pipelines(&mut self, params: PipelineParams) {
+        if let Some(model) = &mut self.model
+            && model.camera().reverse_z != params.reverse_z()
+        {
+            model.set_reverse_z(params.reverse_z());
+            log::info!("{}", model.camera().camera().depth_precision());
+        }
+        self.pipelines = self.build_pipelines(&params);
+        self.pipeline_params = params;
+    }
+
+    /// Puts every runtime setting back to the value the state was built with
+    /// and restarts the animation from the first mesh.
+    pub fn reset(&mut self) {
+        let defaults = self.defaults.clone();
+        self.time = 0.0;
+        self.paused = false;
+        self.set_time_scale(defaults.time_scale);
+        self.shake = None;
+        self.debug_lines_clear();
+        self.set_content_aspect(defaults.content_aspect);
+        self.set_scale_mode(defaults.scale_mode);
+        self.current_mesh = 0;
+        self.set_max_frame_latency(defaults.max_frame_latency);
+        self.set_max_frames_in_flight(defaults.max_frames_in_flight);
+        self.set_bloom(defaults.bloom);
+        self.set_box_blur(defaults.box_blur);
+        self.set_vignette(defaults.vignette);
+        self.set_color_grade(defaults.color_grade);
+        self.set_dither(defaults.dither);
+        self.set_channel_mask(defaults.channel_mask);
+        self.set_pixelation(defaults.pixelation);
+        self.triangle_debug_colors = defaults.triangle_debug_colors;
+        self.show_grid = defaults.show_grid;
+        self.set_line_dash(defaults.line_dash);
+        self.show_depth = defaults.show_depth;
+        self.msaa_debug = false;
+        self.set_spiral(defaults.spiral);
+        self.rebuild_pipelines(PipelineParams {
+            depth_compare: defaults.depth_compare,
+            grid_bias: defaults.depth_bias,
+            blend: defaults.blend,
+            srgb_vertex_colors: defaults.srgb_vertex_colors,
+            alpha_to_coverage: defaults.alpha_to_coverage && self.pipeline_params.sample_count > 1,
+            ..self.pipeline_params
+        });
+        if let Err(e) = self.set_clip_planes(defaults.clip_planes) {
+            log::error!("{e}");
+        }
+        self.debug_markers = defaults.debug_markers;
+        self.angle_snap = defaults.angle_snap;
+        (self.spin_offset, self.rotation, self.held_angle) = (0.0, None, None);
+        self.easing = defaults.easing;
+        self.clear_each_frame = defaults.clear_each_frame;
+        self.clear_depth = defaults.clear_depth;
+        self.clear_stencil = defaults.clear_stencil;
+        self.textured = defaults.textured;
+        self.mipmaps = defaults.mipmaps;
+        self.sprites = defaults.sprites;
+        self.filter = defaults.filter;
+        self.sprite_animation = defaults.sprite_animation;
+        self.sprite_layer = 0;
+        self.set_radial_gradient(defaults.radial_gradient);
+        self.set_corner_radius(defaults.corner_radius);
+        self.set_border(defaults.border);
+        self.set_back_face_color(defaults.back_face_color);
+        self.cutout = defaults.cutout;
+        self.set_checkerboard(defaults.checkerboard);
+        self.set_sky(defaults.sky);
+        if let Err(e) = self.set_gradient_stops(&defaults.gradient_stops) {
+            log::error!("{e}");
+        }
+        match defaults.scatter {
+            Some((count, seed)) => self.scatter_instances(count, seed),
+            None => {
+                self.set_instances(instance_grid());
+                self.scatter_seed = None;
+            }
+        }
+        self.instancing = defaults.instancing;
+        self.instance_culling = defaults.instance_culling;
+        self.gpu_culling = defaults.gpu_culling;
+        self.translucent_quads = defaults.translucent_quads;
+        self.transparency_sort = defaults.transparency_sort;
+        self.ghost = defaults.ghost;
+        self.wave = defaults.wave;
+        self.set_wave_subdivisions(defaults.wave_subdivisions);
+        self.upload_sierpinski(defaults.sierpinski_depth);
+        self.set_render_mode(defaults.render_mode);
+        log::info!("reset runtime settings to defaults");
+    }
+
+    /// The runtime settings a preset holds, as they are now.
+    pub fn settings(&self) -> Settings {
+        let params = &self.pipeline_params;
+        Settings {
+            scale_mode: self.scale_mode,
+            content_aspect: self.content_aspect,
+            bloom: self.bloom(),
+            box_blur: self.box_blur(),
+            vignette: self.vignette(),
+            color_grade: self.color_grade(),
+            lut: self.lut_path.clone(),
+            dither: self.dither(),
+            channel_mask: self.channel_mask(),
+            pixelation: self.pixelation(),
+            show_grid: self.show_grid,
+            line_dash: self.line_dash,
+            show_depth: self.show_depth,
+            msaa_debug: self.msaa_debug,
+            spiral: self.spiral,
+            depth_compare: params.depth_compare,
+            depth_bias: (params.grid_bias.constant, params.grid_bias.slope_scale),
+            blend: params.blend,
+            alpha_to_coverage: params.alpha_to_coverage,
+            srgb_vertex_colors: params.srgb_vertex_colors,
+            angle_snap: self.angle_snap,
+            clear_each_frame: self.clear_each_frame,
+            clear_depth: self.clear_depth,
+            textured: self.textured,
+            mipmaps: self.mipmaps,
+            sprites: self.sprites,
+            filter: self.filter,
+            radial_gradient: self.radial_gradient,
+            corner_radius: self.corner_radius,
+            border: self.border,
+            back_face_color: self.back_face_color,
+            triangle_debug_colors: self.triangle_debug_colors,
+            cutout: self.cutout,
+            checkerboard: self.checkerboard(),
+            sky: self.sky(),
+            gradient_stops: self.gradient_stops().to_vec(),
+            instancing: self.instancing,
+            instance_culling: self.instance_culling,
+            gpu_culling: self.gpu_culling,
+            translucent_quads: self.translucent_quads,
+            transparency_sort: self.transparency_sort,
+            ghost: self.ghost,
+            wave: self.wave,
+            wave_subdivisions: self.wave_subdivisions,
+            sierpinski_depth: self.sierpinski_depth,
+        }
+    }
+
+    /// Switches every setting to the one in `settings`. The pipelines are
+    /// rebuilt and the LUT and meshes reloaded only where those changed;
+    /// the animation carries on from where it was.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.set_content_aspect(settings.content_aspect);
+        self.set_scale_mode(settings.scale_mode);
+        self.set_bloom(settings.bloom);
+        self.set_box_blur(settings.box_blur);
+        self.set_vignette(settings.vignette);
+        self.set_color_grade(settings.color_grade);
+        if settings.lut != self.lut_path
+            && let Err(e) = self.set_lut(settings.lut.as_deref())
+        {
+            log::error!("{e}");
+        }
+        self.set_dither(settings.dither);
+        self.set_channel_mask(settings.channel_mask);
+        self.set_pixelation(settings.pixelation);
+        self.show_grid = settings.show_grid;
+        self.set_line_dash(settings.line_dash);
+        self.show_depth = settings.show_depth;
+        self.set_msaa_debug(settings.msaa_debug);
+        self.set_spiral(settings.spiral);
+        let (constant, slope_scale) = settings.depth_bias;
+        let params = PipelineParams {
+            depth_compare: settings.depth_compare,
+            grid_bias: wgpu::DepthBiasState { constant, slope_scale, clamp: 0.0 },
+            blend: settings.blend,
+            srgb_vertex_colors: settings.srgb_vertex_colors,
+            alpha_to_coverage: settings.alpha_to_coverage && self.pipeline_params.sample_count > 1,
+            ..self.pipeline_params
+        };
+        if params != self.pipeline_params {
+            self.rebuild_pipelines(params);
+        }
+        self.set_angle_snap(settings.angle_snap);
+        self.clear_each_frame = settings.clear_each_frame;
+        self.clear_depth = settings.clear_depth;
+        self.textured = settings.textured;
+        self.mipmaps = settings.mipmaps;
+        self.sprites = settings.sprites;
+        self.filter = settings.filter;
+        self.set_radial_gradient(settings.radial_gradient);
+        self.set_corner_radius(settings.corner_radius);
+        self.set_border(settings.border);
+        self.set_back_face_color(settings.back_face_color);
+        self.triangle_debug_colors = settings.triangle_debug_colors;
+        self.cutout = settings.cutout;
+        self.set_checkerboard(settings.checkerboard);
+        self.set_sky(settings.sky);
+        if let Err(e) = self.set_gradient_stops(&settings.gradient_stops) {
+            log::error!("{e}");
+        }
+        self.instancing = settings.instancing;
+        self.instance_culling = settings.instance_culling;
+        self.gpu_culling = settings.gpu_culling;
+        self.translucent_quads = settings.translucent_quads;
+        self.transparency_sort = settings.transparency_sort;
+        self.set_ghost(settings.ghost);
+        self.wave = settings.wave;
+        self.set_wave_subdivisions(settings.wave_subdivisions);
+        self.upload_sierpinski(settings.sierpinski_depth);
+    }
+
+    /// Writes the current settings to `presets/<name>.json`, making the
+    /// directory if needed, and returns the file's path.
+    pub fn save_preset(&mut self, name: &str) -> Result<PathBuf, PresetError> {
+        let path = preset::path(name)?;
+        std::fs::create_dir_all(preset::PRESET_DIR)?;
+        std::fs::write(&path, self.settings().to_json()?)?;
+        self.preset = Some(name.to_owned());
+        Ok(path)
+    }
+
+    /// Reads `presets/<name>.json` over the current settings and applies
+    /// the result, so settings the file leaves out stay as they are. Nothing
+    /// changes if the file can't be read or parsed.
+    pub fn load_preset(&mut self, name: &str) -> Result<(), PresetError> {
+        let text = std::fs::read_to_string(preset::path(name)?)?;
+        let mut settings = self.settings();
+        settings.read(&text)?;
+        self.apply_settings(&settings);
+        self.preset = Some(name.to_owned());
+        Ok(())
+    }
+
+    /// What the uniforms are computed from as of the last frame drawn; see
+    /// `snapshot`.
+    pub fn uniform_snapshot(&self) -> UniformSnapshot {
+        let camera = self.model.as_ref().map(|model| {
+            let camera = model.camera();
+            CameraSnapshot {
+                target: camera.target.to_array(),
+                distance: camera.distance,
+                yaw: camera.yaw,
+                pitch: camera.pitch,
+                projection: camera.projection,
+                clip_planes: camera.clip_planes(),
+            }
+        });
+        UniformSnapshot {
+            time: self.time,
+            angle: self.display_angle(),
+            prev_angle: self.prev_angle,
+            spin_held: self.spin_held(),
+            paused: self.paused,
+            time_scale: self.time_scale,
+            content_aspect: self.content_aspect,
+            scale_mode: self.scale_mode,
+            radial_gradient: self.radial_gradient,
+            corner_radius: self.corner_radius,
+            border: self.border,
+            line_dash: self.line_dash,
+            back_face_color: self.back_face_color,
+            ghost: self.ghost,
+            camera,
+        }
+    }
+
+    /// Puts every value in `snapshot` back and rewrites the buffers they
+    /// feed. A rotation or shake in progress is dropped, and a spinning
+    /// angle spins on from the snapshot's.
+    pub fn apply_uniform_snapshot(&mut self, snapshot: &UniformSnapshot) {
+        self.time = snapshot.time;
+        self.paused = snapshot.paused;
+        self.set_time_scale(snapshot.time_scale);
+        (self.rotation, self.held_angle) = (None, snapshot.spin_held.then_some(snapshot.angle));
+        self.spin_offset = snapshot.angle - snapshot.time;
+        self.prev_angle = snapshot.prev_angle;
+        self.shake = None;
+        self.set_content_aspect(snapshot.content_aspect);
+        self.set_scale_mode(snapshot.scale_mode);
+        self.set_radial_gradient(snapshot.radial_gradient);
+        self.set_corner_radius(snapshot.corner_radius);
+        self.set_border(snapshot.border);
+        self.set_line_dash(snapshot.line_dash);
+        self.set_back_face_color(snapshot.back_face_color);
+        self.set_ghost(snapshot.ghost);
+        match (&mut self.model, snapshot.camera) {
+            (Some(model), Some(saved)) => {
+                let camera = model.camera_mut();
+                camera.target = saved.target.into();
+                camera.distance = saved.distance;
+                camera.yaw = saved.yaw;
+                camera.pitch = saved.pitch;
+                camera.projection = saved.projection;
+                if let Err(e) = camera.set_clip_planes(saved.clip_planes) {
+                    log::error!("{e}");
+                }
+            }
+            (None, Some(_)) => log::warn!("the snapshot has a model camera but no model is loaded; ignoring it"),
+            _ => {}
+        }
+    }
+
+    /// Writes `uniform_snapshot` to `path` as JSON.
+    pub fn export_uniforms(&self, path: &Path) -> Result<(), SnapshotError> {
+        self.uniform_snapshot().save(path)
+    }
+
+    /// Reads a snapshot `export_uniforms` wrote and applies it. Nothing
+    /// changes if the file can't be read or parsed.
+    pub fn import_uniforms(&mut self, path: &Path) -> Result<(), SnapshotError> {
+        let snapshot = UniformSnapshot::load(path)?;
+        self.apply_uniform_snapshot(&snapshot);
+        Ok(())
+    }
+
+    /// The preset last saved or loaded, if any.
+    pub fn preset(&self) -> Option<&str> {
+        self.preset.as_deref()
+    }
+
+    /// Reapplies the current surface config, e.g. after the surface was lost.
+    pub fn reconfigure(&mut self) {
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    /// Switches to the next preloaded mesh, wrapping around.
+    pub fn next_mesh(&mut self) {
+        self.current_mesh = (self.current_mesh + 1) % self.meshes.len();
+    }
+
+    /// Advances the simulation by the wall-clock time since the last call
+    /// times the time scale, unless paused, and returns how far that was.
+    pub fn update(&mut self) -> f32 {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+        let dt = if self.paused { 0.0 } else { dt * self.time_scale };
+        self.time += dt;
+        self.run_update_callback(dt);
+        dt
+    }
+
+    /// Installs (or with `None`, removes) the per-frame update hook. The
+    /// state isn't reset or rebuilt around it, so it keeps running across
+    /// `reset`. Installing one from inside the running callback replaces it
+    /// after it returns; removing it has to happen from outside.
+    pub fn set_update_callback(&mut self, callback: Option<UpdateCallback>) {
+        self.update_callback = callback;
+    }
+
+    /// Installs (or with `None`, removes) the scene pass draw hook.
+    pub fn set_render_callback(&mut self, callback: Option<RenderCallback>) {
+        self.render_callback = callback;
+    }
+
+    // The callback is out of `self` while it runs, so it can have the whole
+    // state; it goes back unless it installed a replacement
+    fn run_update_callback(&mut self, dt: f32) {
+        let Some(mut callback) = self.update_callback.take() else { return };
+        callback(self, dt);
+        if self.update_callback.is_none() {
+            self.update_callback = Some(callback);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Scales the `dt` that `update` advances the clock by, and so everything
+    /// that runs off it: the time uniform, sprites, shakes and the update
+    /// callback. 0 freezes them like a pause; below 0 runs them backwards.
+    /// `step` takes its `dt` as given. Clamped to ±64; NaN is ignored.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        if scale.is_nan() {
+            log::warn!("ignoring a NaN time scale");
+            return;
+        }
+        self.time_scale = clamp_time_scale(scale);
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Advances the simulation by exactly `dt` seconds and renders once,
+    /// regardless of pause state.
+    pub fn step(&mut self, dt: f32) -> Result<(), GfxError> {
+        // so an `update` after a run of steps doesn't catch up on their wall time
+        self.last_frame = Instant::now();
+        self.time += dt;
+        self.run_update_callback(dt);
+        self.render()
+    }
+
+    /// Draws the 2D meshes with a checkerboard texture instead of vertex colors.
+    pub fn toggle_textured(&mut self) {
+        self.textured = !self.textured;
+    }
+
+    /// Draws the 2D meshes with a layer of the sprite array, ahead of the
+    /// checkerboard texture if both are on.
+    pub fn toggle_sprites(&mut self) {
+        self.sprites = !self.sprites;
+    }
+
+    /// How the checkerboard and sprite textures are filtered within a mip
+    /// level. The sprites are only 16 pixels across by default, so they show
+    /// the difference best: blocky with `Nearest`, smeared with `Linear`.
+    pub fn set_filter(&mut self, filter: wgpu::FilterMode) {
+        self.filter = filter;
+        log::info!("texture filter {filter:?}");
+    }
+
+    pub fn filter(&self) -> wgpu::FilterMode {
+        self.filter
+    }
+
+    /// Shows the next layer and stops the animation there.
+    pub fn next_sprite_layer(&mut self) {
+        self.sprite_layer = (self.sprite_layer() + 1) % self.sprite_sheet.layers;
+        self.sprite_animation = false;
+        log::info!("sprite layer {} of {}", self.sprite_layer + 1, self.sprite_sheet.layers);
+    }
+
+    /// Cycles the layers on a timer, picking up from the one showing when
+    /// it was stopped.
+    pub fn toggle_sprite_animation(&mut self) {
+        self.sprite_layer = self.sprite_layer();
+        self.sprite_animation = !self.sprite_animation;
+    }
+
+    // the animation follows the simulation clock, so pausing holds it
+    fn sprite_layer(&self) -> u32 {
+        if self.sprite_animation {
+            // floored so it keeps stepping backwards through t = 0
+            let frames = (self.time * SPRITE_FPS).floor() as i64;
+            (self.sprite_layer as i64 + frames).rem_euclid(self.sprite_sheet.layers as i64) as u32
+        } else {
+            self.sprite_layer
+        }
+    }
+
+    /// Switches the texture between trilinear sampling of its mip chain and
+    /// sampling only the full-size level, to compare the shimmer at small sizes.
+    pub fn set_mipmaps(&mut self, enabled: bool) {
+        self.mipmaps = enabled;
+        log::info!("mipmaps {}", if enabled { "on" } else { "off" });
+    }
+
+    pub fn mipmaps(&self) -> bool {
+        self.mipmaps
+    }
+
+    /// Colors the 2D meshes by distance from their center, blending from
+    /// `inner` at the center to `outer` at the square's corners. `None` goes
+    /// back to vertex colors. The textured mode takes precedence while on.
+    pub fn set_radial_gradient(&mut self, gradient: Option<([f32; 3], [f32; 3])>) {
+        if let Some((inner, outer)) = gradient {
+            self.queue.write_buffer(&self.gradient_buffer, 0, bytemuck::bytes_of(&GradientUniform::new(inner, outer)));
+        }
+        self.radial_gradient = gradient;
+    }
+
+    pub fn radial_gradient(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.radial_gradient
+    }
+
+    /// Rounds the square mesh's corners by `radius` world units, clamped to
+    /// half its side, with edges anti-aliased in the fragment shader. Only
+    /// the plain vertex-color draw is rounded; 0 goes back to sharp corners.
+    pub fn set_corner_radius(&mut self, radius: f32) {
+        self.corner_radius = radius.clamp(0.0, max_corner_radius());
+        self.write_rounded_rect();
+    }
+
+    pub fn corner_radius(&self) -> f32 {
+        self.corner_radius
+    }
+
+    /// Outlines the square mesh with a `width` world unit border in `color`
+    /// (straight RGBA), inset from its edges and following any rounded
+    /// corners. The width is clamped to half the square's side. Like the
+    /// corners it's cut in the fragment shader, so only the plain
+    /// vertex-color draw gets it; `None` takes it away.
+    pub fn set_border(&mut self, border: Option<(f32, [f32; 4])>) {
+        self.border = border.map(clamp_border);
+        self.write_rounded_rect();
+    }
+
+    pub fn border(&self) -> Option<(f32, [f32; 4])> {
+        self.border
+    }
+
+    fn write_rounded_rect(&self) {
+        let uniform = RoundedRectUniform::new(self.corner_radius, self.border);
+        self.queue.write_buffer(&self.rounded_rect_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Draws the plain shapes with every triangle in a color hashed from its
+    /// index, to show where the triangles are and how many. Devices without
+    /// `primitive_index` in fragment shaders get the same colors from
+    /// de-indexed copies of the meshes made at startup.
+    pub fn set_triangle_debug_colors(&mut self, on: bool) {
+        if on && self.triangle_id_meshes.is_some() {
+            log::info!("no primitive_index on this device, drawing the baked triangle colors");
+        }
+        self.triangle_debug_colors = on;
+    }
+
+    pub fn triangle_debug_colors(&self) -> bool {
+        self.triangle_debug_colors
+    }
+
+    /// Stops culling the plain shapes' back faces and fills them with
+    /// `color` (straight RGBA) instead, so triangles wound the wrong way
+    /// show up rather than vanish. `None` goes back to culling them.
+    pub fn set_back_face_color(&mut self, color: Option<[f32; 4]>) {
+        self.back_face_color = color;
+        if let Some(color) = color {
+            self.queue.write_buffer(&self.back_face_buffer, 0, bytemuck::bytes_of(&BackFaceUniform { color }));
+        }
+    }
+
+    pub fn back_face_color(&self) -> Option<[f32; 4]> {
+        self.back_face_color
+    }
+
+    /// Draws a checkerboard behind the scene instead of the flat clear color.
+    pub fn set_checkerboard(&mut self, config: Option<CheckerboardConfig>) {
+        self.background.set_checkerboard(&self.queue, config);
+    }
+
+    pub fn checkerboard(&self) -> Option<CheckerboardConfig> {
+        self.background.checkerboard()
+    }
+
+    /// Draws a vertical sky gradient behind the scene; with `cycle` set it
+    /// fades to night and back on the simulation clock, so pausing holds it.
+    pub fn set_sky(&mut self, config: Option<SkyConfig>) {
+        self.background.set_sky(&self.queue, config);
+    }
+
+    pub fn sky(&self) -> Option<SkyConfig> {
+        self.background.sky()
+    }
+
+    /// Draws a vertical gradient through up to `MAX_GRADIENT_STOPS` colors
+    /// behind the scene, over the sky if that's on; an empty slice turns it
+    /// off. Positions run from 0 at the bottom to 1 at the top, in order.
+    /// Out of order or out of range ones are an error and leave the old
+    /// gradient.
+    pub fn set_gradient_stops(&mut self, stops: &[GradientStop]) -> Result<(), GfxError> {
+        self.background.set_gradient(&self.queue, stops)
+    }
+
+    pub fn gradient_stops(&self) -> &[GradientStop] {
+        self.background.gradient()
+    }
+
+    /// Draws a grid of small copies of the current mesh in one instanced
+    /// call. Only applies to the vertex-color look; the textured and gradient
+    /// modes still draw a single shape.
+    pub fn toggle_instancing(&mut self) {
+        self.instancing = !self.instancing;
+    }
+
+    /// Replaces the instance grid with `count` instances (at most 100000) at
+    /// random offsets across the same area, each spinning at its own phase.
+    /// The layout depends only on `seed`, so a seed reproduces it exactly.
+    pub fn scatter_instances(&mut self, count: u32, seed: u64) {
+        self.set_instances(scattered_instances(count.min(MAX_SCATTERED_INSTANCES), seed));
+        self.scatter_seed = Some(seed);
+        log::info!("scattered {} instances with seed {seed}", self.instances.len());
+    }
+
+    // growing the buffer if they don't fit
+    fn set_instances(&mut self, instances: Vec<Instance>) {
+        if std::mem::size_of_val(instances.as_slice()) as wgpu::BufferAddress > self.instance_buffer.size() {
+            self.instance_buffer = create_instance_buffer(&self.device, instances.len());
+        }
+        if let Some(cull) = &mut self.gpu_cull {
+            cull.set_instances(&self.device, &self.queue, &instances);
+        }
+        self.instances = instances;
+    }
+
+    pub fn scatter_seed(&self) -> Option<u64> {
+        self.scatter_seed
+    }
+
+    /// With culling on, instances entirely outside the view are dropped on
+    /// the CPU before upload; the image is the same either way.
+    pub fn set_instance_culling(&mut self, enabled: bool) {
+        self.instance_culling = enabled;
+    }
+
+    pub fn instance_culling(&self) -> bool {
+        self.instance_culling
+    }
+
+    /// How many instances the last instanced frame skipped as off-screen;
+    /// `None` while they're culled on the GPU, which doesn't report back.
+    pub fn culled_instance_count(&self) -> Option<usize> {
+        (!self.culls_on_gpu()).then(|| self.instances.len() - self.visible_instances as usize)
+    }
+
+    /// Culls the instances in a compute pass and draws the survivors with
+    /// `draw_indexed_indirect`, instead of filtering them on the CPU and
+    /// uploading them every frame. Only applies while culling is on, and
+    /// devices without compute shaders or indirect draws keep culling on the
+    /// CPU. Overlapping scattered instances may swap which one is in front,
+    /// since the GPU keeps the survivors in no particular order.
+    pub fn set_gpu_culling(&mut self, enabled: bool) {
+        if enabled && self.gpu_cull.is_none() {
+            log::warn!("GPU culling needs compute shaders and indirect draws, culling on the CPU");
+        }
+        self.gpu_culling = enabled;
+    }
+
+    pub fn gpu_culling(&self) -> bool {
+        self.gpu_culling
+    }
+
+    fn culls_on_gpu(&self) -> bool {
+        self.instance_culling && self.gpu_culling && self.gpu_cull.is_some()
+    }
+
+    /// Stops drawing the scene and shows an error banner with `message` over
+    /// the last frame that rendered. The message goes to the log too, in full
+    /// if it's longer than the banner's few lines.
+    pub fn show_error(&mut self, message: &str) {
+        log::error!("{message}");
+        self.post.set_error_text(&self.queue, message, self.config.width);
+        self.error = Some(message.to_owned());
+    }
+
+    /// Removes the banner and goes back to drawing the scene.
+    pub fn clear_error(&mut self) {
+        self.error = None;
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Also draws the current shape at last frame's angle with this alpha,
+    /// behind the current one, so fast spins leave a smear. Paused, the two
+    /// coincide and the ghost is hidden. Only applies to the plain
+    /// vertex-color shapes, not textured, gradient, instanced or 3D ones.
+    pub fn set_ghost(&mut self, alpha: Option<f32>) {
+        self.ghost = alpha.map(|a| a.clamp(0.0, 1.0));
+    }
+
+    pub fn ghost(&self) -> Option<f32> {
+        self.ghost
+    }
+
+    /// Swaps the selected shape for a finely subdivided square that the
+    /// vertex shader ripples.
+    pub fn toggle_wave(&mut self) {
+        self.wave = !self.wave;
+    }
+
+    /// Rebuilds the wave's square with `n` x `n` cells, clamped to 1..=1024.
+    /// Past 255 the mesh has more vertices than 16-bit indices reach, so it
+    /// switches to 32-bit ones.
+    pub fn set_wave_subdivisions(&mut self, n: u32) {
+        let n = n.clamp(1, MAX_WAVE_SUBDIVISIONS);
+        if n != self.wave_subdivisions {
+            self.wave_mesh = build_wave_mesh(&self.device, n, self.pipeline_context.vertex_colors);
+            self.wave_subdivisions = n;
+            log::info!("wave: {n}x{n} cells, {} indices as {:?}", self.wave_mesh.num_indices, self.wave_mesh.index_format);
+        }
+    }
+
+    pub fn wave_subdivisions(&self) -> u32 {
+        self.wave_subdivisions
+    }
+
+    /// Regenerates the Sierpinski mesh at `depth` levels of recursion,
+    /// clamped to 0..=10, and selects it.
+    pub fn set_sierpinski_depth(&mut self, depth: u32) {
+        self.upload_sierpinski(depth);
+        self.current_mesh = SIERPINSKI_MESH;
+    }
+
+    pub fn sierpinski_depth(&self) -> u32 {
+        self.sierpinski_depth
+    }
+
+    fn upload_sierpinski(&mut self, depth: u32) {
+        let depth = depth.min(MAX_SIERPINSKI_DEPTH);
+        if depth != self.sierpinski_depth {
+            let mesh = Mesh::sierpinski(depth, 0.6).with_winding(FRONT_FACE);
+            let layout = self.pipeline_context.vertex_layout;
+            let colors = self.pipeline_context.vertex_colors;
+            self.meshes[SIERPINSKI_MESH] = GpuMesh::new(&self.device, &mesh, layout, colors, Some(&self.pull_bgl));
+            if let Some(meshes) = &mut self.triangle_id_meshes {
+                meshes[SIERPINSKI_MESH] =
+                    GpuMesh::new(&self.device, &mesh.with_triangle_colors(), layout, colors, Some(&self.pull_bgl));
+            }
+            self.sierpinski_depth = depth;
+            let gpu_mesh = &self.meshes[SIERPINSKI_MESH];
+            log::info!(
+                "sierpinski depth {depth}: {} triangles, indices as {:?}",
+                gpu_mesh.num_indices / 3,
+                gpu_mesh.index_format
+            );
+        }
+    }
+
+    /// Draws `scene` instead of the current mesh, or with `None` goes back to
+    /// it. Every node also spins by the animation angle under its local
+    /// transform, so children circle their parent as it turns. Like the
+    /// callbacks, it's left alone by `reset`.
+    pub fn set_scene(&mut self, scene: Option<SceneNode>) {
+        if let Some(scene) = &scene {
+            let mut nodes = Vec::new();
+            scene.world_transforms(Affine2::IDENTITY, 0.0, &mut nodes);
+            let missing = nodes.iter().filter(|&&(index, _)| index >= self.meshes.len()).count();
+            if missing > 0 {
+                log::warn!("{missing} scene nodes use meshes past the {} there are; skipping them", self.meshes.len());
+            }
+            if nodes.len() - missing > MAX_SCENE_NODES {
+                log::warn!("scene has {} drawable nodes, drawing the first {MAX_SCENE_NODES}", nodes.len() - missing);
+            }
+        }
+        self.scene = scene;
+    }
+
+    pub fn scene(&self) -> Option<&SceneNode> {
+        self.scene.as_ref()
+    }
+
+    /// Draws a few overlapping half-transparent squares over the 2D scene.
+    pub fn toggle_translucent_quads(&mut self) {
+        self.translucent_quads = !self.translucent_quads;
+    }
+
+    /// Sorts the translucent squares back to front before drawing them.
+    /// They don't write depth, so with this off they blend in list order,
+    /// which for the built-in set is front to back and comes out wrong.
+    pub fn set_transparency_sort(&mut self, enabled: bool) {
+        self.transparency_sort = enabled;
+        log::info!("transparency sort {}", if enabled { "on" } else { "off" });
+    }
+
+    pub fn transparency_sort(&self) -> bool {
+        self.transparency_sort
+    }
+
+    /// Queues a rectangle for the next frame, `(x, y)` being its bottom-left
+    /// corner in world units and `color` straight RGBA. Everything pushed is
+    /// drawn in one call over the scene, then forgotten; push again each
+    /// frame to keep it on screen.
+    pub fn push_quad(&mut self, x: f32, y: f32, w: f32, h: f32, color: [f32; 4]) {
+        // negative sizes would wind the triangles backwards and get culled
+        let (x0, x1) = (x.min(x + w), x.max(x + w));
+        let (y0, y1) = (y.min(y + h), y.max(y + h));
+        let corners = [[x0, y0], [x1, y0], [x1, y1], [x0, y0], [x1, y1], [x0, y1]];
+        self.quad_vertices.extend(corners.map(|position| Vertex { position, color }));
+    }
+
+    // Uploads the pushed quads, first growing the buffer if they don't fit
+    fn upload_quads(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let quad_bytes = (VERTICES_PER_QUAD * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+        let max_quads = (self.device.limits().max_buffer_size / quad_bytes) as usize;
+        if self.quad_vertices.len() > max_quads * VERTICES_PER_QUAD {
+            log::warn!(
+                "{} quads exceed the {} byte buffer limit, dropping all past {max_quads}",
+                self.quad_vertices.len() / VERTICES_PER_QUAD,
+                self.device.limits().max_buffer_size
+            );
+            self.quad_vertices.truncate(max_quads * VERTICES_PER_QUAD);
+        }
+        let data: &[u8] = bytemuck::cast_slice(&self.quad_vertices);
+        if data.len() as wgpu::BufferAddress > self.quad_buffer.size() {
+            let quads = (self.quad_vertices.len() / VERTICES_PER_QUAD).next_power_of_two().min(max_quads);
+            log::debug!("growing the quad batch to {quads} quads");
+            self.quad_buffer = create_quad_buffer(&self.device, quads);
+        }
+        stage_write(&mut self.belt, encoder, &self.device, &self.quad_buffer, data);
+    }
+
+    /// Queues a line from `a` to `b` for the next frame, in world units and
+    /// straight RGBA. Like `push_quad` the lines are drawn over everything
+    /// in one call and then forgotten, so anything that should stay on
+    /// screen is queued again every frame.
+    pub fn debug_line(&mut self, a: [f32; 2], b: [f32; 2], color: [f32; 4]) {
+        let along = [b[0] - a[0], b[1] - a[1]];
+        self.debug_line_vertices.extend([
+            LineVertex { position: a, color, along: [0.0; 2] },
+            LineVertex { position: b, color, along },
+        ]);
+    }
+
+    /// Drops the lines queued so far this frame.
+    pub fn debug_lines_clear(&mut self) {
+        self.debug_line_vertices.clear();
+    }
+
+    // Uploads the queued lines, first growing the buffer if they don't fit
+    fn upload_debug_lines(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let line_bytes = (VERTICES_PER_LINE * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress;
+        let max_lines = (self.device.limits().max_buffer_size / line_bytes) as usize;
+        if self.debug_line_vertices.len() > max_lines * VERTICES_PER_LINE {
+            log::warn!(
+                "{} debug lines exceed the {} byte buffer limit, dropping all past {max_lines}",
+                self.debug_line_vertices.len() / VERTICES_PER_LINE,
+                self.device.limits().max_buffer_size
+            );
+            self.debug_line_vertices.truncate(max_lines * VERTICES_PER_LINE);
+        }
+        let data: &[u8] = bytemuck::cast_slice(&self.debug_line_vertices);
+        if data.len() as wgpu::BufferAddress > self.debug_line_buffer.size() {
+            let lines = (self.debug_line_vertices.len() / VERTICES_PER_LINE).next_power_of_two().min(max_lines);
+            log::debug!("growing the debug line buffer to {lines} lines");
+            self.debug_line_buffer = create_debug_line_buffer(&self.device, lines);
+        }
+        stage_write(&mut self.belt, encoder, &self.device, &self.debug_line_buffer, data);
+    }
+
+    // Uploads the translucent squares, farthest first when sorting
+    fn update_translucent_quads(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let mut quads = TRANSLUCENT_QUADS
+            .map(|(offset, depth)| Instance { offset, scale: TRANSLUCENT_SCALE, depth, phase: 0.0, tint: [1.0; 4] });
+        if self.transparency_sort {
+            // farther means larger z, unless reverse-Z flips the depth test
+            let reverse = self.pipeline_params.reverse_z();
+            quads.sort_by(|a, b| {
+                let order = b.depth.total_cmp(&a.depth);
+                if reverse { order.reverse() } else { order }
+            });
+        }
+        stage_write(&mut self.belt, encoder, &self.device, &self.translucent_buffer, bytemuck::cast_slice(&quads));
+    }
+
+    // Uploads the instances that can touch the view (all of them without
+    // culling) and remembers how many to draw
+    fn update_instances(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let ([half_w, half_h], _) = self.view_layout_for_target();
+        let radius = self.meshes[self.current_mesh].radius;
+        let visible: Vec<Instance> = self
+            .instances
+            .iter()
+            .filter(|i| {
+                let r = radius * i.scale;
+                !self.instance_culling || (i.offset[0].abs() - r <= half_w && i.offset[1].abs() - r <= half_h)
+            })
+            .copied()
+            .collect();
+        self.visible_instances = visible.len() as u32;
+        if !visible.is_empty() {
+            let data = bytemuck::cast_slice(&visible);
+            stage_write(&mut self.belt, encoder, &self.device, &self.instance_buffer, data);
+        }
+    }
+
+    // Records the compute pass that culls the instances for this frame's
+    // indirect draw
+    fn cull_instances_on_gpu(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let (half_size, _) = self.view_layout_for_target();
+        let mesh = &self.meshes[self.current_mesh];
+        let (radius, index_count) = (mesh.radius, mesh.num_indices);
+        if let Some(cull) = &self.gpu_cull {
+            cull.encode(&mut self.belt, encoder, &self.device, half_size, radius, index_count);
+        }
+    }
+
+    /// With clearing off, each frame draws over the last one instead of the
+    /// background color, so moving shapes smear into trails.
+    pub fn set_clear_each_frame(&mut self, clear: bool) {
+        self.clear_each_frame = clear;
+    }
+
+    pub fn clear_each_frame(&self) -> bool {
+        self.clear_each_frame
+    }
+
+    /// With depth loaded instead of cleared, last frame's depth still
+    /// occludes: anything drawn again at the same depth fails a `Less` test,
+    /// so only what moved shows up. A new depth buffer is cleared regardless.
+    pub fn set_clear_depth(&mut self, clear: bool) {
+        self.clear_depth = clear;
+        log::info!("depth {} each frame", if clear { "cleared" } else { "kept" });
+    }
+
+    pub fn clear_depth(&self) -> bool {
+        self.clear_depth
+    }
+
+    /// Snaps the displayed rotation to multiples of `step` radians, for a
+    /// ticking look. The clock keeps running smoothly underneath, so turning
+    /// snapping off doesn't jump. `None` (or a non-positive step) disables it.
+    pub fn set_angle_snap(&mut self, step: Option<f32>) {
+        self.angle_snap = step.filter(|step| *step > 0.0);
+    }
+
+    pub fn angle_snap(&self) -> Option<f32> {
+        self.angle_snap
+    }
+
+    /// Turns from the angle shown now to `target` radians over `duration`
+    /// seconds of simulation time, eased by `easing()`, in place of the
+    /// spin. It stays at `target` afterwards until `resume_spin`. Running
+    /// the clock back past the start cancels it and spins on from where it
+    /// began. A `duration` that isn't positive goes straight to `target`.
+    pub fn rotate_to(&mut self, target: f32, duration: f32) {
+        let from = self.display_angle();
+        self.held_angle = None;
+        if duration > 0.0 && target.is_finite() {
+            self.rotation = Some(Rotation { from, to: target, start: self.time, duration, easing: self.easing });
+        } else if target.is_finite() {
+            self.rotation = None;
+            self.held_angle = Some(target);
+        }
+    }
+
+    /// Lets the spin carry on from the angle the last `rotate_to` stopped
+    /// at, or from where one still running has got to.
+    pub fn resume_spin(&mut self) {
+        let angle = self.display_angle();
+        self.spin_offset = angle - self.time;
+        (self.rotation, self.held_angle) = (None, None);
+    }
+
+    /// The square's angle in radians as it's drawn now.
+    pub fn angle(&self) -> f32 {
+        self.display_angle()
+    }
+
+    /// Whether a `rotate_to` has taken over from the spin, running or done.
+    pub fn spin_held(&self) -> bool {
+        self.rotation.is_some() || self.held_angle.is_some()
+    }
+
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    /// The curve later `rotate_to` calls follow; one already running keeps its own.
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    // Holds a finished rotation at its target, or drops one the clock has
+    // run back past
+    fn settle_rotation(&mut self) {
+        let Some(rotation) = self.rotation else { return };
+        let elapsed = self.time - rotation.start;
+        if elapsed >= rotation.duration {
+            self.rotation = None;
+            self.held_angle = Some(rotation.to);
+        } else if elapsed < 0.0 {
+            self.rotation = None;
+            self.spin_offset = rotation.from - self.time;
+        }
+    }
+
+    // the angle that actually gets uploaded; snapping only applies to the spin
+    fn display_angle(&self) -> f32 {
+        if let Some(rotation) = &self.rotation {
+            let t = ((self.time - rotation.start) / rotation.duration).clamp(0.0, 1.0);
+            return rotation.from + (rotation.to - rotation.from) * rotation.easing.apply(t);
+        }
+        if let Some(angle) = self.held_angle {
+            return angle;
+        }
+        let spin = self.time + self.spin_offset;
+        match self.angle_snap {
+            Some(step) => (spin / step).round() * step,
+            None => spin,
+        }
+    }
+
+    /// Toggles debug groups/markers in the command stream, which show up as
+    /// named sections in RenderDoc/PIX captures.
+    pub fn set_debug_markers(&mut self, enabled: bool) {
+        self.debug_markers = enabled;
+    }
+
+    pub fn debug_markers(&self) -> bool {
+        self.debug_markers
+    }
+
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Switches between drawing frames and running only the compute
+    /// workload. `ComputeOnly` is ignored on devices without compute shaders.
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        let fallback = || format!("staying in {:?}", self.render_mode);
+        if mode == RenderMode::ComputeOnly && !self.capabilities.require(Capability::ComputeShaders, &fallback()) {
+            return;
+        }
+        self.render_mode = mode;
+        self.compute_dispatches = 0;
+        self.compute_stats_at = Instant::now();
+        log::info!("render mode {mode:?}");
+    }
+
+    /// The optional features this device has, and which fallbacks are in use.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// The next surface texture. A busy compositor can time out handing one
+    /// over, so `Timeout` is retried a few times, yielding in between;
+    /// `None` means it never came and the frame should be skipped. Other
+    /// errors are returned as they are.
+    fn acquire_frame(&self) -> Result<Option<wgpu::SurfaceTexture>, wgpu::SurfaceError> {
+        for attempt in 1..=ACQUIRE_ATTEMPTS {
+            match self.surface.get_current_texture() {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(wgpu::SurfaceError::Timeout) => {
+                    log::warn!("surface texture timed out (attempt {attempt} of {ACQUIRE_ATTEMPTS})");
+                    std::thread::yield_now();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        log::warn!("skipping a frame, the surface kept timing out");
+        Ok(None)
+    }
+
+    /// Draws a frame to the window. `render_to` does the drawing, into the
+    /// surface texture, which is then presented.
+    pub fn render(&mut self) -> Result<(), GfxError> {
+        if self.render_mode == RenderMode::ComputeOnly {
+            return self.render_compute_only();
+        }
+        let gpu_wait = self.wait_for_frames_in_flight();
+        let Some(output) = self.acquire_frame()? else {
+            return Ok(());
+        };
+        let cpu_start = Instant::now();
+        let view = output.texture.create_view(&Default::default());
+
+        self.render_to(&view, self.config.width, self.config.height)?;
+        output.present();
+        self.check_frame_budget(cpu_start.elapsed(), gpu_wait);
+
+        // rough input-to-photon: misses scanout, but tracks the queued-frame latency
+        if let Some(input_at) = self.input_at.take() {
+            log::debug!(
+                "input-to-present {:.1} ms (max frame latency {}, {} frames in flight, waited {:.1} ms on the GPU)",
+                input_at.elapsed().as_secs_f64() * 1000.0,
+                self.config.desired_maximum_frame_latency,
+                self.max_frames_in_flight,
+                gpu_wait.as_secs_f64() * 1000.0
+            );
+        }
+        Ok(())
+    }
+
+    /// Draws a frame into `view` rather than the window, for embedding the
+    /// renderer in something else: a texture an editor panel samples, or a
+    /// layer of a larger compositor. The view has to be `width` by `height`,
+    /// single-sampled, and of `surface_format()` with `RENDER_ATTACHMENT`
+    /// usage. The offscreen targets and the view of the world follow that
+    /// size, so switching between sizes (including back to the window's
+    /// with `render`) rebuilds them and starts over from a cleared scene.
+    /// The frame is submitted but nothing is presented. A zero `width` or
+    /// `height` draws nothing, as a minimized window does with `resize`.
+    pub fn render_to(&mut self, view: &wgpu::TextureView, width: u32, height: u32) -> Result<(), GfxError> {
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        if self.render_mode == RenderMode::ComputeOnly {
+            return self.render_compute_only();
+        }
+        self.apply_finished_assets();
+        self.fit_targets(width, height);
+        self.wait_for_frames_in_flight();
+        let commands = self.encode_frame(view);
+        self.in_flight.push_back(self.queue.submit(std::iter::once(commands)));
+        self.belt.recall();
+        Ok(())
+    }
+
+    /// The format the window surface was configured with, which `render_to`
+    /// targets also have to use.
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// Reconfigures the window surface with `format` and rebuilds the post
+    /// passes that write it, for comparing sRGB and linear output. Formats
+    /// the surface doesn't offer are refused with a warning, and so is
+    /// moving in or out of the HDR format, whose output scale is baked into
+    /// the pipelines at startup (`--hdr`).
+    pub fn set_surface_format(&mut self, format: wgpu::TextureFormat) {
+        if !self.surface_formats.contains(&format) {
+            log::warn!("the surface can't use {format:?} (it offers {:?})", self.surface_formats);
+            return;
+        }
+        let hdr = wgpu::TextureFormat::Rgba16Float;
+        if (format == hdr) != (self.config.format == hdr) {
+            log::warn!("switching to or from {hdr:?} needs a restart with or without --hdr");
+            return;
+        }
+        self.config.format = format;
+        self.surface.configure(&self.device, &self.config);
+        self.post.set_output_format(&self.device, &self.queue, format);
+        log::info!("surface format {format:?}");
+    }
+
+    // Swaps in whatever startup files have finished decoding, without waiting
+    fn apply_finished_assets(&mut self) {
+        if self.assets.loading() {
+            let assets = self.assets.finished();
+            self.apply_assets(assets);
+        }
+    }
+
+    fn render_compute_only(&mut self) -> Result<(), GfxError> {
+        self.apply_finished_assets();
+        self.render_compute();
+        Ok(())
+    }
+
+    /// Whether files given at startup (`--model`, `--lut`, `--sprite`) are
+    /// still being decoded. Until they're all in, frames are just the clear
+    /// color.
+    pub fn loading(&self) -> bool {
+        self.assets.loading()
+    }
+
+    // Uploads decoded startup files and swaps them in for the placeholders.
+    // A file that failed leaves its placeholder, as a failed load at startup
+    // always has.
+    fn apply_assets(&mut self, assets: Vec<Asset>) {
+        for asset in assets {
+            match asset {
+                Asset::Sprites(images) => {
+                    let sheet = images.map_err(GfxError::from).and_then(|images| TextureArray::new(&self.device, &self.queue, &images));
+                    match sheet {
+                        Ok(sheet) => {
+                            log::info!("{} sprite layers", sheet.layers);
+                            self.sprite_bind_groups = sprite_bind_groups(&self.device, &self.sprite_bgl, &sheet);
+                            self.sprite_sheet = sheet;
+                            self.sprite_layer = 0;
+                        }
+                        Err(e) => log::error!("{e}; using the built-in sprite frames"),
+                    }
+                }
+                Asset::Lut(image) => {
+                    let lut = image.map_err(GfxError::from).and_then(|image| {
+                        let lut = Lut::new(&self.device, &self.queue, &image)?;
+                        log::info!("loaded a {0}x{0}x{0} LUT from {1}", lut.size, image.path().display());
+                        self.lut_path = Some(image.path().to_owned());
+                        Ok(lut)
+                    });
+                    match lut {
+                        Ok(lut) => self.post.set_lut(&self.device, &self.queue, Some(lut)),
+                        Err(e) => log::error!("{e}; not grading"),
+                    }
+                }
+                // a bad model file just falls back to the 2D meshes
+                Asset::Model(path, Ok(model)) => {
+                    log::info!("loaded {} ({} primitives)", path.display(), model.primitives.len());
+                    let PipelineParams { sample_count, depth_format, .. } = self.pipeline_params;
+                    let reverse_z = self.pipeline_params.reverse_z();
+                    match ModelRenderer::new(&self.device, &model, post::SCENE_FORMAT, depth_format, sample_count, reverse_z) {
+                        Ok(renderer) => {
+                            self.model = Some(renderer);
+                            if let Err(e) = self.set_clip_planes(self.clip_planes) {
+                                log::error!("{e}");
+                            }
+                        }
+                        Err(e) => log::error!("{}: {e}", path.display()),
+                    }
+                }
+                Asset::Model(path, Err(e)) => log::error!("{}: {e}", path.display()),
+            }
+        }
+        if !self.assets.loading() {
+            // the clock starts with the first real frame
+            self.last_frame = Instant::now();
+            self.scene_needs_clear = true;
+        }
+    }
+
+    // One compute-only frame: still bounded by frames in flight, so the CPU
+    // can't queue unbounded work ahead of the GPU
+    fn render_compute(&mut self) {
+        self.wait_for_frames_in_flight();
+        let Some(compute) = &self.compute else { return };
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("compute encoder"),
+        });
+        compute.encode(&mut self.belt, &mut encoder, &self.device, 0.01);
+        self.belt.finish();
+        self.in_flight.push_back(self.queue.submit(Some(encoder.finish())));
+        self.belt.recall();
+
+        self.compute_dispatches += 1;
+        let elapsed = self.compute_stats_at.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            log::info!("{:.0} compute dispatches/s", self.compute_dispatches as f64 / elapsed.as_secs_f64());
+            self.compute_dispatches = 0;
+            self.compute_stats_at = Instant::now();
+        }
+    }
+
+    /// Renders one frame into an offscreen texture the size of the window
+    /// and reads it back.
+    pub fn capture_frame(&mut self) -> Result<Capture, GfxError> {
+        self.capture_frame_scaled(1)
+    }
+
+    /// Like `capture_frame`, but `scale` times the window size on each side.
+    /// The scene is rendered at that size rather than upscaled, with the same
+    /// view of the world, so edges come out crisp.
+    pub fn capture_frame_scaled(&mut self, scale: u32) -> Result<Capture, GfxError> {
+        let format = self.surface_format();
+        let bgra = capture::is_bgra(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+        let (window_width, window_height) = (self.config.width, self.config.height);
+        let max = self.max_texture_size();
+        // the largest scale that fits, as long as the window itself does
+        let fit = (max / window_width.max(window_height).max(1)).max(1);
+        let scale = match scale.max(1) {
+            scale if scale > fit => {
+                log::warn!("a {scale}x capture would exceed the {max}px texture limit, capturing at {fit}x");
+                fit
+            }
+            scale => scale,
+        };
+        let (scaled_width, scaled_height) = (window_width as u64 * scale as u64, window_height as u64 * scale as u64);
+        if scaled_width > max as u64 || scaled_height > max as u64 {
+            return Err(CaptureError::TooLarge { width: scaled_width, height: scaled_height, max }.into());
+        }
+        let (width, height) = (scaled_width as u32, scaled_height as u32);
+        self.fit_targets(width, height);
+        let result = self.capture_at(format, bgra, width, height);
+        self.fit_targets(window_width, window_height);
+        result
+    }
+
+    /// The largest width or height a 2D texture can have on this device; the
+    /// surface and offscreen targets are clamped to it.
+    pub fn max_texture_size(&self) -> u32 {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+    // The offscreen pieces that follow the size of what's drawn to
+    fn resize_targets(&mut self, width: u32, height: u32) {
+        self.post.resize(&self.device, width, height);
+        (self.depth_texture, self.depth_view) = create_depth_target(&self.device, self.pipeline_params.depth_format, width, height, self.post.sample_count());
+        self.post.set_depth_source(&self.device, &self.depth_texture);
+        self.background.resize(&self.queue, width, height);
+        self.scene_needs_clear = true;
+    }
+
+    // Resizes the targets for a frame of another size, which after a
+    // `render_to` can also be another aspect, so the projection follows
+    fn fit_targets(&mut self, width: u32, height: u32) {
+        if (width, height) != (self.depth_texture.width(), self.depth_texture.height()) {
+            self.resize_targets(width, height);
+            self.write_projection();
+        }
+    }
+
+    fn capture_at(&mut self, format: wgpu::TextureFormat, bgra: bool, width: u32, height: u32) -> Result<Capture, GfxError> {
+        // a capture is of the real thing, never the loading frame
+        if self.assets.loading() {
+            let assets = self.assets.wait();
+            self.apply_assets(assets);
+        }
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+
+        // same format as the surface so the existing pipeline can draw into it
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+
+        let bytes_per_row = capture::padded_bytes_per_row(width);
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback"),
+            size: (bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let frame = self.encode_frame(&view);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("capture encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        self.queue.submit([frame, encoder.finish()]);
+        self.belt.recall();
+
+        // block until the copy lands, then unpad while mapped
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        let _ = self.device.poll(wgpu::PollType::Wait);
+        rx.recv().expect("map callback dropped").map_err(CaptureError::Map)?;
+        let rgba = capture::unpad(&slice.get_mapped_range(), width, height, bgra);
+        readback.unmap();
+
+        Ok(Capture { width, height, rgba })
+    }
+
+    /// How many textures and buffers the renderer holds and roughly how much
+    /// memory they take, by kind; its `Display` is a table meant for the log.
+    /// See `ResourceReport` for what the estimate leaves out.
+    pub fn resource_report(&self) -> ResourceReport {
+        let mut report = ResourceReport::default();
+        report.texture(ResourceKind::ColorTexture, &self.checkerboard_texture.texture);
+        report.texture(ResourceKind::ColorTexture, &self.sprite_sheet.texture);
+        report.texture(ResourceKind::DepthTexture, &self.depth_texture);
+        self.post.report_resources(&mut report);
+
+        let triangle_id_meshes = self.triangle_id_meshes.iter().flatten();
+        for mesh in self.meshes.iter().chain(triangle_id_meshes).chain([&self.wave_mesh, &self.translucent_mesh]) {
+            mesh.report_resources(&mut report);
+        }
+        self.grid.report_resources(&mut report);
+        report.buffer(ResourceKind::VertexBuffer, &self.quad_buffer);
+        report.buffer(ResourceKind::VertexBuffer, &self.debug_line_buffer);
+        report.buffer(ResourceKind::VertexBuffer, &self.spiral_buffer);
+        report.buffer(ResourceKind::InstanceBuffer, &self.instance_buffer);
+        report.buffer(ResourceKind::InstanceBuffer, &self.translucent_buffer);
+        let uniforms = [&self.angle_buffer, &self.projection_buffer, &self.gradient_buffer, &self.rounded_rect_buffer, &self.back_face_buffer, &self.line_dash_buffer, &self.node_buffer];
+        for buffer in uniforms {
+            report.buffer(ResourceKind::UniformBuffer, buffer);
+        }
+        self.background.report_resources(&mut report);
+        if let Some(model) = &self.model {
+            model.report_resources(&mut report);
+        }
+        if let Some(compute) = &self.compute {
+            compute.report_resources(&mut report);
+        }
+        if let Some(cull) = &self.gpu_cull {
+            cull.report_resources(&mut report);
+        }
+        report
+    }
+
+    /// Rewrites the angle uniform `frames` times with each upload method and
+    /// times them; see `upload_bench`. The next frame rewrites it properly.
+    pub fn bench_uniform_uploads(&mut self, frames: u32) -> Result<Vec<UploadTiming>, wgpu::BufferAsyncError> {
+        self.angle_uploaded = None;
+        upload_bench::time_uploads(&self.device, &self.queue, &self.angle_buffer, 1, frames)
+    }
+
+    /// Like `bench_uniform_uploads`, but each frame makes `uploads` separate
+    /// uniform-sized writes into a scratch buffer, which is where staging
+    /// through the belt should pay off over `write_buffer`.
+    pub fn bench_many_uploads(&self, uploads: u32, frames: u32) -> Result<Vec<UploadTiming>, wgpu::BufferAsyncError> {
+        let slot = self.device.limits().min_uniform_buffer_offset_alignment as u64;
+        let scratch = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Upload Bench Scratch"),
+            size: slot * uploads.max(1) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        upload_bench::time_uploads(&self.device, &self.queue, &scratch, uploads, frames)
+    }
+
+    /// Draws `frames` frames offscreen of the instancing demo with the most
+    /// instances `scatter_instances` places, culled on the CPU and then on
+    /// the GPU where the device can, and times each. Instancing, culling and
+    /// the instances themselves are put back after.
+    pub fn bench_instance_culling(&mut self, frames: u32) -> Vec<CullTiming> {
+        if self.assets.loading() {
+            let assets = self.assets.wait();
+            self.apply_assets(assets);
+        }
+        let saved = (self.instancing, self.instance_culling, self.gpu_culling, self.instances.clone());
+        self.instancing = true;
+        self.instance_culling = true;
+        self.set_instances(scattered_instances(MAX_SCATTERED_INSTANCES, 0));
+
+        self.fit_targets(self.config.width, self.config.height);
+        let target = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Cull Bench Target"),
+            size: wgpu::Extent3d { width: self.config.width, height: self.config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = target.create_view(&Default::default());
+        let mut methods = vec![CullMethod::Cpu];
+        if self.gpu_cull.is_some() {
+            methods.push(CullMethod::Gpu);
+        } else {
+            log::warn!("GPU culling needs compute shaders and indirect draws, only timing the CPU");
+        }
+        let timings = methods
+            .into_iter()
+            .map(|method| {
+                self.gpu_culling = method == CullMethod::Gpu;
+                let start = Instant::now();
+                for _ in 0..frames {
+                    self.wait_for_frames_in_flight();
+                    let commands = self.encode_frame(&view);
+                    self.in_flight.push_back(self.queue.submit(Some(commands)));
+                    self.belt.recall();
+                }
+                self.in_flight.clear();
+                if let Err(e) = self.device.poll(wgpu::PollType::Wait) {
+                    log::warn!("waiting for the GPU to finish failed: {e}");
+                }
+                CullTiming { method, instances: self.instances.len(), frames, total: start.elapsed() }
+            })
+            .collect();
+
+        let (instancing, instance_culling, gpu_culling, instances) = saved;
+        (self.instancing, self.instance_culling, self.gpu_culling) = (instancing, instance_culling, gpu_culling);
+        self.set_instances(instances);
+        timings
+    }
+
+    /// Draws `frames` frames offscreen of the wave at its most cells with
+    /// each vertex color format, and times them. Everything but the vertex
+    /// data is the same between the runs, so the difference is what the
+    /// smaller colors save in memory and bandwidth. The wave, its mesh and
+    /// its pipeline are put back after, and so is a model, which would be
+    /// drawn instead.
+    pub fn bench_vertex_colors(&mut self, frames: u32) -> Vec<VertexColorTiming> {
+        if self.assets.loading() {
+            let assets = self.assets.wait();
+            self.apply_assets(assets);
+        }
+        let saved = (self.wave, self.model.take(), self.pipeline_context.vertex_colors);
+        self.wave = true;
+
+        self.fit_targets(self.config.width, self.config.height);
+        let target = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Vertex Color Bench Target"),
+            size: wgpu::Extent3d { width: self.config.width, height: self.config.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = target.create_view(&Default::default());
+        let mut timings = Vec::new();
+        for colors in VertexColors::ALL {
+            self.pipeline_context.vertex_colors = colors;
+            self.pipelines.wave = self.pipeline_context.wave_pipeline(&self.device, &self.pipeline_params);
+            self.wave_mesh = build_wave_mesh(&self.device, MAX_WAVE_SUBDIVISIONS, colors);
+            let vertex_bytes = self.wave_mesh.vertex_buffers.interleaved().size();
+            let start = Instant::now();
+            for _ in 0..frames {
+                self.wait_for_frames_in_flight();
+                let commands = self.encode_frame(&view);
+                self.in_flight.push_back(self.queue.submit(Some(commands)));
+                self.belt.recall();
+            }
+            self.in_flight.clear();
+            if let Err(e) = self.device.poll(wgpu::PollType::Wait) {
+                log::warn!("waiting for the GPU to finish failed: {e}");
+            }
+            timings.push(VertexColorTiming { colors, vertex_bytes, frames, total: start.elapsed() });
+        }
+
+        let (wave, model, colors) = saved;
+        (self.wave, self.model) = (wave, model);
+        self.pipeline_context.vertex_colors = colors;
+        self.pipelines.wave = self.pipeline_context.wave_pipeline(&self.device, &self.pipeline_params);
+        self.wave_mesh = build_wave_mesh(&self.device, self.wave_subdivisions, colors);
+        timings
+    }
+
+    /// Captures the frame at window size and puts it on the system
+    /// clipboard. Where no clipboard tool takes it, it's saved to `fallback`
+    /// instead and a warning says so.
+    pub fn copy_frame_to_clipboard(&mut self, fallback: &Path) -> Result<(), GfxError> {
+        let capture = self.capture_frame()?;
+        // the tools read from a file; one name per process keeps temp from
+        // filling up without two windows overwriting each other's
+        let staged = std::env::temp_dir().join(format!("gfx_1-{}-clipboard.png", std::process::id()));
+        capture::write_png(&staged, &capture)?;
+        match capture::copy_png_to_clipboard(&staged) {
+            Ok(tool) => log::info!("copied the frame to the clipboard with {tool}"),
+            Err(e) => {
+                log::warn!("{e}; saving the frame to {} instead", fallback.display());
+                capture::write_png(fallback, &capture)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders `frames` frames offscreen at a fixed `1 / fps` timestep from
+    /// t = 0, so the result loops and doesn't depend on frame timing, and
+    /// writes them to `path` as a gif. The simulation clock is restored after.
+    pub fn record_gif(&mut self, path: impl AsRef<Path>, frames: u32, fps: u32) -> Result<(), GfxError> {
+        let saved_time = self.time;
+        let mut captures = Vec::with_capacity(frames as usize);
+        for i in 0..frames {
+            self.time = i as f32 / fps.max(1) as f32;
+            match self.capture_frame() {
+                Ok(capture) => captures.push(capture),
+                Err(e) => {
+                    self.time = saved_time;
+                    return Err(e);
+                }
+            }
+        }
+        self.time = saved_time;
+
+        capture::write_gif(path.as_ref(), captures, fps)?;
+        log::info!("recorded {frames} frames to {}", path.as_ref().display());
+        Ok(())
+    }
+
+    // Uploads this frame's uniforms, records the scene and the post chain
+    // that writes the final image into `view`
+    fn encode_frame(&mut self, view: &wgpu::TextureView) -> wgpu::CommandBuffer {
+        // number encoder and pass labels so captures show which frame they came from
+        self.frame_count += 1;
+        let frame = self.frame_count;
+
+        let mut encoder = self.device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some(&format!("render encoder #{frame}"))
+            }
+        );
+        if self.error.is_some() {
+            // the scene target still holds the last good frame
+            self.quad_vertices.clear();
+            self.debug_line_vertices.clear();
+            self.post.encode(&mut encoder, view);
+            self.post.encode_error_banner(&mut encoder, view);
+            return encoder.finish();
+        }
+        if self.assets.loading() {
+            self.quad_vertices.clear();
+            self.debug_line_vertices.clear();
+            let (scene_view, resolve_target) = self.post.scene_attachment();
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&format!("Loading Pass #{frame}")),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: scene_view,
+                    resolve_target,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(CLEAR_COLOR), store: wgpu::StoreOp::Store },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.post.encode(&mut encoder, view);
+            return encoder.finish();
+        }
+
+        // ---- update angle uniform ----
+        self.settle_rotation();
+        let angle = self.display_angle();
+        let current = AngleUniform {
+            angle,
+            prev_angle: self.prev_angle,
+            ghost_alpha: self.ghost.unwrap_or(0.0),
+            layer: self.sprite_layer(),
+        };
+        self.prev_angle = angle;
+        // nothing to upload while paused with no ghost fading
+        if self.angle_uploaded.as_ref().map(bytemuck::bytes_of) != Some(bytemuck::bytes_of(&current)) {
+            stage_write(&mut self.belt, &mut encoder, &self.device, &self.angle_buffer, bytemuck::bytes_of(&current));
+            self.angle_uploaded = Some(current);
+        }
+        self.update_shake(&mut encoder);
+        self.background.update(&mut self.belt, &mut encoder, &self.device, self.time);
+        if self.instancing {
+            if self.culls_on_gpu() {
+                self.cull_instances_on_gpu(&mut encoder);
+            } else {
+                self.update_instances(&mut encoder);
+            }
+        }
+        let translucent = self.translucent_quads && self.model.is_none();
+        if translucent {
+            self.update_translucent_quads(&mut encoder);
+        }
+        let quad_vertices = self.quad_vertices.len() as u32;
+        if quad_vertices > 0 {
+            self.upload_quads(&mut encoder);
+        }
+        self.quad_vertices.clear();
+        if !self.debug_line_vertices.is_empty() {
+            self.upload_debug_lines(&mut encoder);
+        }
+        let debug_line_vertices = self.debug_line_vertices.len() as u32;
+        self.debug_line_vertices.clear();
+        let scene = self.scene.is_some() && self.model.is_none() && !self.wave;
+        if scene {
+            self.update_scene(&mut encoder, angle);
+        }
+        // letterboxing draws into part of the target, scaled captures included
+        let (_, [viewport_x, viewport_y, viewport_w, viewport_h]) = self.view_layout_for_target();
+        if let Some(model) = &mut self.model {
+            let aspect = viewport_w / viewport_h.max(1.0);
+            model.update(&mut self.belt, &mut encoder, &self.device, aspect, angle);
+        }
+        if self.debug_markers {
+            // the uploads are anonymous buffer copies, so mark where they end
+            encoder.insert_debug_marker("uniforms updated");
+        }
+
+        let clear_depth = (self.clear_depth || self.scene_needs_clear).then(|| self.depth_clear_value());
+        let (depth_ops, stencil_ops) = depth_stencil_ops(clear_depth, self.clear_stencil || self.scene_needs_clear);
+        let load = if self.clear_each_frame || self.scene_needs_clear {
+            wgpu::LoadOp::Clear(CLEAR_COLOR)
+        } else {
+            wgpu::LoadOp::Load
+        };
+        self.scene_needs_clear = false;
+
+        // with MSAA the pass resolves into the post chain's input; only the
+        // composite pass below may write `view`
+        let mut render_callback = self.render_callback.take();
+        let (scene_view, resolve_target) = self.post.scene_attachment();
+        debug_assert!(resolve_target.is_none_or(|target| target != view), "MSAA resolves past the post chain");
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&format!("Render Pass #{frame}")),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: scene_view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(depth_ops),
+                    stencil_ops: Some(stencil_ops),
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_viewport(viewport_x, viewport_y, viewport_w, viewport_h, 0.0, 1.0);
+            self.background.draw(&mut render_pass);
+            if self.debug_markers {
+                render_pass.push_debug_group("main draw");
+            }
+            match &self.model {
+                Some(model) => model.draw(&mut render_pass),
+                None if self.wave => {
+                    let mesh = &self.wave_mesh;
+                    render_pass.set_pipeline(&self.pipelines.wave);
+                    self.set_shared_bind_groups(&mut render_pass);
+                    render_pass.set_vertex_buffer(0, mesh.vertex_buffers.interleaved().slice(..));
+                    render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+                    render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+                }
+                None if scene => self.draw_scene(&mut render_pass),
+                None => self.draw_mesh(&mut render_pass),
+            }
+            if self.show_grid {
+                render_pass.set_pipeline(&self.pipelines.grid);
+                self.set_shared_bind_groups(&mut render_pass);
+                render_pass.set_vertex_buffer(0, self.grid.vertex_buffer.slice(..));
+                render_pass.set_index_buffer(self.grid.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.grid.num_indices, 0, 0..1);
+            }
+            if self.spiral.is_some() {
+                render_pass.set_pipeline(&self.pipelines.spiral);
+                self.set_shared_bind_groups(&mut render_pass);
+                render_pass.set_vertex_buffer(0, self.spiral_buffer.slice(..));
+                render_pass.draw(0..self.spiral_points, 0..1);
+            }
+            // blended last, over everything opaque
+            if self.ghost.is_some() && self.model.is_none() && !self.wave && !scene && self.plain_shape() {
+                self.draw_ghost(&mut render_pass);
+            }
+            if translucent {
+                let mesh = &self.translucent_mesh;
+                render_pass.set_pipeline(&self.pipelines.translucent);
+                self.set_shared_bind_groups(&mut render_pass);
+                render_pass.set_vertex_buffer(0, mesh.vertex_buffers.interleaved().slice(..));
+                render_pass.set_vertex_buffer(1, self.translucent_buffer.slice(..));
+                render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+                render_pass.draw_indexed(0..mesh.num_indices, 0, 0..TRANSLUCENT_QUADS.len() as u32);
+            }
+            if quad_vertices > 0 {
+                render_pass.set_pipeline(&self.pipelines.quad);
+                self.set_shared_bind_groups(&mut render_pass);
+                render_pass.set_vertex_buffer(0, self.quad_buffer.slice(..));
+                render_pass.draw(0..quad_vertices, 0..1);
+            }
+            if debug_line_vertices > 0 {
+                render_pass.set_pipeline(&self.pipelines.debug_lines);
+                self.set_shared_bind_groups(&mut render_pass);
+                render_pass.set_vertex_buffer(0, self.debug_line_buffer.slice(..));
+                render_pass.draw(0..debug_line_vertices, 0..1);
+            }
+            if self.debug_markers {
+                render_pass.pop_debug_group();
+            }
+            if let Some(callback) = &mut render_callback {
+                callback(self, &mut render_pass);
+            }
+        }
+        self.render_callback = render_callback;
+
+        if self.debug_markers {
+            encoder.push_debug_group("post");
+        }
+        if self.show_depth {
+            let range = self.depth_range();
+            self.post.encode_depth(&self.queue, &mut encoder, view, range);
+        } else if self.msaa_debug {
+            self.post.encode_msaa_coverage(&mut encoder, view);
+        } else {
+            self.post.encode(&mut encoder, view);
+        }
+        if self.debug_markers {
+            encoder.pop_debug_group();
+        }
+
+        // unmaps this frame's staging chunks; the caller recalls them after submitting
+        self.belt.finish();
+        encoder.finish()
+    }
+
+    // What the depth buffer holds: the model camera's perspective or
+    // orthographic range, or the 2D scene's orthographic 0..1
+    fn depth_range(&self) -> DepthRange {
+        match &self.model {
+            Some(model) => {
+                let camera = model.camera().camera();
+                DepthRange {
+                    near: camera.znear,
+                    far: camera.zfar,
+                    perspective: matches!(camera.projection, ProjectionMode::Perspective { .. }),
+                    reverse: camera.reverse_z,
+                }
+            }
+            None => DepthRange { near: 0.0, far: 1.0, perspective: false, reverse: self.pipeline_params.reverse_z() },
+        }
+    }
+
+    // the far plane for whichever depth test is in use
+    fn depth_clear_value(&self) -> f32 {
+        if self.pipeline_params.reverse_z() { 0.0 } else { 1.0 }
+    }
+
+    // whether `draw_mesh` uses the plain vertex-color pipeline
+    fn plain_shape(&self) -> bool {
+        !self.sprites && !self.textured && self.radial_gradient.is_none() && !self.instancing && !self.cutout
+    }
+
+    // whether the current mesh is the square and has its corners rounded or
+    // a border, both of which the rounded-rect shader draws
+    fn rounded_square(&self) -> bool {
+        self.current_mesh == SQUARE_MESH && (self.corner_radius > 0.0 || self.border.is_some())
+    }
+
+    // Groups 0 and 1, which every pipeline built from `shader.wgsl` shares
+    fn set_shared_bind_groups(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_bind_group(0, &self.frame_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.view_bind_group, &[]);
+    }
+
+    // The current mesh at last frame's angle; its depth ties with the shape
+    // drawn this frame, so it only shows where that one has moved away from
+    fn draw_ghost(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        let mesh = &self.meshes[self.current_mesh];
+        render_pass.set_pipeline(&self.pipelines.ghost);
+        self.set_shared_bind_groups(render_pass);
+        mesh.set_vertex_buffers(render_pass);
+        render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+        render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+    }
+
+    // One draw per node uploaded this frame, each at its own offset into
+    // the node buffer
+    fn draw_scene(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_pipeline(&self.pipelines.scene);
+        self.set_shared_bind_groups(render_pass);
+        for (slot, &(index, _)) in self.scene_nodes.iter().enumerate() {
+            let mesh = &self.meshes[index];
+            render_pass.set_bind_group(2, &self.node_bind_group, &[slot as u32 * self.node_stride]);
+            mesh.set_vertex_buffers(render_pass);
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+            render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+        }
+    }
+
+    // Flattens the scene at this frame's angle and uploads every node's
+    // transform in one write
+    fn update_scene(&mut self, encoder: &mut wgpu::CommandEncoder, angle: f32) {
+        self.scene_nodes.clear();
+        let Some(scene) = &self.scene else { return };
+        scene.world_transforms(Affine2::IDENTITY, angle, &mut self.scene_nodes);
+        // `set_scene` already warned about both
+        let meshes = self.meshes.len();
+        self.scene_nodes.retain(|&(index, _)| index < meshes);
+        self.scene_nodes.truncate(MAX_SCENE_NODES);
+        if self.scene_nodes.is_empty() {
+            return;
+        }
+        let stride = self.node_stride as usize;
+        let mut data = vec![0; self.scene_nodes.len() * stride];
+        for (chunk, &(_, world)) in data.chunks_exact_mut(stride).zip(&self.scene_nodes) {
+            chunk[..std::mem::size_of::<NodeUniform>()].copy_from_slice(bytemuck::bytes_of(&NodeUniform::new(world)));
+        }
+        stage_write(&mut self.belt, encoder, &self.device, &self.node_buffer, &data);
+    }
+
+    fn draw_mesh(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        // both own group 2
+        let textured = self.sprites || self.textured;
+        let cutout = self.cutout && !textured && self.radial_gradient.is_none();
+        let instanced = self.instancing && !textured && self.radial_gradient.is_none() && !cutout;
+        let rounded = !textured && self.radial_gradient.is_none() && !cutout && !instanced && self.rounded_square();
+        let plain = !textured && self.radial_gradient.is_none() && !cutout && !instanced && !rounded;
+        if self.sprites {
+            render_pass.set_pipeline(&self.pipelines.sprite);
+            render_pass.set_bind_group(2, &self.sprite_bind_groups[filter_index(self.filter)], &[]);
+        } else if self.textured {
+            render_pass.set_pipeline(&self.pipelines.textured);
+            render_pass.set_bind_group(2, &self.texture_bind_groups[filter_index(self.filter)][self.mipmaps as usize], &[]);
+        } else if self.radial_gradient.is_some() {
+            render_pass.set_pipeline(&self.pipelines.radial);
+        } else if cutout {
+            render_pass.set_pipeline(&self.pipelines.cutout);
+        } else if instanced {
+            render_pass.set_pipeline(&self.pipelines.instanced);
+        } else if rounded {
+            render_pass.set_pipeline(&self.pipelines.rounded);
+        } else if self.triangle_debug_colors {
+            render_pass.set_pipeline(&self.pipelines.triangle_ids);
+        } else if self.back_face_color.is_some() {
+            render_pass.set_pipeline(&self.pipelines.back_faces);
+        } else {
+            render_pass.set_pipeline(&self.pipelines.render);
+        }
+        self.set_shared_bind_groups(render_pass);
+        let mesh = match &self.triangle_id_meshes {
+            Some(meshes) if plain && self.triangle_debug_colors => &meshes[self.current_mesh],
+            _ => &self.meshes[self.current_mesh],
+        };
+        let instance_slot = match &mesh.vertex_buffers {
+            VertexBuffers::Interleaved(buffer) => {
+                render_pass.set_vertex_buffer(0, buffer.slice(..));
+                1
+            }
+            VertexBuffers::Split { positions, colors } => {
+                render_pass.set_vertex_buffer(0, positions.slice(..));
+                render_pass.set_vertex_buffer(1, colors.slice(..));
+                2
+            }
+            // only the plain pipeline pulls; the textured ones own group 2
+            VertexBuffers::Pulled { bind_group, .. } if plain => {
+                render_pass.set_bind_group(2, bind_group, &[]);
+                0
+            }
+            VertexBuffers::Pulled { buffer, .. } => {
+                render_pass.set_vertex_buffer(0, buffer.slice(..));
+                1
+            }
+        };
+        render_pass.set_index_buffer(mesh.index_buffer.slice(..), mesh.index_format);
+        if instanced {
+            match &self.gpu_cull {
+                Some(cull) if self.culls_on_gpu() => cull.draw(render_pass, instance_slot),
+                _ if self.visible_instances > 0 => {
+                    render_pass.set_vertex_buffer(instance_slot, self.instance_buffer.slice(..));
+                    render_pass.draw_indexed(0..mesh.num_indices, 0, 0..self.visible_instances);
+                }
+                _ => {}
+            }
+        } else {
+            render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+        }
+    }
+}
+
+// The 2D pipelines, all built together from one `PipelineParams`
+struct Pipelines {
+    // plain vertex colors, pulling vertices itself in `VertexLayout::Pulled`
+    render: wgpu::RenderPipeline,
+    textured: wgpu::RenderPipeline,
+    sprite: wgpu::RenderPipeline,
+    radial: wgpu::RenderPipeline,
+    instanced: wgpu::RenderPipeline,
+    ghost: wgpu::RenderPipeline,
+    wave: wgpu::RenderPipeline,
+    translucent: wgpu::RenderPipeline,
+    // a leaf-shaped alpha cutout of the current mesh
+    cutout: wgpu::RenderPipeline,
+    // the square with rounded, anti-aliased corners
+    rounded: wgpu::RenderPipeline,
+    back_faces: wgpu::RenderPipeline,
+    triangle_ids: wgpu::RenderPipeline,
+    quad: wgpu::RenderPipeline,
+    debug_lines: wgpu::RenderPipeline,
+    grid: wgpu::RenderPipeline,
+    spiral: wgpu::RenderPipeline,
+    scene: wgpu::RenderPipeline,
+}
+
+// The settings that reach into every 2D pipeline, so changing any of them
+// means building the whole set again. One struct means a new setting
+// composes with the others instead of rebuilding from its own copy of them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PipelineParams {
+    // has to match the scene's color and depth targets
+    sample_count: u32,
+    depth_format: wgpu::TextureFormat,
+    depth_compare: wgpu::CompareFunction,
+    // given for the standard depth direction; flipped under reverse-Z
+    grid_bias: wgpu::DepthBiasState,
+    // for the translucent pipelines; opaque ones always replace
+    blend: wgpu::BlendState,
+    // decode vertex colors from sRGB in the vertex shaders that use them
+    srgb_vertex_colors: bool,
+    // cutout edges become MSAA coverage instead of a hard discard; only
+    // ever set with `sample_count` above 1
+    alpha_to_coverage: bool,
 }
 
-pub struct State {
-    surface: wgpu::Surface<'static>,
-    device:  wgpu::Device,
-    queue:   wgpu::Queue,
-    config:  wgpu::SurfaceConfiguration,
-    render_pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    angle_buffer: wgpu::Buffer,
-    angle_bind_group: wgpu::BindGroup,
-    start_time: Instant,
+impl PipelineParams {
+    fn reverse_z(&self) -> bool {
+        matches!(self.depth_compare, wgpu::CompareFunction::Greater | wgpu::CompareFunction::GreaterEqual)
+    }
 }
 
-impl State {
-    pub async fn new(window: Arc<Window>) -> Self {
-        // creating instance
-        let instance = wgpu::Instance::default();
-        // get a surface
-        let surface = instance.create_surface(window.clone()).unwrap();
-        // get an adapter using adapteroptions
-        let adapter = instance
-        .request_adapter(
-            &wgpu::RequestAdapterOptions {
-                compatible_surface: Some(&surface),
-                ..Default::default()
-            }
-        ).await.unwrap();
+// The bind group layouts the 2D pipelines are built against. The bind
+// groups themselves belong to the state
+struct SceneBindGroupLayouts {
+    // group 0
+    frame: wgpu::BindGroupLayout,
+    // group 1
+    view: wgpu::BindGroupLayout,
+    // group 2, depending on the draw: pulled vertices, the checkerboard,
+    // the sprite array or a scene node's transform
+    pull: wgpu::BindGroupLayout,
+    texture: wgpu::BindGroupLayout,
+    sprite: wgpu::BindGroupLayout,
+    node: wgpu::BindGroupLayout,
+}
 
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor::default()
-        ).await.unwrap();
-        // configure the surface
-        let size = window.inner_size();
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface.get_capabilities(&adapter).formats[0],
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
+impl SceneBindGroupLayouts {
+    fn new(device: &wgpu::Device) -> Self {
+        // The shared uniforms, split by how often they're written so a
+        // frame's upload only touches group 0: the angle every frame, then
+        // the view and styling in group 1 on resize or when a setting
+        // changes. Group 2 is left to whatever the draw binds for itself.
+        let uniform_entry = |binding, visibility, size: usize| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(size as u64),
+            },
+            count: None,
         };
-        surface.configure(&device, &config);
-
-        // Square vertices: 6 vertices for 2 triangles
-        let vertices = [
-            // Triangle 1: bottom-left, bottom-right, top-right
-            -0.5_f32, -0.5, 1.0, 0.0, 0.0,  // bottom-left, red
-             0.5, -0.5, 0.0, 1.0, 0.0,       // bottom-right, green
-             0.5,  0.5, 0.0, 0.0, 1.0,       // top-right, blue
-            
-            // Triangle 2: bottom-left, top-right, top-left  
-            -0.5, -0.5, 1.0, 0.0, 0.0,       // bottom-left, red (repeated)
-             0.5,  0.5, 0.0, 0.0, 1.0,       // top-right, blue (repeated)
-            -0.5,  0.5, 1.0, 1.0, 0.0,       // top-left, yellow
-        ];
-
-        // Create vertex buffer
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+        let frame_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Per-Frame BGL"),
+            entries: &[uniform_entry(0, wgpu::ShaderStages::VERTEX, std::mem::size_of::<AngleUniform>())],
         });
-
-        // init angle: 
-        let angle_init = AngleUniform { angle: 0.0, _pad: [0.0; 3] };
-
-        // Create angle buffer
-        let angle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Angle UBO"),
-            contents: bytemuck::bytes_of(&angle_init),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        let view_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("View BGL"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX, std::mem::size_of::<ProjectionUniform>()),
+                uniform_entry(1, wgpu::ShaderStages::FRAGMENT, std::mem::size_of::<GradientUniform>()),
+                uniform_entry(2, wgpu::ShaderStages::FRAGMENT, std::mem::size_of::<RoundedRectUniform>()),
+                uniform_entry(3, wgpu::ShaderStages::FRAGMENT, std::mem::size_of::<BackFaceUniform>()),
+                uniform_entry(4, wgpu::ShaderStages::VERTEX_FRAGMENT, std::mem::size_of::<LineDashUniform>()),
+            ],
         });
-
-        let angle_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Angle BGL"),
+        let pull_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Vertex Storage BGL"),
             entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
+                binding: 2,
                 visibility: wgpu::ShaderStages::VERTEX,
                 ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
                     has_dynamic_offset: false,
-                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<AngleUniform>() as u64),
+                    min_binding_size: None,
                 },
                 count: None,
-            }]
+            }],
         });
-
-        let angle_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Angle BG"),
-            layout: &angle_bgl,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: angle_buffer.as_entire_binding()
-            }]
+        let texture_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sprite_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let node_size = wgpu::BufferSize::new(std::mem::size_of::<NodeUniform>() as u64);
+        let node_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Node BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: node_size,
+                },
+                count: None,
+            }],
         });
+        Self {
+            frame: frame_bgl,
+            view: view_bgl,
+            pull: pull_bgl,
+            texture: texture_bgl,
+            sprite: sprite_bgl,
+            node: node_bgl,
+        }
+    }
+}
+
+// Shader, layouts and formats shared by the 2D pipelines, which don't change
+// after startup
+struct PipelineContext {
+    shader: wgpu::ShaderModule,
+    render_layout: wgpu::PipelineLayout,
+    pulled_layout: wgpu::PipelineLayout,
+    textured_layout: wgpu::PipelineLayout,
+    sprite_layout: wgpu::PipelineLayout,
+    node_layout: wgpu::PipelineLayout,
+    vertex_layout: VertexLayout,
+    vertex_colors: VertexColors,
+    output_scale: f64,
+    // `None` where the device has no `primitive_index`
+    triangle_id_shader: Option<wgpu::ShaderModule>,
+}
 
-        // Load WGSL shader from external file
-        let shader_source = include_str!("shader.wgsl");
+impl PipelineContext {
+    fn new(
+        device: &wgpu::Device,
+        layouts: &SceneBindGroupLayouts,
+        shader: wgpu::ShaderModule,
+        triangle_id_shader: Option<wgpu::ShaderModule>,
+        vertex_layout: VertexLayout,
+        vertex_colors: VertexColors,
+        output_scale: f64,
+    ) -> Self {
+        let pipeline_layout = |label, group_2: Option<&wgpu::BindGroupLayout>| {
+            let mut bind_group_layouts = vec![&layouts.frame, &layouts.view];
+            bind_group_layouts.extend(group_2);
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &bind_group_layouts,
+                push_constant_ranges: &[],
+            })
+        };
+        Self {
+            shader,
+            render_layout: pipeline_layout("Render Pipeline Layout", None),
+            pulled_layout: pipeline_layout("Pulled Pipeline Layout", Some(&layouts.pull)),
+            textured_layout: pipeline_layout("Textured Pipeline Layout", Some(&layouts.texture)),
+            sprite_layout: pipeline_layout("Sprite Pipeline Layout", Some(&layouts.sprite)),
+            node_layout: pipeline_layout("Scene Node Pipeline Layout", Some(&layouts.node)),
+            vertex_layout,
+            vertex_colors,
+            output_scale,
+            triangle_id_shader,
+        }
+    }
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
-        });
+    fn build(&self, device: &wgpu::Device, params: &PipelineParams) -> Pipelines {
+        Pipelines {
+            render: self.render_pipeline(device, params),
+            textured: self.textured_pipeline(device, params),
+            sprite: self.sprite_pipeline(device, params),
+            radial: self.radial_pipeline(device, params),
+            instanced: self.instanced_pipeline(device, params),
+            ghost: self.ghost_pipeline(device, params),
+            wave: self.wave_pipeline(device, params),
+            translucent: self.translucent_pipeline(device, params),
+            cutout: self.cutout_pipeline(device, params),
+            rounded: self.rounded_pipeline(device, params),
+            back_faces: self.back_faces_pipeline(device, params),
+            triangle_ids: self.triangle_ids_pipeline(device, params),
+            quad: self.quad_pipeline(device, params),
+            debug_lines: self.debug_lines_pipeline(device, params),
+            grid: self.grid_pipeline(device, params),
+            spiral: self.spiral_pipeline(device, params),
+            scene: self.scene_pipeline(device, params),
+        }
+    }
 
-        // Render pipeline
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&angle_bgl],
-            push_constant_ranges: &[],
-        });
+    // `build` inside a validation error scope, for shaders that weren't
+    // written against these pipelines
+    fn build_checked(&self, device: &wgpu::Device, params: &PipelineParams, label: &str) -> Result<Pipelines, GfxError> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipelines = self.build(device, params);
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(e) => Err(GfxError::Shader { label: label.to_owned(), message: e.to_string() }),
+            None => Ok(pipelines),
+        }
+    }
+
+    fn render_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let (layout, vs_entry, buffers) = match self.vertex_layout {
+            VertexLayout::Pulled => (&self.pulled_layout, "vs_pulled", &[][..]),
+            layout => (&self.render_layout, "vs_main", vertex_buffer_layouts(layout, self.vertex_colors)),
+        };
+        let desc = ShapeDesc { label: "Render Pipeline", layout, vs_entry, fs_entry: "fs_main", buffers };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    // The plain shapes with nothing culled, back faces in their own color
+    fn back_faces_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let (layout, vs_entry, buffers) = match self.vertex_layout {
+            VertexLayout::Pulled => (&self.pulled_layout, "vs_pulled", &[][..]),
+            layout => (&self.render_layout, "vs_main", vertex_buffer_layouts(layout, self.vertex_colors)),
+        };
+        let style = ShapeStyle { double_sided: true, ..Default::default() };
+        let desc = ShapeDesc { label: "Back Faces Pipeline", layout, vs_entry, fs_entry: "fs_back_faces", buffers };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // The plain shapes in per-triangle colors: hashed from `primitive_index`
+    // where there is one, otherwise taken from the baked copies' vertices
+    fn triangle_ids_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let (layout, vs_entry, buffers) = match self.vertex_layout {
+            VertexLayout::Pulled => (&self.pulled_layout, "vs_pulled", &[][..]),
+            layout => (&self.render_layout, "vs_main", vertex_buffer_layouts(layout, self.vertex_colors)),
+        };
+        let (fs_entry, style) = match self.triangle_id_shader {
+            Some(_) => ("fs_triangle_ids", ShapeStyle { triangle_ids: true, ..Default::default() }),
+            None => ("fs_vertex_color", ShapeStyle::default()),
+        };
+        let desc = ShapeDesc { label: "Triangle ID Pipeline", layout, vs_entry, fs_entry, buffers };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    fn textured_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let layout = &self.textured_layout;
+        let desc = ShapeDesc {
+            label: "Textured Pipeline", layout,
+            vs_entry: "vs_textured", fs_entry: "fs_textured", buffers,
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    fn sprite_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let layout = &self.sprite_layout;
+        let desc = ShapeDesc {
+            label: "Sprite Pipeline", layout,
+            vs_entry: "vs_sprite", fs_entry: "fs_sprite", buffers,
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    // Plain vertex colors with per-instance offset and scale; pulled meshes
+    // are bound as ordinary vertex buffers here
+    fn instanced_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let buffers = instanced_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let layout = &self.render_layout;
+        let desc = ShapeDesc {
+            label: "Instanced Pipeline", layout,
+            vs_entry: "vs_instanced", fs_entry: "fs_instanced", buffers,
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    // The subdivided quad, displaced in the vertex shader; always interleaved,
+    // in whichever color format the other meshes use
+    fn wave_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let desc = ShapeDesc {
+            label: "Wave Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_wave", fs_entry: "fs_vertex_color",
+            buffers: vertex_buffer_layouts(VertexLayout::Interleaved, self.vertex_colors),
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    // The plain shapes at last frame's angle, alpha-blended; pulled meshes
+    // are bound as ordinary vertex buffers here
+    fn ghost_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { translucent: true, ..Default::default() };
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let desc = ShapeDesc {
+            label: "Ghost Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_ghost", fs_entry: "fs_ghost", buffers,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // Instanced vertex colors with alpha blending; tested against the depth
+    // buffer but not writing it, so draw order decides how they blend
+    fn translucent_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { translucent: true, ..Default::default() };
+        let layout = &self.render_layout;
+        let buffers = &INTERLEAVED_INSTANCED_LAYOUT;
+        let desc = ShapeDesc {
+            label: "Translucent Pipeline", layout,
+            vs_entry: "vs_translucent", fs_entry: "fs_vertex_color", buffers,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // The current mesh cut to a leaf shape by alpha testing; opaque and
+    // depth-writing like the plain shapes, with no blending either way
+    fn cutout_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { cutout: true, ..Default::default() };
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let desc = ShapeDesc {
+            label: "Cutout Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_textured", fs_entry: "fs_cutout", buffers,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // The plain shape cut to a rounded rectangle, its edge alpha-blended over
+    // what's behind; pulled meshes are bound as ordinary vertex buffers here
+    fn rounded_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { edge_alpha: true, ..Default::default() };
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let desc = ShapeDesc {
+            label: "Rounded Rect Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_rounded", fs_entry: "fs_rounded", buffers,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // the gradient only needs the local position, which vs_textured already passes as uv
+    fn radial_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let layout = &self.render_layout;
+        let desc = ShapeDesc {
+            label: "Radial Gradient Pipeline", layout,
+            vs_entry: "vs_textured", fs_entry: "fs_radial", buffers,
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    // Pushed quads in world space, blended over everything regardless of depth
+    fn quad_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { translucent: true, overlay: true, ..Default::default() };
+        let desc = ShapeDesc {
+            label: "Quad Batch Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_grid", fs_entry: "fs_vertex_color", buffers: &INTERLEAVED_LAYOUT,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // Queued debug lines, the quad batch's line-list counterpart, dashed
+    // with the grid
+    fn debug_lines_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { translucent: true, overlay: true, topology: wgpu::PrimitiveTopology::LineList, ..Default::default() };
+        let desc = ShapeDesc {
+            label: "Debug Line Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_line", fs_entry: "fs_dashed", buffers: &LINE_LAYOUT,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    fn grid_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let mut bias = params.grid_bias;
+        if params.reverse_z() {
+            bias.constant = -bias.constant;
+            bias.slope_scale = -bias.slope_scale;
+        }
+        let style = ShapeStyle { bias, ..Default::default() };
+        let desc = ShapeDesc {
+            label: "Grid Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_line", fs_entry: "fs_dashed", buffers: &LINE_LAYOUT,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
+
+    // The spiral as one line strip in world space, always interleaved; lines
+    // can't take a depth bias, so it's drawn with the overlays' Always test
+    fn spiral_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let style = ShapeStyle { overlay: true, topology: wgpu::PrimitiveTopology::LineStrip, ..Default::default() };
+        let desc = ShapeDesc {
+            label: "Spiral Pipeline", layout: &self.render_layout,
+            vs_entry: "vs_grid", fs_entry: "fs_vertex_color", buffers: &INTERLEAVED_LAYOUT,
+        };
+        self.shape_pipeline(device, params, desc, style)
+    }
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
+    // Scene nodes in their own colors, placed by the transform at the bound
+    // offset; pulled meshes are bound as ordinary vertex buffers here
+    fn scene_pipeline(&self, device: &wgpu::Device, params: &PipelineParams) -> wgpu::RenderPipeline {
+        let buffers = vertex_buffer_layouts(self.vertex_layout, self.vertex_colors);
+        let desc = ShapeDesc {
+            label: "Scene Pipeline", layout: &self.node_layout,
+            vs_entry: "vs_node", fs_entry: "fs_vertex_color", buffers,
+        };
+        self.shape_pipeline(device, params, desc, ShapeStyle::default())
+    }
+
+    fn shape_pipeline(
+        &self,
+        device: &wgpu::Device,
+        params: &PipelineParams,
+        desc: ShapeDesc,
+        style: ShapeStyle,
+    ) -> wgpu::RenderPipeline {
+        let ShapeDesc { label, layout, vs_entry, fs_entry, buffers } = desc;
+        let constants = [
+            ("output_scale", self.output_scale),
+            ("alpha_to_coverage", f64::from(u8::from(params.alpha_to_coverage))),
+        ];
+        // the triangle ID module only has the output scale
+        let (fragment_module, constants) = match &self.triangle_id_shader {
+            Some(module) if style.triangle_ids => (module, &constants[..1]),
+            _ => (&self.shader, &constants[..]),
+        };
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        // Position
-                        wgpu::VertexAttribute {
-                            offset: 0,
-                            shader_location: 0,
-                            format: wgpu::VertexFormat::Float32x2,
-                        },
-                        // Color
-                        wgpu::VertexAttribute {
-                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                            shader_location: 1,
-                            format: wgpu::VertexFormat::Float32x3,
-                        },
-                    ],
-                }],
+                module: &self.shader,
+                entry_point: Some(vs_entry),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[("srgb_vertex_colors", f64::from(u8::from(params.srgb_vertex_colors)))],
+                    ..Default::default()
+                },
+                buffers,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                module: fragment_module,
+                entry_point: Some(fs_entry),
+                compilation_options: wgpu::PipelineCompilationOptions { constants, ..Default::default() },
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    format: post::SCENE_FORMAT,
+                    blend: Some(if style.translucent {
+                        params.blend
+                    } else if style.edge_alpha {
+                        wgpu::BlendState::ALPHA_BLENDING
+                    } else {
+                        wgpu::BlendState::REPLACE
+                    }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
+                topology: style.topology,
+                front_face: FRONT_FACE,
+                cull_mode: if style.double_sided { None } else { CULL_MODE },
+                ..Default::default()
             },
-            depth_stencil: None,
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: params.depth_format,
+                depth_write_enabled: !style.translucent,
+                depth_compare: if style.overlay { wgpu::CompareFunction::Always } else { params.depth_compare },
+                stencil: wgpu::StencilState::default(),
+                bias: style.bias,
+            }),
             multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+                count: params.sample_count,
+                alpha_to_coverage_enabled: style.cutout && params.alpha_to_coverage,
+                ..Default::default()
             },
             multiview: None,
             cache: None,
-        });
+        })
+    }
+}
+
+// Which shader entry points a shape pipeline runs, and what it binds
+struct ShapeDesc<'a> {
+    label: &'a str,
+    layout: &'a wgpu::PipelineLayout,
+    vs_entry: &'a str,
+    fs_entry: &'a str,
+    buffers: &'a [wgpu::VertexBufferLayout<'a>],
+}
 
-        Self { surface, device, queue, config, render_pipeline, vertex_buffer, angle_buffer, angle_bind_group: angle_bg, start_time: Instant::now() }
+// The per-pipeline differences `shape_pipeline` allows on top of the context
+#[derive(Default)]
+struct ShapeStyle {
+    bias: wgpu::DepthBiasState,
+    // alpha-blended, and leaves depth alone
+    translucent: bool,
+    // passes the depth test wherever it lands
+    overlay: bool,
+    // opaque, but alpha-blended so anti-aliased edges fade out; still writes depth
+    edge_alpha: bool,
+    // alpha-tested, with alpha-to-coverage while `PipelineParams` asks for it
+    cutout: bool,
+    // triangle lists unless it says otherwise
+    topology: wgpu::PrimitiveTopology,
+    // back faces drawn rather than culled
+    double_sided: bool,
+    // the fragment entry point is in the triangle ID module
+    triangle_ids: bool,
+}
+
+// Logs what of `mesh` the shape pipelines wouldn't draw as it was generated,
+// the usual "my shape doesn't show up" mistake, before `with_winding` fixes it
+fn check_winding(name: &str, mesh: &Mesh) {
+    if !mesh.matches_declared_winding() {
+        log::warn!("{name}: generated as {:?} but not all of its triangles wind that way", mesh.winding);
     }
+    let triangles = mesh.indices.len() / 3;
+    match mesh.invisible_triangles(FRONT_FACE, CULL_MODE) {
+        0 => (),
+        n if n == triangles => log::warn!(
+            "{name}: all {n} triangles would be culled with front face {FRONT_FACE:?} and cull mode {CULL_MODE:?}; rewinding"
+        ),
+        n => log::info!("{name}: {n} of {triangles} triangles are degenerate or would be culled; rewinding"),
+    }
+}
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(&Default::default());
+// The wave's square at `n` x `n` cells. It's always interleaved, since its
+// vertex shader reads positions and colors together
+fn build_wave_mesh(device: &wgpu::Device, n: u32, colors: VertexColors) -> GpuMesh {
+    GpuMesh::new(device, &Mesh::subdivided_quad(n, WAVE_SIZE), VertexLayout::Interleaved, colors, None)
+}
 
-        let mut encoder = self.device.create_command_encoder(
-            &wgpu::CommandEncoderDescriptor {
-                label: Some("render encoder")
+// Caps a texture request at the device's 2D limit on each side, saying so
+// when it has to
+fn clamp_texture_size(device: &wgpu::Device, what: &str, width: u32, height: u32) -> (u32, u32) {
+    let max = device.limits().max_texture_dimension_2d;
+    let clamped = (width.min(max), height.min(max));
+    if clamped != (width, height) {
+        log::warn!("{what}: {width}x{height} exceeds the {max}px texture limit, clamping to {}x{}", clamped.0, clamped.1);
+    }
+    clamped
+}
+
+fn clamp_spiral(spiral: Spiral) -> Spiral {
+    Spiral { points: spiral.points.clamp(MIN_SPIRAL_POINTS, MAX_SPIRAL_POINTS), turns: spiral.turns.max(f32::EPSILON), ..spiral }
+}
+
+// Room for `points` spiral vertices, and at least a default spiral's worth
+fn create_spiral_buffer(device: &wgpu::Device, points: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Spiral Buffer"),
+        size: (points.max(Spiral::default().points as usize) * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+// NaN becomes 1
+fn clamp_time_scale(scale: f32) -> f32 {
+    if scale.is_nan() { 1.0 } else { scale.clamp(-MAX_TIME_SCALE, MAX_TIME_SCALE) }
+}
+
+// Past half the shorter side the corners would overlap
+fn max_corner_radius() -> f32 {
+    SQUARE_SIZE[0].min(SQUARE_SIZE[1]) * 0.5
+}
+
+// A border can be at most as wide as the corners can be round; NaN becomes 0
+fn clamp_border((width, color): (f32, [f32; 4])) -> (f32, [f32; 4]) {
+    (width.max(0.0).min(max_corner_radius()), color)
+}
+
+// The default instance layout: a square grid around the origin
+// Tinted around the color wheel by direction from the center
+fn instance_grid() -> Vec<Instance> {
+    (-INSTANCE_GRID..=INSTANCE_GRID)
+        .flat_map(|y| (-INSTANCE_GRID..=INSTANCE_GRID).map(move |x| (x, y)))
+        .map(|(x, y)| Instance {
+            offset: [x as f32 * INSTANCE_SPACING, y as f32 * INSTANCE_SPACING],
+            scale: INSTANCE_SCALE,
+            depth: 0.0,
+            phase: 0.0,
+            tint: mesh::hue((y as f32).atan2(x as f32)),
+        })
+        .collect()
+}
+
+// Spread over the grid's area; each instance draws its numbers in the same
+// order, so a seed always gives the same layout
+fn scattered_instances(count: u32, seed: u64) -> Vec<Instance> {
+    let mut rng = SplitMix64::new(seed);
+    let extent = INSTANCE_GRID as f32 * INSTANCE_SPACING;
+    (0..count)
+        .map(|_| {
+            let offset = [rng.range(-extent, extent), rng.range(-extent, extent)];
+            let phase = rng.range(0.0, std::f32::consts::TAU);
+            // tinted by phase rather than another draw, so seeds keep their layouts
+            Instance { offset, scale: INSTANCE_SCALE, depth: 0.0, phase, tint: mesh::hue(phase) }
+        })
+        .collect()
+}
+
+// never empty, even for a scatter of zero
+fn create_instance_buffer(device: &wgpu::Device, instances: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (instances.max(1) * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_quad_buffer(device: &wgpu::Device, quads: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Quad Batch Buffer"),
+        size: (quads * VERTICES_PER_QUAD * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_debug_line_buffer(device: &wgpu::Device, lines: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Debug Line Buffer"),
+        size: (lines * VERTICES_PER_LINE * std::mem::size_of::<LineVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+// `sample_count` has to match the scene's color target
+fn create_depth_target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        // sampled by the depth view
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&Default::default());
+    (texture, view)
+}
+
+// The scene pass's depth and stencil ops. Depth clears to `clear_depth`
+// and stencil to 0; whichever isn't cleared keeps the previous frame's
+fn depth_stencil_ops(clear_depth: Option<f32>, clear_stencil: bool) -> (wgpu::Operations<f32>, wgpu::Operations<u32>) {
+    let depth = wgpu::Operations { load: clear_depth.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear), store: wgpu::StoreOp::Store };
+    let stencil = wgpu::Operations {
+        load: if clear_stencil { wgpu::LoadOp::Clear(0) } else { wgpu::LoadOp::Load },
+        store: wgpu::StoreOp::Store,
+    };
+    (depth, stencil)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A device on whatever adapter WGPU_BACKEND allows, no surface needed;
+    // `None` on machines without one, where the GPU tests skip
+    fn headless_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default())).ok()?;
+        pollster::block_on(adapter.request_device(&Default::default())).ok()
+    }
+
+    // The angle `buffer` holds once the GPU is done with everything
+    // submitted so far, or NaN (with a warning) if it can't be mapped.
+    // `buffer` needs `COPY_SRC`.
+    fn read_angle(device: &wgpu::Device, queue: &wgpu::Queue, buffer: &wgpu::Buffer) -> f32 {
+        let size = std::mem::size_of::<AngleUniform>() as wgpu::BufferAddress;
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Angle Readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("angle readback encoder") });
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        let _ = device.poll(wgpu::PollType::Wait);
+        match rx.recv() {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => {
+                log::warn!("couldn't read the angle back: {e}");
+                return f32::NAN;
             }
-        );
+            Err(_) => {
+                log::warn!("couldn't read the angle back: the map callback never ran");
+                return f32::NAN;
+            }
+        }
+        let angle = bytemuck::pod_read_unaligned::<AngleUniform>(&slice.get_mapped_range()).angle;
+        readback.unmap();
+        angle
+    }
 
-        // ---- update angle uniform ----
-        let t = self.start_time.elapsed().as_secs_f32();
-        let current = AngleUniform { angle: t, _pad: [0.0; 3] };
-        self.queue.write_buffer(&self.angle_buffer, 0, bytemuck::bytes_of(&current));
+    // Which pixels of a `width` x `height` target `lines` light through the
+    // grid pipeline, dashed by `dash`, with `half_size` world units shown
+    // either side of the origin; rows from the top
+    fn dashed_pixels(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lines: &LineMesh,
+        half_size: [f32; 2],
+        (width, height): (u32, u32),
+        dash: Option<(f32, f32)>,
+    ) -> Vec<bool> {
+        let layouts = SceneBindGroupLayouts::new(device);
+        let shader = create_shader_module(device, "Shader", include_str!("shader.wgsl")).unwrap();
+        let context = PipelineContext::new(device, &layouts, shader, None, VertexLayout::Interleaved, VertexColors::Float, 1.0);
+        let params = PipelineParams {
+            sample_count: 1,
+            depth_format: wgpu::TextureFormat::Depth24PlusStencil8,
+            depth_compare: wgpu::CompareFunction::Less,
+            grid_bias: wgpu::DepthBiasState::default(),
+            blend: wgpu::BlendState::REPLACE,
+            srgb_vertex_colors: false,
+            alpha_to_coverage: false,
+        };
+        let pipeline = context.grid_pipeline(device, &params);
+
+        let buffer = |contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor { label: None, contents, usage })
+        };
+        let uniform = |contents: &[u8]| buffer(contents, wgpu::BufferUsages::UNIFORM);
+        let angle = uniform(bytemuck::bytes_of(&AngleUniform::zeroed()));
+        let view = [
+            uniform(bytemuck::bytes_of(&ProjectionUniform::new(half_size, [0.0; 2]))),
+            uniform(bytemuck::bytes_of(&GradientUniform::new([0.0; 3], [0.0; 3]))),
+            uniform(bytemuck::bytes_of(&RoundedRectUniform::new(0.0, None))),
+            uniform(bytemuck::bytes_of(&BackFaceUniform { color: [0.0; 4] })),
+            uniform(bytemuck::bytes_of(&LineDashUniform::new(dash, [0.0, 0.0, width as f32, height as f32]))),
+        ];
+        let frame_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layouts.frame,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: angle.as_entire_binding() }],
+        });
+        let entries: Vec<_> = (0..)
+            .zip(&view)
+            .map(|(binding, buffer)| wgpu::BindGroupEntry { binding, resource: buffer.as_entire_binding() })
+            .collect();
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor { label: None, layout: &layouts.view, entries: &entries });
+        let vertices = buffer(bytemuck::cast_slice(&lines.vertices), wgpu::BufferUsages::VERTEX);
+        let indices = buffer(bytemuck::cast_slice(&lines.indices), wgpu::BufferUsages::INDEX);
+
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let target = |format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let color = target(post::SCENE_FORMAT, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC);
+        let depth = target(params.depth_format, wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let (color_view, depth_view) = (color.create_view(&Default::default()), depth.create_view(&Default::default()));
+        let pixel_bytes = post::SCENE_FORMAT.block_copy_size(None).unwrap();
+        let row_bytes = (width * pixel_bytes).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: u64::from(row_bytes * height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
 
+        let mut encoder = device.create_command_encoder(&Default::default());
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: &color_view,
                     resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2, 
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations { load: wgpu::LoadOp::Clear(1.0), store: wgpu::StoreOp::Discard }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &frame_bind_group, &[]);
+            pass.set_bind_group(1, &view_bind_group, &[]);
+            pass.set_vertex_buffer(0, vertices.slice(..));
+            pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+            pass.draw_indexed(0..lines.indices.len() as u32, 0, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            color.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(row_bytes), rows_per_image: None },
+            },
+            size,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| ());
+        device.poll(wgpu::PollType::Wait).unwrap();
+        let data = slice.get_mapped_range();
+        // anything drawn at all; it's cleared to zeros
+        data.chunks_exact(row_bytes as usize)
+            .flat_map(|row| row[..(width * pixel_bytes) as usize].chunks_exact(pixel_bytes as usize))
+            .map(|pixel| pixel.iter().any(|&byte| byte != 0))
+            .collect()
+    }
+
+    // The lengths of the runs of lit (positive) and unlit (negative)
+    // pixels, without the first and last, which the edges may cut short
+    fn inner_runs(pixels: impl IntoIterator<Item = bool>) -> Vec<i32> {
+        let mut runs: Vec<(bool, i32)> = Vec::new();
+        for lit in pixels {
+            match runs.last_mut() {
+                Some((run, length)) if *run == lit => *length += 1,
+                _ => runs.push((lit, 1)),
+            }
+        }
+        let inner = runs.get(1..runs.len().saturating_sub(1)).unwrap_or_default();
+        inner.iter().map(|&(lit, length)| if lit { length } else { -length }).collect()
+    }
+
+    #[test]
+    fn lines_dash_in_pixels_whatever_the_view() {
+        let Some((device, queue)) = headless_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        // a cross through the origin, 2 units wide and 240 long
+        let cross = LineMesh::grid(120.0, 1000.0, 2.0, [1.0; 3]);
+        let dash = Some((8.0, 4.0));
+        let row = |pixels: &[bool], width: u32, y: u32| pixels[(y * width) as usize..][..width as usize].to_vec();
+        let column = |pixels: &[bool], width: u32, x: u32| pixels.iter().skip(x as usize).step_by(width as usize).copied().collect::<Vec<_>>();
+        let assert_dashed = |runs: Vec<i32>, what: &str| {
+            assert!(runs.len() >= 6, "{what}: only {runs:?}");
+            assert!(runs.iter().all(|&run| run == 8 || run == -4), "{what}: {runs:?}");
+        };
+
+        // 1 pixel a unit, and 2; the horizontal line's left half is clear of the vertical one
+        let pixels = dashed_pixels(&device, &queue, &cross, [128.0, 32.0], (256, 64), dash);
+        assert_dashed(inner_runs(row(&pixels, 256, 31)[..124].to_vec()), "1 px per unit");
+        let pixels = dashed_pixels(&device, &queue, &cross, [128.0, 32.0], (512, 128), dash);
+        assert_dashed(inner_runs(row(&pixels, 512, 63)[..248].to_vec()), "2 px per unit");
+
+        // stretched 4:1, still in pixels both ways
+        let pixels = dashed_pixels(&device, &queue, &cross, [128.0, 32.0], (256, 256), dash);
+        assert_dashed(inner_runs(row(&pixels, 256, 127)[..124].to_vec()), "stretched, across");
+        assert_dashed(inner_runs(column(&pixels, 256, 127)), "stretched, up");
+
+        // solid from end to end without a pattern
+        let pixels = dashed_pixels(&device, &queue, &cross, [128.0, 32.0], (256, 64), None);
+        assert_eq!(inner_runs(row(&pixels, 256, 31)), [240]);
+    }
+
+    #[test]
+    fn depth_carries_over_while_stencil_clears() {
+        let Some((device, queue)) = headless_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        // a full-screen triangle at depth 0.5 that only draws over nearer
+        // depth and zero stencil
+        let shader = create_shader_module(
+            &device,
+            "Depth Stencil Test",
+            "@vertex fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+                let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+                return vec4<f32>(uv * 2.0 - 1.0, 0.5, 1.0);
+            }
+            @fragment fn fs() -> @location(0) vec4<f32> { return vec4<f32>(1.0, 0.0, 0.0, 1.0); }",
+        )
+        .unwrap();
+        let format = wgpu::TextureFormat::Depth24PlusStencil8;
+        let stencil_face = wgpu::StencilFaceState { compare: wgpu::CompareFunction::Equal, ..Default::default() };
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState { module: &shader, entry_point: Some("vs"), compilation_options: Default::default(), buffers: &[] },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::TextureFormat::Rgba8Unorm.into())],
+            }),
+            primitive: Default::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Greater,
+                stencil: wgpu::StencilState { front: stencil_face, back: stencil_face, read_mask: !0, write_mask: 0 },
+                bias: Default::default(),
+            }),
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let size = wgpu::Extent3d { width: 4, height: 4, depth_or_array_layers: 1 };
+        let target = |format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let color = target(wgpu::TextureFormat::Rgba8Unorm, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC);
+        let depth = target(format, wgpu::TextureUsages::RENDER_ATTACHMENT);
+        let (color_view, depth_view) = (color.create_view(&Default::default()), depth.create_view(&Default::default()));
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: u64::from(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT * 4),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        // one frame leaves depth 0.25 and stencil 7, then the next loads the
+        // depth and clears the stencil before drawing
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let stencil_7 = wgpu::Operations { load: wgpu::LoadOp::Clear(7), store: wgpu::StoreOp::Store };
+        for (depth_ops, stencil_ops) in [(depth_stencil_ops(Some(0.25), false).0, stencil_7), depth_stencil_ops(None, true)] {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: wgpu::StoreOp::Store },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(depth_ops),
+                    stencil_ops: Some(stencil_ops),
+                }),
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
+            pass.set_pipeline(&pipeline);
+            pass.draw(0..3, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            color.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| ());
+        device.poll(wgpu::PollType::Wait).unwrap();
+        // drawn only if the second frame kept depth 0.25 and zeroed stencil
+        assert_eq!(slice.get_mapped_range()[..4], [255, 0, 0, 255]);
+    }
 
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.angle_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.draw(0..6, 0..1);
+    #[test]
+    fn line_dashes_need_a_positive_dash_and_a_gap_of_zero_or_more() {
+        assert!(valid_line_dash((8.0, 4.0)) && valid_line_dash((8.0, 0.0)));
+        for dash in [(0.0, 4.0), (-8.0, 4.0), (8.0, -1.0), (f32::NAN, 4.0), (8.0, f32::INFINITY)] {
+            assert!(!valid_line_dash(dash), "{dash:?}");
         }
+    }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
-        Ok(())
+    #[test]
+    fn pipelines_build_with_msaa_reverse_z_additive_and_alpha_to_coverage() {
+        let Some((device, _queue)) = headless_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        let layouts = SceneBindGroupLayouts::new(&device);
+        let params = PipelineParams {
+            sample_count: 4,
+            depth_format: wgpu::TextureFormat::Depth24PlusStencil8,
+            depth_compare: wgpu::CompareFunction::Greater,
+            grid_bias: wgpu::DepthBiasState { constant: -2, slope_scale: -1.0, clamp: 0.0 },
+            blend: ADDITIVE_BLEND,
+            srgb_vertex_colors: false,
+            alpha_to_coverage: true,
+        };
+        for layout in [VertexLayout::Interleaved, VertexLayout::Split] {
+            for colors in VertexColors::ALL {
+                let shader = create_shader_module(&device, "Shader", include_str!("shader.wgsl")).unwrap();
+                let context = PipelineContext::new(&device, &layouts, shader, None, layout, colors, 1.0);
+                let built = context.build_checked(&device, &params, "Shader");
+                assert!(built.is_ok(), "{layout:?} with {colors:?} colors: {}", built.err().unwrap());
+            }
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pixel_to_ndc_maps_corners_and_center() {
+        assert_eq!(pixel_to_ndc(0.0, 0.0, 800, 600), [-1.0, 1.0]);
+        assert_eq!(pixel_to_ndc(800.0, 600.0, 800, 600), [1.0, -1.0]);
+        assert_eq!(pixel_to_ndc(400.0, 300.0, 800, 600), [0.0, 0.0]);
+        assert_eq!(pixel_to_ndc(0.0, 600.0, 800, 600), [-1.0, -1.0]);
+    }
+
+    #[test]
+    fn easings_start_and_stop_on_the_mark() {
+        let samples = |easing: Easing| (0..=100).map(move |i| easing.apply(i as f32 / 100.0));
+        for easing in [Easing::Linear, Easing::EaseInOut, Easing::Spring] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{easing:?}");
+            assert_eq!(easing.next().next().next(), easing);
+        }
+        for easing in [Easing::Linear, Easing::EaseInOut] {
+            let progress: Vec<f32> = samples(easing).collect();
+            assert!(progress.windows(2).all(|pair| pair[0] <= pair[1]), "{easing:?} went backwards");
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-6);
+        }
+        // slow at both ends, unlike linear
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1 && Easing::EaseInOut.apply(0.9) > 0.9);
+        let overshoot = samples(Easing::Spring).fold(0.0, f32::max);
+        assert!((1.1..1.2).contains(&overshoot), "spring overshoots to {overshoot}");
+    }
+
+    #[test]
+    fn easings_parse_by_their_cli_names() {
+        assert_eq!("linear".parse(), Ok(Easing::Linear));
+        assert_eq!("ease-in-out".parse(), Ok(Easing::EaseInOut));
+        assert_eq!("spring".parse(), Ok(Easing::Spring));
+        assert!("bounce".parse::<Easing>().is_err());
+    }
+
+    #[test]
+    fn angle_uniform_reads_back_what_the_frame_staged() {
+        let Some((device, queue)) = headless_device() else {
+            eprintln!("no adapter, skipping");
+            return;
+        };
+        let angle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Angle UBO"),
+            contents: bytemuck::bytes_of(&AngleUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        });
+        // staged through the belt and submitted the way `render` does it
+        let mut belt = wgpu::util::StagingBelt::new(UPLOAD_CHUNK_SIZE);
+        for angle in [1.25, -0.5] {
+            let current = AngleUniform { angle, ..AngleUniform::zeroed() };
+            let mut encoder = device.create_command_encoder(&Default::default());
+            stage_write(&mut belt, &mut encoder, &device, &angle_buffer, bytemuck::bytes_of(&current));
+            belt.finish();
+            queue.submit(Some(encoder.finish()));
+            belt.recall();
+            assert_eq!(read_angle(&device, &queue, &angle_buffer), angle);
+        }
+    }
+}
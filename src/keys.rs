@@ -0,0 +1,624 @@
+// Which key triggers which action, with defaults and an optional config file
+//
+// The config is one `action = Key` per line, `#` starts a comment. Actions are
+// the snake_case names below and keys are winit `KeyCode` names ("KeyN",
+// "Digit9", "F2", "Space", ...), optionally after `Ctrl+`, `Alt+` or `Shift+`
+// ("Shift+KeyS"). Anything not mentioned keeps its default.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use winit::keyboard::{KeyCode, ModifiersState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    NextMesh,
+    TogglePause,
+    Step,
+    Reset,
+    CycleFrameLatency,
+    CycleFramesInFlight,
+    RecordGif,
+    Screenshot,
+    CopyFrame,
+    ToggleBloom,
+    BloomThresholdDown,
+    BloomThresholdUp,
+    BloomIntensityDown,
+    BloomIntensityUp,
+    BloomRadiusDown,
+    BloomRadiusUp,
+    ToggleBoxBlur,
+    BoxBlurRadiusDown,
+    BoxBlurRadiusUp,
+    BoxBlurIterationsDown,
+    BoxBlurIterationsUp,
+    ToggleRadialGradient,
+    ToggleCheckerboard,
+    ToggleSky,
+    ToggleTranslucentQuads,
+    ToggleTransparencySort,
+    ToggleQuadBars,
+    ToggleGhost,
+    ToggleWave,
+    ToggleProjection,
+    RandomizeColors,
+    ToggleSprites,
+    NextSpriteLayer,
+    ToggleSpriteAnimation,
+    ScatterInstances,
+    ToggleFilter,
+    ResourceReport,
+    ToggleVignette,
+    CycleBlend,
+    ToggleScene,
+    CycleCornerRadius,
+    ToggleSrgbVertexColors,
+    ToggleCutout,
+    ToggleAlphaToCoverage,
+    ToggleSpiral,
+    SpiralPointsDown,
+    SpiralPointsUp,
+    ToggleDepthView,
+    SierpinskiDepthDown,
+    SierpinskiDepthUp,
+    ToggleBackFaces,
+    ToggleFrameBudget,
+    ToggleColorGrade,
+    ColorGradeIntensityDown,
+    ColorGradeIntensityUp,
+    ReloadLut,
+    ExplodeLess,
+    ExplodeMore,
+    ToggleDither,
+    Shake,
+    ToggleTriangleColors,
+    CycleScaleMode,
+    CycleGradient,
+    ToggleRedChannel,
+    ToggleGreenChannel,
+    ToggleBlueChannel,
+    ToggleAlphaChannel,
+    ShowAllChannels,
+    ToggleDebugLines,
+    ToggleBorder,
+    ToggleGpuCulling,
+    NextPreset,
+    SavePreset,
+    TimeScaleDown,
+    TimeScaleUp,
+    ResetTimeScale,
+    ToggleMsaaDebug,
+    CyclePixelation,
+    ToggleSurfaceSrgb,
+    RotateToQuarter,
+    CycleEasing,
+    ExportUniforms,
+    ImportUniforms,
+    ToggleLineDash,
+    WaveSubdivisionsDown,
+    WaveSubdivisionsUp,
+    ToggleInstancing,
+    ToggleInstanceCulling,
+    ToggleTextured,
+    ToggleMipmaps,
+    ToggleClearEachFrame,
+    ToggleClearDepth,
+    CycleAngleSnap,
+    CycleDepthCompare,
+    ToggleGrid,
+    DepthBiasUp,
+    DepthBiasDown,
+    ToggleComputeOnly,
+    ToggleDebugMarkers,
+    DismissError,
+}
+
+impl Action {
+    /// Every action, in the order lookups try them.
+    pub const ALL: [Action; 100] = [
+        Self::NextMesh,
+        Self::TogglePause,
+        Self::Step,
+        Self::Reset,
+        Self::CycleFrameLatency,
+        Self::CycleFramesInFlight,
+        Self::RecordGif,
+        Self::Screenshot,
+        Self::CopyFrame,
+        Self::ToggleBloom,
+        Self::BloomThresholdDown,
+        Self::BloomThresholdUp,
+        Self::BloomIntensityDown,
+        Self::BloomIntensityUp,
+        Self::BloomRadiusDown,
+        Self::BloomRadiusUp,
+        Self::ToggleBoxBlur,
+        Self::BoxBlurRadiusDown,
+        Self::BoxBlurRadiusUp,
+        Self::BoxBlurIterationsDown,
+        Self::BoxBlurIterationsUp,
+        Self::ToggleRadialGradient,
+        Self::ToggleCheckerboard,
+        Self::ToggleSky,
+        Self::ToggleTranslucentQuads,
+        Self::ToggleTransparencySort,
+        Self::ToggleQuadBars,
+        Self::ToggleGhost,
+        Self::ToggleWave,
+        Self::ToggleProjection,
+        Self::RandomizeColors,
+        Self::ToggleSprites,
+        Self::NextSpriteLayer,
+        Self::ToggleSpriteAnimation,
+        Self::ScatterInstances,
+        Self::ToggleFilter,
+        Self::ResourceReport,
+        Self::ToggleVignette,
+        Self::CycleBlend,
+        Self::ToggleScene,
+        Self::CycleCornerRadius,
+        Self::ToggleSrgbVertexColors,
+        Self::ToggleCutout,
+        Self::ToggleAlphaToCoverage,
+        Self::ToggleSpiral,
+        Self::SpiralPointsDown,
+        Self::SpiralPointsUp,
+        Self::ToggleDepthView,
+        Self::SierpinskiDepthDown,
+        Self::SierpinskiDepthUp,
+        Self::ToggleBackFaces,
+        Self::ToggleFrameBudget,
+        Self::ToggleColorGrade,
+        Self::ColorGradeIntensityDown,
+        Self::ColorGradeIntensityUp,
+        Self::ReloadLut,
+        Self::ExplodeLess,
+        Self::ExplodeMore,
+        Self::ToggleDither,
+        Self::Shake,
+        Self::ToggleTriangleColors,
+        Self::CycleScaleMode,
+        Self::CycleGradient,
+        Self::ToggleRedChannel,
+        Self::ToggleGreenChannel,
+        Self::ToggleBlueChannel,
+        Self::ToggleAlphaChannel,
+        Self::ShowAllChannels,
+        Self::ToggleDebugLines,
+        Self::ToggleBorder,
+        Self::ToggleGpuCulling,
+        Self::NextPreset,
+        Self::SavePreset,
+        Self::TimeScaleDown,
+        Self::TimeScaleUp,
+        Self::ResetTimeScale,
+        Self::ToggleMsaaDebug,
+        Self::CyclePixelation,
+        Self::ToggleSurfaceSrgb,
+        Self::RotateToQuarter,
+        Self::CycleEasing,
+        Self::ExportUniforms,
+        Self::ImportUniforms,
+        Self::ToggleLineDash,
+        Self::WaveSubdivisionsDown,
+        Self::WaveSubdivisionsUp,
+        Self::ToggleInstancing,
+        Self::ToggleInstanceCulling,
+        Self::ToggleTextured,
+        Self::ToggleMipmaps,
+        Self::ToggleClearEachFrame,
+        Self::ToggleClearDepth,
+        Self::CycleAngleSnap,
+        Self::CycleDepthCompare,
+        Self::ToggleGrid,
+        Self::DepthBiasUp,
+        Self::DepthBiasDown,
+        Self::ToggleComputeOnly,
+        Self::ToggleDebugMarkers,
+        Self::DismissError,
+    ];
+
+    /// The name config files use.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NextMesh => "next_mesh",
+            Self::TogglePause => "toggle_pause",
+            Self::Step => "step",
+            Self::Reset => "reset",
+            Self::CycleFrameLatency => "cycle_frame_latency",
+            Self::CycleFramesInFlight => "cycle_frames_in_flight",
+            Self::RecordGif => "record_gif",
+            Self::Screenshot => "screenshot",
+            Self::CopyFrame => "copy_frame",
+            Self::ToggleBloom => "toggle_bloom",
+            Self::BloomThresholdDown => "bloom_threshold_down",
+            Self::BloomThresholdUp => "bloom_threshold_up",
+            Self::BloomIntensityDown => "bloom_intensity_down",
+            Self::BloomIntensityUp => "bloom_intensity_up",
+            Self::BloomRadiusDown => "bloom_radius_down",
+            Self::BloomRadiusUp => "bloom_radius_up",
+            Self::ToggleBoxBlur => "toggle_box_blur",
+            Self::BoxBlurRadiusDown => "box_blur_radius_down",
+            Self::BoxBlurRadiusUp => "box_blur_radius_up",
+            Self::BoxBlurIterationsDown => "box_blur_iterations_down",
+            Self::BoxBlurIterationsUp => "box_blur_iterations_up",
+            Self::ToggleRadialGradient => "toggle_radial_gradient",
+            Self::ToggleCheckerboard => "toggle_checkerboard",
+            Self::ToggleSky => "toggle_sky",
+            Self::ToggleTranslucentQuads => "toggle_translucent_quads",
+            Self::ToggleTransparencySort => "toggle_transparency_sort",
+            Self::ToggleQuadBars => "toggle_quad_bars",
+            Self::ToggleGhost => "toggle_ghost",
+            Self::ToggleWave => "toggle_wave",
+            Self::ToggleProjection => "toggle_projection",
+            Self::RandomizeColors => "randomize_colors",
+            Self::ToggleSprites => "toggle_sprites",
+            Self::NextSpriteLayer => "next_sprite_layer",
+            Self::ToggleSpriteAnimation => "toggle_sprite_animation",
+            Self::ScatterInstances => "scatter_instances",
+            Self::ToggleFilter => "toggle_filter",
+            Self::ResourceReport => "resource_report",
+            Self::ToggleVignette => "toggle_vignette",
+            Self::CycleBlend => "cycle_blend",
+            Self::ToggleScene => "toggle_scene",
+            Self::CycleCornerRadius => "cycle_corner_radius",
+            Self::ToggleSrgbVertexColors => "toggle_srgb_vertex_colors",
+            Self::ToggleCutout => "toggle_cutout",
+            Self::ToggleAlphaToCoverage => "toggle_alpha_to_coverage",
+            Self::ToggleSpiral => "toggle_spiral",
+            Self::SpiralPointsDown => "spiral_points_down",
+            Self::SpiralPointsUp => "spiral_points_up",
+            Self::ToggleDepthView => "toggle_depth_view",
+            Self::SierpinskiDepthDown => "sierpinski_depth_down",
+            Self::SierpinskiDepthUp => "sierpinski_depth_up",
+            Self::ToggleBackFaces => "toggle_back_faces",
+            Self::ToggleFrameBudget => "toggle_frame_budget",
+            Self::ToggleColorGrade => "toggle_color_grade",
+            Self::ColorGradeIntensityDown => "color_grade_intensity_down",
+            Self::ColorGradeIntensityUp => "color_grade_intensity_up",
+            Self::ReloadLut => "reload_lut",
+            Self::ExplodeLess => "explode_less",
+            Self::ExplodeMore => "explode_more",
+            Self::ToggleDither => "toggle_dither",
+            Self::Shake => "shake",
+            Self::ToggleTriangleColors => "toggle_triangle_colors",
+            Self::CycleScaleMode => "cycle_scale_mode",
+            Self::CycleGradient => "cycle_gradient",
+            Self::ToggleRedChannel => "toggle_red_channel",
+            Self::ToggleGreenChannel => "toggle_green_channel",
+            Self::ToggleBlueChannel => "toggle_blue_channel",
+            Self::ToggleAlphaChannel => "toggle_alpha_channel",
+            Self::ShowAllChannels => "show_all_channels",
+            Self::ToggleDebugLines => "toggle_debug_lines",
+            Self::ToggleBorder => "toggle_border",
+            Self::ToggleGpuCulling => "toggle_gpu_culling",
+            Self::NextPreset => "next_preset",
+            Self::SavePreset => "save_preset",
+            Self::TimeScaleDown => "time_scale_down",
+            Self::TimeScaleUp => "time_scale_up",
+            Self::ResetTimeScale => "reset_time_scale",
+            Self::ToggleMsaaDebug => "toggle_msaa_debug",
+            Self::CyclePixelation => "cycle_pixelation",
+            Self::ToggleSurfaceSrgb => "toggle_surface_srgb",
+            Self::RotateToQuarter => "rotate_to_quarter",
+            Self::CycleEasing => "cycle_easing",
+            Self::ExportUniforms => "export_uniforms",
+            Self::ImportUniforms => "import_uniforms",
+            Self::ToggleLineDash => "toggle_line_dash",
+            Self::WaveSubdivisionsDown => "wave_subdivisions_down",
+            Self::WaveSubdivisionsUp => "wave_subdivisions_up",
+            Self::ToggleInstancing => "toggle_instancing",
+            Self::ToggleInstanceCulling => "toggle_instance_culling",
+            Self::ToggleTextured => "toggle_textured",
+            Self::ToggleMipmaps => "toggle_mipmaps",
+            Self::ToggleClearEachFrame => "toggle_clear_each_frame",
+            Self::ToggleClearDepth => "toggle_clear_depth",
+            Self::CycleAngleSnap => "cycle_angle_snap",
+            Self::CycleDepthCompare => "cycle_depth_compare",
+            Self::ToggleGrid => "toggle_grid",
+            Self::DepthBiasUp => "depth_bias_up",
+            Self::DepthBiasDown => "depth_bias_down",
+            Self::ToggleComputeOnly => "toggle_compute_only",
+            Self::ToggleDebugMarkers => "toggle_debug_markers",
+            Self::DismissError => "dismiss_error",
+        }
+    }
+
+    fn default_key(self) -> KeyChord {
+        match self {
+            Self::NextMesh => KeyCode::KeyN.into(),
+            Self::TogglePause => KeyCode::Space.into(),
+            Self::Step => KeyCode::Period.into(),
+            Self::Reset => KeyCode::Backspace.into(),
+            Self::CycleFrameLatency => KeyCode::KeyL.into(),
+            Self::CycleFramesInFlight => KeyCode::KeyF.into(),
+            Self::RecordGif => KeyCode::KeyG.into(),
+            Self::Screenshot => KeyCode::KeyP.into(),
+            Self::CopyFrame => KeyChord::ctrl(KeyCode::KeyC),
+            Self::ToggleBloom => KeyCode::KeyB.into(),
+            Self::BloomThresholdDown => KeyCode::Digit9.into(),
+            Self::BloomThresholdUp => KeyCode::Digit0.into(),
+            Self::BloomIntensityDown => KeyCode::Minus.into(),
+            Self::BloomIntensityUp => KeyCode::Equal.into(),
+            Self::BloomRadiusDown => KeyCode::BracketLeft.into(),
+            Self::BloomRadiusUp => KeyCode::BracketRight.into(),
+            Self::ToggleBoxBlur => KeyCode::F4.into(),
+            Self::BoxBlurRadiusDown => KeyCode::F5.into(),
+            Self::BoxBlurRadiusUp => KeyCode::F6.into(),
+            Self::BoxBlurIterationsDown => KeyCode::F7.into(),
+            Self::BoxBlurIterationsUp => KeyCode::F8.into(),
+            Self::ToggleRadialGradient => KeyCode::KeyR.into(),
+            Self::ToggleCheckerboard => KeyCode::KeyV.into(),
+            Self::ToggleSky => KeyCode::KeyU.into(),
+            Self::ToggleTranslucentQuads => KeyCode::KeyQ.into(),
+            Self::ToggleTransparencySort => KeyCode::KeyY.into(),
+            Self::ToggleQuadBars => KeyCode::KeyE.into(),
+            Self::ToggleGhost => KeyCode::KeyA.into(),
+            Self::ToggleWave => KeyCode::KeyS.into(),
+            Self::ToggleProjection => KeyCode::Digit5.into(),
+            Self::RandomizeColors => KeyCode::Digit3.into(),
+            Self::ToggleSprites => KeyCode::Digit4.into(),
+            Self::NextSpriteLayer => KeyCode::Digit6.into(),
+            Self::ToggleSpriteAnimation => KeyCode::Digit7.into(),
+            Self::ScatterInstances => KeyCode::Digit8.into(),
+            Self::ToggleFilter => KeyCode::F9.into(),
+            Self::ResourceReport => KeyCode::F10.into(),
+            Self::ToggleVignette => KeyCode::F11.into(),
+            Self::CycleBlend => KeyCode::F12.into(),
+            Self::ToggleScene => KeyCode::Tab.into(),
+            Self::CycleCornerRadius => KeyCode::Home.into(),
+            Self::ToggleSrgbVertexColors => KeyCode::Insert.into(),
+            Self::ToggleCutout => KeyCode::End.into(),
+            Self::ToggleAlphaToCoverage => KeyCode::Delete.into(),
+            Self::ToggleSpiral => KeyCode::Backslash.into(),
+            Self::SpiralPointsDown => KeyCode::PageDown.into(),
+            Self::SpiralPointsUp => KeyCode::PageUp.into(),
+            Self::ToggleDepthView => KeyCode::Backquote.into(),
+            Self::SierpinskiDepthDown => KeyCode::Semicolon.into(),
+            Self::SierpinskiDepthUp => KeyCode::Quote.into(),
+            Self::ToggleBackFaces => KeyCode::Slash.into(),
+            Self::ToggleFrameBudget => KeyCode::F1.into(),
+            Self::ToggleColorGrade => KeyCode::Enter.into(),
+            Self::ColorGradeIntensityDown => KeyCode::ArrowDown.into(),
+            Self::ColorGradeIntensityUp => KeyCode::ArrowUp.into(),
+            Self::ReloadLut => KeyCode::ArrowLeft.into(),
+            Self::ExplodeLess => KeyCode::NumpadSubtract.into(),
+            Self::ExplodeMore => KeyCode::NumpadAdd.into(),
+            Self::ToggleDither => KeyCode::Numpad0.into(),
+            Self::Shake => KeyCode::Numpad1.into(),
+            Self::ToggleTriangleColors => KeyCode::Numpad2.into(),
+            Self::CycleScaleMode => KeyCode::Numpad3.into(),
+            Self::CycleGradient => KeyCode::Numpad4.into(),
+            Self::ToggleRedChannel => KeyChord::ctrl(KeyCode::KeyR),
+            Self::ToggleGreenChannel => KeyChord::ctrl(KeyCode::KeyG),
+            Self::ToggleBlueChannel => KeyChord::ctrl(KeyCode::KeyB),
+            Self::ToggleAlphaChannel => KeyChord::ctrl(KeyCode::KeyA),
+            Self::ShowAllChannels => KeyChord::ctrl(KeyCode::Backspace),
+            Self::ToggleDebugLines => KeyCode::NumpadMultiply.into(),
+            Self::ToggleBorder => KeyCode::NumpadDivide.into(),
+            Self::ToggleGpuCulling => KeyCode::NumpadDecimal.into(),
+            Self::NextPreset => KeyCode::ArrowRight.into(),
+            Self::SavePreset => KeyCode::Comma.into(),
+            Self::TimeScaleDown => KeyCode::ScrollLock.into(),
+            Self::TimeScaleUp => KeyCode::Pause.into(),
+            Self::ResetTimeScale => KeyCode::NumpadEnter.into(),
+            Self::ToggleMsaaDebug => KeyCode::ContextMenu.into(),
+            Self::CyclePixelation => KeyCode::IntlBackslash.into(),
+            Self::ToggleSurfaceSrgb => KeyChord::shift(KeyCode::KeyS),
+            Self::RotateToQuarter => KeyChord::shift(KeyCode::KeyR),
+            Self::CycleEasing => KeyChord::shift(KeyCode::KeyE),
+            Self::ExportUniforms => KeyChord::shift(KeyCode::KeyX),
+            Self::ImportUniforms => KeyChord::shift(KeyCode::KeyI),
+            Self::ToggleLineDash => KeyChord::shift(KeyCode::KeyD),
+            Self::WaveSubdivisionsDown => KeyCode::Digit1.into(),
+            Self::WaveSubdivisionsUp => KeyCode::Digit2.into(),
+            Self::ToggleInstancing => KeyCode::KeyI.into(),
+            Self::ToggleInstanceCulling => KeyCode::KeyO.into(),
+            Self::ToggleTextured => KeyCode::KeyX.into(),
+            Self::ToggleMipmaps => KeyCode::KeyM.into(),
+            Self::ToggleClearEachFrame => KeyCode::KeyC.into(),
+            Self::ToggleClearDepth => KeyCode::KeyD.into(),
+            Self::CycleAngleSnap => KeyCode::KeyT.into(),
+            Self::CycleDepthCompare => KeyCode::KeyZ.into(),
+            Self::ToggleGrid => KeyCode::KeyH.into(),
+            Self::DepthBiasUp => KeyCode::KeyJ.into(),
+            Self::DepthBiasDown => KeyCode::KeyK.into(),
+            Self::ToggleComputeOnly => KeyCode::KeyW.into(),
+            Self::ToggleDebugMarkers => KeyCode::F2.into(),
+            Self::DismissError => KeyCode::Escape.into(),
+        }
+    }
+}
+
+// Keys a config file can name; winit has no name lookup of its own
+const NAMED_KEYS: [KeyCode; 100] = {
+    use KeyCode::*;
+    [
+        KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM,
+        KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+        Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18,
+        Space, Enter, Tab, Escape, Backspace, Delete, Insert, Home, End, PageUp, PageDown,
+        ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+        Minus, Equal, BracketLeft, BracketRight, Backslash, Semicolon, Quote, Backquote,
+        Comma, Period, Slash,
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+        NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide, NumpadDecimal, NumpadEnter,
+        ScrollLock, Pause, ContextMenu, IntlBackslash,
+    ]
+};
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    NAMED_KEYS.into_iter().find(|key| format!("{key:?}") == name)
+}
+
+// The modifiers a chord can hold, in the order they're written
+const MODIFIERS: [(ModifiersState, &str); 3] =
+    [(ModifiersState::CONTROL, "Ctrl"), (ModifiersState::ALT, "Alt"), (ModifiersState::SHIFT, "Shift")];
+
+/// A key and the modifiers held with it. Modifiers match exactly, so
+/// Shift+S doesn't also fire whatever S does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub modifiers: ModifiersState,
+    pub key: KeyCode,
+}
+
+impl KeyChord {
+    /// The chord a press of `key` makes while `modifiers` are held. Super
+    /// is left out; the desktop tends to keep it for itself.
+    pub fn new(modifiers: ModifiersState, key: KeyCode) -> Self {
+        Self { modifiers: modifiers - ModifiersState::SUPER, key }
+    }
+
+    pub fn shift(key: KeyCode) -> Self {
+        Self { modifiers: ModifiersState::SHIFT, key }
+    }
+
+    pub fn ctrl(key: KeyCode) -> Self {
+        Self { modifiers: ModifiersState::CONTROL, key }
+    }
+}
+
+impl From<KeyCode> for KeyChord {
+    fn from(key: KeyCode) -> Self {
+        Self { modifiers: ModifiersState::empty(), key }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// A key name with any modifiers before it, the way `KeyChord` displays.
+pub fn parse_chord(name: &str) -> Option<KeyChord> {
+    let (modifier_names, key) = name.rsplit_once('+').unwrap_or(("", name));
+    let mut modifiers = ModifiersState::empty();
+    for modifier_name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        let (modifier, _) = MODIFIERS.iter().find(|(_, name)| *name == modifier_name.trim())?;
+        modifiers |= *modifier;
+    }
+    Some(KeyChord { modifiers, key: parse_key(key.trim())? })
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    /// A line that isn't `action = Key`.
+    Syntax { line: usize },
+    UnknownAction { line: usize, name: String },
+    UnknownKey { line: usize, name: String },
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read key bindings: {e}"),
+            Self::Syntax { line } => write!(f, "key bindings line {line}: expected `action = Key`"),
+            Self::UnknownAction { line, name } => write!(f, "key bindings line {line}: no action called `{name}`"),
+            Self::UnknownKey { line, name } => write!(f, "key bindings line {line}: unknown key `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Action -> key. Every action has exactly one key.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    keys: HashMap<Action, KeyChord>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self { keys: Action::ALL.into_iter().map(|action| (action, action.default_key())).collect() }
+    }
+}
+
+impl KeyBindings {
+    /// The defaults with whatever the file at `path` rebinds. A key bound to
+    /// two actions only triggers the first one in `Action::ALL`, which is
+    /// logged rather than rejected.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        let mut bindings = Self::default();
+        for (index, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((action, key)) = line.split_once('=') else {
+                return Err(BindingsError::Syntax { line: line_number });
+            };
+            let (action, key) = (action.trim(), key.trim());
+            let Some(action) = Action::ALL.into_iter().find(|a| a.name() == action) else {
+                return Err(BindingsError::UnknownAction { line: line_number, name: action.to_owned() });
+            };
+            let Some(key) = parse_chord(key) else {
+                return Err(BindingsError::UnknownKey { line: line_number, name: key.to_owned() });
+            };
+            bindings.keys.insert(action, key);
+        }
+
+        for (i, a) in Action::ALL.iter().enumerate() {
+            for b in &Action::ALL[i + 1..] {
+                if bindings.keys[a] == bindings.keys[b] {
+                    log::warn!("{} is bound to both {} and {}; only {} will fire", bindings.keys[a], a.name(), b.name(), a.name());
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// The action `chord` triggers, if any.
+    pub fn match_action(&self, chord: KeyChord) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| self.keys.get(action) == Some(&chord))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_read_back_the_way_they_display() {
+        for chord in [KeyChord::from(KeyCode::KeyN), KeyChord::shift(KeyCode::KeyS), KeyChord::new(ModifiersState::all(), KeyCode::F2)] {
+            assert_eq!(parse_chord(&chord.to_string()), Some(chord));
+        }
+        assert_eq!(KeyChord::new(ModifiersState::all(), KeyCode::F2).to_string(), "Ctrl+Alt+Shift+F2");
+        assert_eq!(parse_chord("Shift + KeyS"), Some(KeyChord::shift(KeyCode::KeyS)));
+        assert_eq!(parse_chord("Ctrl+KeyC"), Some(KeyChord::ctrl(KeyCode::KeyC)));
+        assert_eq!(parse_chord("Hyper+KeyS"), None);
+        assert_eq!(parse_chord("Shift+"), None);
+    }
+
+    #[test]
+    fn default_keys_are_all_different() {
+        let bindings = KeyBindings::default();
+        for action in Action::ALL {
+            assert_eq!(bindings.match_action(action.default_key()), Some(action), "{}", action.name());
+        }
+    }
+}
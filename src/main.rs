@@ -1,50 +1,974 @@
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
+use winit::dpi::PhysicalPosition;
+use winit::event::{MouseButton, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::window::{Window, WindowId};
+use winit::keyboard::ModifiersState;
+use winit::window::{Icon, Window, WindowId};
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use keys::Action;
+use replay::InputEvent;
+use rng::SplitMix64;
+use scene::{SceneNode, Transform};
+
+mod assets;
+mod background;
+mod camera;
+mod capabilities;
+mod capture;
+mod cli;
+mod compute;
+mod cull;
+mod error;
+mod font;
 mod gfx;
+mod keys;
+mod mesh;
+mod model;
+mod post;
+mod preset;
+mod replay;
+mod resources;
+mod rng;
+mod scene;
+mod snapshot;
+mod texture;
+mod upload_bench;
+mod vertex_file;
+
+// Window title; anything shown in the title bar later builds on this
+const TITLE: &str = "gfx_1 - wgpu demo";
+
+// Playback rate for recorded gifs; gif delays are in 1/100 s, so this divides evenly
+const GIF_FPS: u32 = 25;
+
+// Inner and outer colors the R key toggles the radial gradient with
+const RADIAL_GRADIENT: ([f32; 3], [f32; 3]) = ([1.0, 0.85, 0.3], [0.5, 0.05, 0.15]);
+
+// Seconds for one day-night cycle of the U key's sky
+const SKY_CYCLE_SECONDS: f32 = 20.0;
+
+// Background gradients the cycle key steps through before turning it off
+const GRADIENT_PRESETS: [&[background::GradientStop]; 3] = [
+    // sunset
+    &[(0.0, [0.08, 0.04, 0.18]), (0.35, [0.85, 0.3, 0.2]), (0.55, [1.0, 0.7, 0.35]), (1.0, [0.2, 0.2, 0.5])],
+    // sea and sky, with a hard horizon
+    &[(0.0, [0.0, 0.04, 0.12]), (0.45, [0.0, 0.3, 0.45]), (0.45, [0.85, 0.9, 1.0]), (1.0, [0.25, 0.5, 0.9])],
+    // rainbow
+    &[
+        (0.0, [0.6, 0.0, 0.8]),
+        (0.2, [0.1, 0.2, 1.0]),
+        (0.4, [0.0, 0.8, 0.3]),
+        (0.6, [1.0, 0.9, 0.0]),
+        (0.8, [1.0, 0.5, 0.0]),
+        (1.0, [0.9, 0.0, 0.1]),
+    ],
+];
+
+// Bars the E key pushes through the quad batch each frame, along the bottom of the view
+const QUAD_BARS: u32 = 48;
+
+// Half size of the debug line demo's path, and the segments it's drawn with
+const DEBUG_PATH_SIZE: f32 = 0.6;
+const DEBUG_PATH_STEPS: u32 = 128;
+
+// Instances the 8 key scatters when --scatter didn't give a count
+const SCATTER_COUNT: u32 = 400;
+
+// Alpha of the A key's ghost at last frame's angle
+const GHOST_ALPHA: f32 = 0.35;
+
+// Depth tests the Z key cycles through; Greater runs the 2D scene reverse-Z
+const DEPTH_COMPARES: [wgpu::CompareFunction; 4] = [
+    wgpu::CompareFunction::Less,
+    wgpu::CompareFunction::LessEqual,
+    wgpu::CompareFunction::Greater,
+    wgpu::CompareFunction::Always,
+];
+
+// Blend modes for translucent draws that F12 cycles through
+const BLEND_MODES: [(&str, wgpu::BlendState); 4] = [
+    ("alpha", wgpu::BlendState::ALPHA_BLENDING),
+    ("additive", gfx::ADDITIVE_BLEND),
+    ("multiply", gfx::MULTIPLY_BLEND),
+    ("screen", gfx::SCREEN_BLEND),
+];
+
+// Corner radii for the square that Home cycles through, in world units;
+// 0.5 turns it into a circle
+const CORNER_RADII: [f32; 4] = [0.0, 0.1, 0.25, 0.5];
+
+// What the back faces are filled with while they're shown: magenta, which
+// none of the shapes' own colors come close to
+const BACK_FACE_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+// The square's border while numpad / has it on: a thin white frame
+const BORDER: (f32, [f32; 4]) = (0.04, [1.0, 1.0, 1.0, 1.0]);
+
+// The frame budget the toggle key checks against without `--frame-budget`:
+// one frame at 60 Hz
+const DEFAULT_FRAME_BUDGET_MS: f32 = 1000.0 / 60.0;
+
+// How much each explode key press moves the model's triangles, in bounding radii
+const EXPLODE_STEP: f32 = 0.05;
+
+// The shake key's intensity, in world units, and duration in seconds
+const SHAKE_INTENSITY: f32 = 0.05;
+const SHAKE_DURATION: f32 = 0.5;
+
+// Smallest nonzero time scale the ScrollLock/Pause keys step through;
+// going slower snaps to 0, and past that the scale flips sign
+const TIME_SCALE_STEP: f32 = 1.0 / 64.0;
+
+// Pixelation block sizes the IntlBackslash key cycles through before
+// turning it off again
+const PIXELATION_SIZES: [u32; 3] = [4, 8, 16];
+
+// How long Shift+R takes to turn the square to the nearest quarter turn, in
+// simulation seconds
+const ROTATE_DURATION: f32 = 0.6;
+
+// Where Shift+X exports the uniform state and Shift+I imports it from, relative
+// to the working directory
+const UNIFORMS_FILE: &str = "uniforms.json";
+
+// Dash and gap in pixels for the grid and debug lines while Shift+D has them dashed
+const LINE_DASH: (f32, f32) = (8.0, 4.0);
+
+// Snap step sizes (degrees) cycled by T, after which snapping turns off again
+const ANGLE_SNAP_STEPS: [f32; 3] = [15.0, 45.0, 90.0];
 
 #[derive(Default)]
 struct App {
+    args: cli::Args,
+    bindings: keys::KeyBindings,
+    // held as of the last ModifiersChanged, for telling Shift+S from S
+    modifiers: ModifiersState,
+    // last reported cursor position, physical pixels
+    cursor: Option<PhysicalPosition<f64>>,
+    // held buttons, dragging the orbit camera
+    orbiting: bool,
+    panning: bool,
+    // when poll mode next requests a redraw
+    next_frame: Option<Instant>,
     window: Option<Arc<Window>>,
     state: Option<gfx::State>,
+    recorder: Option<replay::Recorder>,
+    // while set, frames come from the recording and live input is ignored
+    player: Option<replay::Player>,
+    // frames drawn and when the first one started, for --run-frames/--run-seconds
+    frames_run: u64,
+    started: Option<Instant>,
+    // what the window title was last set to
+    title: String,
+    // which update-hook demos the keys have turned on
+    demos: Demos,
+}
+
+// The demos drawn from the update hook. There's one hook, so whichever are
+// on share a callback, rebuilt by `install` when one is toggled
+#[derive(Clone, Copy, Default)]
+struct Demos {
+    quad_bars: bool,
+    debug_lines: bool,
+}
+
+impl Demos {
+    fn install(self, state: &mut gfx::State) {
+        if !self.quad_bars && !self.debug_lines {
+            state.set_update_callback(None);
+            return;
+        }
+        let mut debug_lines = self.debug_lines.then(debug_line_demo);
+        let quad_bars = self.quad_bars;
+        state.set_update_callback(Some(Box::new(move |state, dt| {
+            if quad_bars {
+                push_quad_bars(state);
+            }
+            if let Some(debug_lines) = &mut debug_lines {
+                debug_lines(state, dt);
+            }
+        })));
+    }
 }
 
 impl ApplicationHandler for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_arc = Arc::new(event_loop.create_window(Window::default_attributes()).unwrap());
+        let attributes = Window::default_attributes().with_title(TITLE).with_window_icon(load_icon());
+        let window_arc = Arc::new(event_loop.create_window(attributes).unwrap());
         self.window = Some(window_arc.clone());
 
-        self.state = Some(pollster::block_on(
-            gfx::State::new(window_arc.clone())
-        ));
+        let mut builder = gfx::StateBuilder::default()
+            .hdr(self.args.hdr)
+            .msaa(self.args.msaa)
+            .clear_depth(!self.args.keep_depth)
+            .clear_stencil(!self.args.keep_stencil)
+            .vertex_layout(if self.args.pull_vertices {
+                gfx::VertexLayout::Pulled
+            } else if self.args.split_vertices {
+                gfx::VertexLayout::Split
+            } else {
+                gfx::VertexLayout::Interleaved
+            })
+            .vertex_colors(if self.args.unorm_colors { gfx::VertexColors::Unorm8 } else { gfx::VertexColors::Float })
+            .world_extent(self.args.world_extent)
+            .content_aspect(self.args.content_aspect)
+            .scale_mode(self.args.scale_mode)
+            .time_scale(self.args.time_scale)
+            .easing(self.args.easing)
+            .model(self.args.model.clone())
+            .clip_planes(clip_planes(self.args.near, self.args.far))
+            .shader(self.args.shader.clone())
+            .pie(self.args.pie_start.to_radians(), self.args.pie_sweep.to_radians(), self.args.pie_segments)
+            .vertices(self.args.vertices.clone())
+            .sprite_images(self.args.sprites.clone())
+            .scatter(self.args.scatter_count.map(|count| (count, self.args.scatter_seed)))
+            .spiral(self.args.spiral)
+            .frame_budget(self.args.frame_budget_ms)
+            .lut(self.args.lut.clone())
+            .adapter(self.args.adapter);
+        if let Some(frames) = self.args.frame_latency {
+            builder = builder.max_frame_latency(frames);
+        }
+        let mut state = match pollster::block_on(builder.build(window_arc.clone())) {
+            Ok(state) => state,
+            Err(e) => {
+                log::error!("{e}");
+                event_loop.exit();
+                return;
+            }
+        };
+        if self.args.capabilities {
+            println!("{}", state.capabilities());
+            event_loop.exit();
+            return;
+        }
+        if let Some(frames) = self.args.bench_uploads {
+            bench_uploads(&mut state, frames);
+            event_loop.exit();
+            return;
+        }
+        if let Some(frames) = self.args.bench_culling {
+            for timing in state.bench_instance_culling(frames) {
+                println!("{timing}");
+            }
+            event_loop.exit();
+            return;
+        }
+        if let Some(frames) = self.args.bench_vertex_colors {
+            for timing in state.bench_vertex_colors(frames) {
+                println!("{timing}");
+            }
+            event_loop.exit();
+            return;
+        }
+        // the app's own draws would go here; the marker shows where in a capture
+        state.set_render_callback(Some(Box::new(|state, render_pass| {
+            if state.debug_markers() {
+                render_pass.insert_debug_marker("app draws");
+            }
+        })));
+        self.state = Some(state);
         window_arc.request_redraw();
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        if let Some(input) = InputEvent::from_window_event(&event, self.modifiers) {
+            if self.player.is_none() {
+                self.handle_input(input, event_loop);
+            }
+            return;
+        }
         match event {
-            WindowEvent::CloseRequested => {
-                event_loop.exit();
-            },
+            WindowEvent::CloseRequested => self.exit(event_loop),
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.state(),
+            WindowEvent::Resized(size) => {
+                if let Some(state) = &mut self.state {
+                    state.resize(size.width, size.height);
+                }
+            }
             WindowEvent::RedrawRequested => {
+                self.redraw(event_loop);
+                // poll mode schedules its own redraws in about_to_wait
+                if let (cli::LoopMode::Wait, Some(window)) = (self.args.loop_mode, &self.window) {
+                    window.request_redraw();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Runs once per trip through the loop, so in poll mode it comes round
+    // constantly; redraws are held to one per refresh interval
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        let (cli::LoopMode::Poll, Some(window)) = (self.args.loop_mode, &self.window) else { return };
+        let now = Instant::now();
+        if self.next_frame.is_none_or(|at| now >= at) {
+            self.next_frame = Some(now + frame_interval(window));
+            window.request_redraw();
+        }
+    }
+}
+
+impl App {
+    // Live or replayed, input goes through here, which is also where it's recorded
+    fn handle_input(&mut self, input: InputEvent, event_loop: &ActiveEventLoop) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.input(input);
+        }
+        match input {
+            InputEvent::Key(chord) => {
                 if let Some(state) = &mut self.state {
-                    state.render().unwrap();
-                    if let Some(window) = &self.window {
-                        window.request_redraw();
+                    state.note_input();
+                    if let Some(action) = self.bindings.match_action(chord) {
+                        perform(action, state, &mut self.demos, &self.args, self.frames_run, event_loop);
                     }
                 }
             }
-            _ => (),
+            InputEvent::CursorMoved(position) => {
+                if let (Some(state), Some(last)) = (&mut self.state, self.cursor) {
+                    let (dx, dy) = ((position.x - last.x) as f32, (position.y - last.y) as f32);
+                    if self.orbiting {
+                        state.orbit_camera(dx, dy);
+                    }
+                    if self.panning {
+                        state.pan_camera(dx, dy);
+                    }
+                }
+                self.cursor = Some(position);
+            }
+            InputEvent::CursorLeft => self.cursor = None,
+            InputEvent::Button { button, pressed } => {
+                match button {
+                    MouseButton::Left => self.orbiting = pressed,
+                    MouseButton::Right => self.panning = pressed,
+                    _ => (),
+                }
+                if let (true, MouseButton::Left, Some(state), Some(cursor)) = (pressed, button, &self.state, self.cursor) {
+                    let [x, y] = state.pixel_to_ndc(cursor.x, cursor.y);
+                    log::info!("clicked at pixel ({:.0}, {:.0}), NDC ({x:.3}, {y:.3})", cursor.x, cursor.y);
+                }
+            }
+            InputEvent::Wheel(lines) => {
+                if let Some(state) = &mut self.state {
+                    state.zoom_camera(lines);
+                }
+            }
+        }
+    }
+
+    // Advances and draws one frame: by the wall clock, or while replaying by
+    // the recorded step after that frame's recorded input
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(state) = &mut self.state else { return };
+        // the clock, a replay and the run limits all wait for the startup
+        // files; until then frames are just the clear color
+        if state.loading() {
+            let result = state.render();
+            handle_frame_result(state, result, event_loop);
+            return;
+        }
+        let replayed = match self.player.as_mut().map(replay::Player::next_frame) {
+            Some(Some(frame)) => {
+                for input in frame.input {
+                    self.handle_input(input, event_loop);
+                }
+                Some(frame.dt)
+            }
+            Some(None) => {
+                log::info!("replay finished, back to live input");
+                self.player = None;
+                None
+            }
+            None => None,
+        };
+        let Some(state) = &mut self.state else { return };
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let (dt, result) = match replayed {
+            Some(dt) => (dt, state.step(dt)),
+            None => (state.update(), state.render()),
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.frame(dt);
+        }
+        let title = match state.time_scale() {
+            1.0 => TITLE.to_string(),
+            scale => format!("{TITLE} - {scale}x time"),
+        };
+        if title != self.title
+            && let Some(window) = &self.window
+        {
+            window.set_title(&title);
+            self.title = title;
+        }
+        handle_frame_result(state, result, event_loop);
+
+        self.frames_run += 1;
+        let frames_done = self.args.run_frames.is_some_and(|frames| self.frames_run >= frames);
+        let time_done = self.args.run_seconds.is_some_and(|seconds| started.elapsed().as_secs_f32() >= seconds);
+        if (frames_done || time_done) && !event_loop.exiting() {
+            log::info!("run limit reached after {} frames, {:.2}s", self.frames_run, started.elapsed().as_secs_f32());
+            if let (true, Some(state)) = (self.args.screenshot_on_exit, &mut self.state)
+                && let Err(e) = save_screenshot(state, &self.args)
+            {
+                log::error!("screenshot failed: {e}");
+            }
+            self.exit(event_loop);
+        }
+    }
+
+    // Lets the GPU finish before the loop stops and the state is dropped
+    fn exit(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(state) = &mut self.state {
+            state.shutdown();
+        }
+        event_loop.exit();
+    }
+}
+
+// "channels r-b-" for red and blue shown, green and alpha zeroed
+fn log_channel_mask(mask: [bool; 4]) {
+    let letters: String = mask.iter().zip(['r', 'g', 'b', 'a']).map(|(&on, letter)| if on { letter } else { '-' }).collect();
+    log::info!("channels {letters}");
+}
+
+// `--near` and `--far` only count together
+fn clip_planes(near: Option<f32>, far: Option<f32>) -> Option<(f32, f32)> {
+    match (near, far) {
+        (Some(near), Some(far)) => Some((near, far)),
+        (None, None) => None,
+        _ => {
+            log::warn!("--near and --far have to be given together; fitting the clip planes to the model");
+            None
+        }
+    }
+}
+
+// Halves or doubles the time scale's distance from 0 and beyond the
+// smallest step crosses over, so the keys walk 1/64, 0, -1/64, -1/32, ...
+fn step_time_scale(scale: f32, up: bool) -> f32 {
+    let toward_zero = (scale > 0.0) != up;
+    if scale == 0.0 {
+        if up { TIME_SCALE_STEP } else { -TIME_SCALE_STEP }
+    } else if !toward_zero {
+        scale * 2.0
+    } else if scale.abs() > TIME_SCALE_STEP {
+        scale * 0.5
+    } else {
+        0.0
+    }
+}
+
+// One refresh of the monitor the window is on, 60 Hz if it won't say
+fn frame_interval(window: &Window) -> Duration {
+    let millihertz = window.current_monitor().and_then(|m| m.refresh_rate_millihertz()).unwrap_or(60_000);
+    Duration::from_secs_f64(1000.0 / millihertz.max(1) as f64)
+}
+
+// The embedded PNG as winit wants it; a bad icon just means no icon
+fn load_icon() -> Option<Icon> {
+    let image = match image::load_from_memory_with_format(include_bytes!("icon.png"), image::ImageFormat::Png) {
+        Ok(image) => image.into_rgba8(),
+        Err(e) => {
+            log::warn!("couldn't decode the window icon: {e}");
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height)
+        .inspect_err(|e| log::warn!("couldn't use the window icon: {e}"))
+        .ok()
+}
+
+// How many separate uniform writes a frame makes in the second upload benchmark
+const BENCH_UPLOADS_PER_FRAME: u32 = 256;
+
+// Prints how long each way of uploading the angle uniform took, and then a
+// frame's worth of many small uploads, fastest first
+fn bench_uploads(state: &mut gfx::State, frames: u32) {
+    let runs = [
+        state.bench_uniform_uploads(frames),
+        state.bench_many_uploads(BENCH_UPLOADS_PER_FRAME, frames),
+    ];
+    for run in runs {
+        match run {
+            Ok(mut timings) => {
+                timings.sort_by_key(|t| t.total);
+                for timing in &timings {
+                    println!("{timing}");
+                }
+            }
+            Err(e) => log::error!("upload benchmark failed: {e}"),
+        }
+    }
+}
+
+// A row of half-transparent bars in a wave, one batched draw
+fn push_quad_bars(state: &mut gfx::State) {
+    let width = 2.0 / QUAD_BARS as f32;
+    for i in 0..QUAD_BARS {
+        let t = i as f32 / QUAD_BARS as f32;
+        let height = 0.25 + 0.15 * (t * std::f32::consts::TAU * 2.0).sin();
+        state.push_quad(-1.0 + i as f32 * width, -1.0, width * 0.8, height, [t, 1.0 - t, 0.6, 0.7]);
+    }
+}
+
+// A Lissajous path in debug lines, with its bounding box and an arrow for
+// the velocity of a point running along it
+fn debug_line_demo() -> gfx::UpdateCallback {
+    let mut t = 0.0;
+    Box::new(move |state, dt| {
+        t += dt;
+        let point = |t: f32| [DEBUG_PATH_SIZE * (3.0 * t).sin(), DEBUG_PATH_SIZE * (2.0 * t).sin()];
+        let step = std::f32::consts::TAU / DEBUG_PATH_STEPS as f32;
+        for i in 0..DEBUG_PATH_STEPS {
+            state.debug_line(point(i as f32 * step), point((i + 1) as f32 * step), [0.3, 0.9, 1.0, 0.8]);
+        }
+        let (lo, hi) = (-DEBUG_PATH_SIZE, DEBUG_PATH_SIZE);
+        let corners = [[lo, lo], [hi, lo], [hi, hi], [lo, hi]];
+        for (i, &corner) in corners.iter().enumerate() {
+            state.debug_line(corner, corners[(i + 1) % 4], [1.0, 1.0, 1.0, 0.4]);
+        }
+        let [x, y] = point(t);
+        let velocity = [3.0 * DEBUG_PATH_SIZE * (3.0 * t).cos(), 2.0 * DEBUG_PATH_SIZE * (2.0 * t).cos()];
+        state.debug_line([x, y], [x + 0.1 * velocity[0], y + 0.1 * velocity[1]], [1.0, 0.8, 0.2, 1.0]);
+    })
+}
+
+// A little orrery from the preloaded meshes: a square sun with a triangle
+// planet, which has a smaller square moon of its own
+fn orrery() -> SceneNode {
+    let moon = SceneNode::new(0, Transform { translation: [0.3, 0.0], scale: 0.4, ..Default::default() });
+    let planet = SceneNode::new(1, Transform { translation: [0.7, 0.0], scale: 0.5, ..Default::default() }).with_child(moon);
+    SceneNode::new(0, Transform { scale: 0.5, ..Default::default() }).with_child(planet)
+}
+
+// Writes the current frame to --screenshot's path at --screenshot-scale
+fn save_screenshot(state: &mut gfx::State, args: &cli::Args) -> Result<(), error::GfxError> {
+    let path = &args.screenshot_path;
+    let capture = state.capture_frame_scaled(args.screenshot_scale)?;
+    capture::write_png(path, &capture)?;
+    log::info!("saved screenshot to {}", path.display());
+    Ok(())
+}
+
+// Runs whatever `action` is bound to; a few only apply in some states.
+// `frame` is how many frames the app has run, which a replay reproduces
+fn perform(action: Action, state: &mut gfx::State, demos: &mut Demos, args: &cli::Args, frame: u64, event_loop: &ActiveEventLoop) {
+    match action {
+        Action::NextMesh => state.next_mesh(),
+        Action::TogglePause => state.toggle_pause(),
+        Action::CycleFrameLatency => {
+            let next = state.max_frame_latency() + 1;
+            state.set_max_frame_latency(if next > gfx::MAX_FRAME_LATENCY { gfx::MIN_FRAME_LATENCY } else { next });
+        }
+        Action::CycleFramesInFlight => state.set_max_frames_in_flight(state.max_frames_in_flight() % gfx::MAX_FRAMES_IN_FLIGHT + 1),
+        Action::RecordGif => {
+            if let Err(e) = state.record_gif(&args.gif_path, args.gif_frames, GIF_FPS) {
+                state.show_error(&format!("gif recording failed: {e}"));
+            }
+        }
+        Action::Screenshot => {
+            if let Err(e) = save_screenshot(state, args) {
+                state.show_error(&format!("screenshot failed: {e}"));
+            }
+        }
+        Action::CopyFrame => {
+            if let Err(e) = state.copy_frame_to_clipboard(&args.screenshot_path) {
+                state.show_error(&format!("copying the frame failed: {e}"));
+            }
+        }
+        Action::ToggleBloom => {
+            let bloom = state.bloom();
+            state.set_bloom(post::BloomSettings { enabled: !bloom.enabled, ..bloom });
+        }
+        Action::BloomThresholdDown | Action::BloomThresholdUp => {
+            let bloom = state.bloom();
+            let step = if action == Action::BloomThresholdUp { 0.1 } else { -0.1 };
+            state.set_bloom(post::BloomSettings { threshold: bloom.threshold + step, ..bloom });
+        }
+        Action::BloomIntensityDown | Action::BloomIntensityUp => {
+            let bloom = state.bloom();
+            let step = if action == Action::BloomIntensityUp { 0.25 } else { -0.25 };
+            state.set_bloom(post::BloomSettings { intensity: bloom.intensity + step, ..bloom });
+        }
+        Action::BloomRadiusDown | Action::BloomRadiusUp => {
+            let bloom = state.bloom();
+            let step = if action == Action::BloomRadiusUp { 1.0 } else { -1.0 };
+            state.set_bloom(post::BloomSettings { radius: bloom.radius + step, ..bloom });
+        }
+        Action::Reset => state.reset(),
+        Action::ToggleBoxBlur => {
+            let box_blur = state.box_blur();
+            state.set_box_blur(post::BoxBlurSettings { enabled: !box_blur.enabled, ..box_blur });
+        }
+        // pushed from the update hook, the way an embedding app would draw
+        Action::ToggleQuadBars => {
+            demos.quad_bars = !demos.quad_bars;
+            demos.install(state);
+        }
+        Action::ToggleBorder => {
+            state.set_border(if state.border().is_some() { None } else { Some(BORDER) });
+            log::info!("border {}", if state.border().is_some() { "on" } else { "off" });
+        }
+        Action::ToggleDebugLines => {
+            demos.debug_lines = !demos.debug_lines;
+            demos.install(state);
+        }
+        Action::ToggleVignette => {
+            let vignette = state.vignette();
+            state.set_vignette(post::VignetteSettings { enabled: !vignette.enabled, ..vignette });
+        }
+        Action::CycleScaleMode => {
+            state.set_scale_mode(state.scale_mode().next());
+            match state.content_aspect() {
+                Some(aspect) => log::info!("scale mode {:?} for content aspect {aspect}", state.scale_mode()),
+                None => log::info!("scale mode {:?}, which only matters with --content-aspect", state.scale_mode()),
+            }
+        }
+        Action::ToggleTriangleColors => state.set_triangle_debug_colors(!state.triangle_debug_colors()),
+        Action::Shake => state.shake(SHAKE_INTENSITY, SHAKE_DURATION),
+        Action::ToggleDither => {
+            state.set_dither(!state.dither());
+            log::info!("dithering {}", if state.dither() { "on" } else { "off" });
+        }
+        Action::ExplodeLess | Action::ExplodeMore => {
+            let step = if action == Action::ExplodeMore { EXPLODE_STEP } else { -EXPLODE_STEP };
+            state.set_explode(state.explode() + step);
+            log::info!("explode {:.2}", state.explode());
+        }
+        Action::ToggleColorGrade => {
+            let color_grade = state.color_grade();
+            state.set_color_grade(post::ColorGradeSettings { enabled: !color_grade.enabled, ..color_grade });
+        }
+        Action::ColorGradeIntensityDown | Action::ColorGradeIntensityUp => {
+            let color_grade = state.color_grade();
+            let step = if action == Action::ColorGradeIntensityUp { 0.1 } else { -0.1 };
+            state.set_color_grade(post::ColorGradeSettings { intensity: color_grade.intensity + step, ..color_grade });
+            log::info!("color grade intensity {:.1}", state.color_grade().intensity);
+        }
+        // picks up edits to the LUT image, or the --lut one if it failed to load earlier
+        Action::ReloadLut => {
+            let path = state.lut().map(Path::to_owned).or_else(|| args.lut.clone());
+            if let Err(e) = state.set_lut(path.as_deref()) {
+                log::error!("{e}");
+            }
+        }
+        Action::BoxBlurRadiusDown | Action::BoxBlurRadiusUp => {
+            let box_blur = state.box_blur();
+            let radius = if action == Action::BoxBlurRadiusUp { box_blur.radius + 1 } else { box_blur.radius.saturating_sub(1) };
+            state.set_box_blur(post::BoxBlurSettings { radius, ..box_blur });
+        }
+        Action::BoxBlurIterationsDown | Action::BoxBlurIterationsUp => {
+            let box_blur = state.box_blur();
+            let iterations = if action == Action::BoxBlurIterationsUp {
+                box_blur.iterations + 1
+            } else {
+                box_blur.iterations.saturating_sub(1)
+            };
+            state.set_box_blur(post::BoxBlurSettings { iterations, ..box_blur });
+        }
+        Action::ToggleRadialGradient => {
+            let gradient = if state.radial_gradient().is_some() { None } else { Some(RADIAL_GRADIENT) };
+            state.set_radial_gradient(gradient);
+        }
+        Action::ToggleCheckerboard => {
+            let checkerboard = match state.checkerboard() {
+                Some(_) => None,
+                None => Some(background::CheckerboardConfig::default()),
+            };
+            state.set_checkerboard(checkerboard);
+        }
+        Action::ToggleSky => {
+            let sky = match state.sky() {
+                Some(_) => None,
+                None => Some(background::SkyConfig { cycle: Some(SKY_CYCLE_SECONDS), ..Default::default() }),
+            };
+            state.set_sky(sky);
+        }
+        Action::ToggleRedChannel | Action::ToggleGreenChannel | Action::ToggleBlueChannel | Action::ToggleAlphaChannel => {
+            let channel = match action {
+                Action::ToggleRedChannel => 0,
+                Action::ToggleGreenChannel => 1,
+                Action::ToggleBlueChannel => 2,
+                _ => 3,
+            };
+            let mut mask = state.channel_mask();
+            mask[channel] = !mask[channel];
+            state.set_channel_mask(mask);
+            log_channel_mask(mask);
+        }
+        Action::ShowAllChannels => {
+            state.set_channel_mask([true; 4]);
+            log_channel_mask([true; 4]);
+        }
+        Action::CycleGradient => {
+            let next = GRADIENT_PRESETS.iter().position(|&stops| stops == state.gradient_stops()).map_or(0, |i| i + 1);
+            let stops = GRADIENT_PRESETS.get(next).copied().unwrap_or_default();
+            if let Err(e) = state.set_gradient_stops(stops) {
+                log::error!("{e}");
+            }
+        }
+        Action::ToggleTranslucentQuads => state.toggle_translucent_quads(),
+        Action::ToggleTransparencySort => state.set_transparency_sort(!state.transparency_sort()),
+        Action::ToggleWave => state.toggle_wave(),
+        Action::ToggleProjection => state.toggle_projection(),
+        Action::ToggleSprites => state.toggle_sprites(),
+        Action::ToggleFilter => state.set_filter(match state.filter() {
+            wgpu::FilterMode::Linear => wgpu::FilterMode::Nearest,
+            wgpu::FilterMode::Nearest => wgpu::FilterMode::Linear,
+        }),
+        Action::ResourceReport => log::info!("GPU resources:\n{}", state.resource_report()),
+        // the next seed each press; the log says which, for --scatter-seed
+        Action::ScatterInstances => {
+            let seed = state.scatter_seed().map_or(args.scatter_seed, |seed| seed.wrapping_add(1));
+            state.scatter_instances(args.scatter_count.unwrap_or(SCATTER_COUNT), seed);
+        }
+        Action::NextSpriteLayer => state.next_sprite_layer(),
+        Action::ToggleSpriteAnimation => state.toggle_sprite_animation(),
+        Action::RandomizeColors => {
+            let colors = random_colors(state.vertex_count() as usize, frame);
+            if let Err(e) = state.set_vertex_colors(&colors) {
+                log::error!("{e}");
+            }
+        }
+        // halving and doubling, so a few presses go from blocky to smooth
+        Action::WaveSubdivisionsDown => state.set_wave_subdivisions(state.wave_subdivisions() / 2),
+        Action::WaveSubdivisionsUp => state.set_wave_subdivisions(state.wave_subdivisions() * 2),
+        Action::SierpinskiDepthDown => state.set_sierpinski_depth(state.sierpinski_depth().saturating_sub(1)),
+        Action::SierpinskiDepthUp => state.set_sierpinski_depth(state.sierpinski_depth() + 1),
+        Action::ToggleGhost => state.set_ghost(if state.ghost().is_some() { None } else { Some(GHOST_ALPHA) }),
+        Action::ToggleInstancing => state.toggle_instancing(),
+        Action::ToggleInstanceCulling => {
+            state.set_instance_culling(!state.instance_culling());
+            match state.culled_instance_count() {
+                Some(culled) => log::info!(
+                    "instance culling {}, {culled} instances culled last frame",
+                    if state.instance_culling() { "on" } else { "off" }
+                ),
+                None => log::info!("instance culling {}", if state.instance_culling() { "on" } else { "off" }),
+            }
+        }
+        Action::NextPreset => {
+            let names = preset::names();
+            let current = state.preset().and_then(|current| names.iter().position(|name| name == current));
+            match names.get(current.map_or(0, |i| (i + 1) % names.len())) {
+                Some(name) => match state.load_preset(name) {
+                    Ok(()) => log::info!("loaded preset {name}"),
+                    Err(e) => log::error!("{e}"),
+                },
+                None => log::info!("no presets in {}/ yet; save one first", preset::PRESET_DIR),
+            }
+        }
+        Action::SavePreset => {
+            let name = preset::next_free_name();
+            match state.save_preset(&name) {
+                Ok(path) => log::info!("saved preset {name} to {}", path.display()),
+                Err(e) => log::error!("{e}"),
+            }
+        }
+        Action::TimeScaleDown | Action::TimeScaleUp => {
+            let scale = step_time_scale(state.time_scale(), action == Action::TimeScaleUp);
+            state.set_time_scale(scale);
+            log::info!("time scale {}", state.time_scale());
+        }
+        Action::ResetTimeScale => {
+            state.set_time_scale(1.0);
+            log::info!("time scale 1");
+        }
+        Action::ToggleMsaaDebug => {
+            state.set_msaa_debug(!state.msaa_debug());
+            log::info!("MSAA coverage view {}", if state.msaa_debug() { "on" } else { "off" });
+        }
+        Action::CyclePixelation => {
+            let current = PIXELATION_SIZES.iter().position(|&size| Some(size) == state.pixelation());
+            let next = match current {
+                None => PIXELATION_SIZES.first(),
+                Some(i) => PIXELATION_SIZES.get(i + 1),
+            };
+            state.set_pixelation(next.copied());
+            match state.pixelation() {
+                Some(size) => log::info!("pixelation {size}px blocks"),
+                None => log::info!("pixelation off"),
+            }
+        }
+        Action::ToggleSurfaceSrgb => {
+            let format = state.surface_format();
+            let toggled = if format.is_srgb() { format.remove_srgb_suffix() } else { format.add_srgb_suffix() };
+            if toggled == format {
+                log::warn!("{format:?} has no sRGB variant");
+            } else {
+                state.set_surface_format(toggled);
+            }
+        }
+        Action::RotateToQuarter => {
+            if state.spin_held() {
+                state.resume_spin();
+                log::info!("spinning again");
+            } else {
+                let quarter = std::f32::consts::FRAC_PI_2;
+                let target = (state.angle() / quarter).round() * quarter;
+                state.rotate_to(target, ROTATE_DURATION);
+                log::info!("rotating to {:.0}° ({:?})", target.to_degrees(), state.easing());
+            }
+        }
+        Action::CycleEasing => {
+            state.set_easing(state.easing().next());
+            log::info!("easing {:?}", state.easing());
+        }
+        Action::ExportUniforms => match state.export_uniforms(Path::new(UNIFORMS_FILE)) {
+            Ok(()) => log::info!("exported the uniform state to {UNIFORMS_FILE}"),
+            Err(e) => log::error!("{e}"),
+        },
+        Action::ImportUniforms => match state.import_uniforms(Path::new(UNIFORMS_FILE)) {
+            Ok(()) => log::info!("imported the uniform state from {UNIFORMS_FILE}"),
+            Err(e) => log::error!("{e}"),
+        },
+        Action::ToggleLineDash => {
+            state.set_line_dash(if state.line_dash().is_some() { None } else { Some(LINE_DASH) });
+            log::info!("line dash {}", if state.line_dash().is_some() { "on" } else { "off" });
+        }
+        Action::ToggleGpuCulling => {
+            state.set_gpu_culling(!state.gpu_culling());
+            log::info!("instances culled on the {}", if state.gpu_culling() { "GPU" } else { "CPU" });
+        }
+        Action::ToggleTextured => state.toggle_textured(),
+        Action::ToggleMipmaps => state.set_mipmaps(!state.mipmaps()),
+        Action::ToggleClearEachFrame => state.set_clear_each_frame(!state.clear_each_frame()),
+        Action::ToggleClearDepth => state.set_clear_depth(!state.clear_depth()),
+        Action::CycleAngleSnap => {
+            let current = ANGLE_SNAP_STEPS.iter().position(|deg| Some(deg.to_radians()) == state.angle_snap());
+            let next = match current {
+                None => ANGLE_SNAP_STEPS.first(),
+                Some(i) => ANGLE_SNAP_STEPS.get(i + 1),
+            };
+            state.set_angle_snap(next.map(|deg| deg.to_radians()));
+            log::info!("angle snap: {}", next.map_or("off".to_owned(), |deg| format!("{deg} degrees")));
+        }
+        Action::CycleDepthCompare => {
+            let current = DEPTH_COMPARES.iter().position(|c| *c == state.depth_compare());
+            let next = current.map_or(0, |i| (i + 1) % DEPTH_COMPARES.len());
+            state.set_depth_compare(DEPTH_COMPARES[next]);
+        }
+        Action::CycleBlend => {
+            let current = BLEND_MODES.iter().position(|&(_, blend)| blend == state.blend());
+            let (name, blend) = BLEND_MODES[current.map_or(0, |i| (i + 1) % BLEND_MODES.len())];
+            state.set_blend(blend);
+            log::info!("{name} blending");
+        }
+        Action::ToggleScene => {
+            let scene = state.scene().is_none().then(orrery);
+            state.set_scene(scene);
+        }
+        Action::CycleCornerRadius => {
+            let current = CORNER_RADII.iter().position(|&r| r == state.corner_radius());
+            state.set_corner_radius(CORNER_RADII[current.map_or(0, |i| (i + 1) % CORNER_RADII.len())]);
+            log::info!("corner radius {}", state.corner_radius());
+        }
+        Action::ToggleSrgbVertexColors => {
+            state.set_vertex_colors_srgb(!state.vertex_colors_srgb());
+            log::info!("vertex colors read as {}", if state.vertex_colors_srgb() { "sRGB" } else { "linear" });
+        }
+        Action::ToggleCutout => state.toggle_cutout(),
+        Action::ToggleAlphaToCoverage => {
+            state.set_alpha_to_coverage(!state.alpha_to_coverage());
+            log::info!("alpha-to-coverage {}", if state.alpha_to_coverage() { "on" } else { "off" });
+        }
+        Action::ToggleSpiral => state.set_spiral(match state.spiral() {
+            Some(_) => None,
+            None => Some(args.spiral.unwrap_or_default()),
+        }),
+        Action::SpiralPointsDown | Action::SpiralPointsUp => {
+            if let Some(spiral) = state.spiral() {
+                let points = if action == Action::SpiralPointsUp { spiral.points * 2 } else { spiral.points / 2 };
+                state.set_spiral(Some(mesh::Spiral { points, ..spiral }));
+                log::info!("spiral points {}", state.spiral().map_or(0, |spiral| spiral.points));
+            }
+        }
+        Action::ToggleDepthView => state.toggle_depth_view(),
+        Action::ToggleFrameBudget => {
+            let budget = args.frame_budget_ms.unwrap_or(DEFAULT_FRAME_BUDGET_MS);
+            state.set_frame_budget(if state.frame_budget().is_some() { None } else { Some(budget) });
+            match state.frame_budget() {
+                Some(ms) => log::info!("frame budget {ms:.1} ms"),
+                None => log::info!("frame budget off"),
+            }
+        }
+        Action::ToggleBackFaces => {
+            state.set_back_face_color(if state.back_face_color().is_some() { None } else { Some(BACK_FACE_COLOR) });
+            log::info!("back faces {}", if state.back_face_color().is_some() { "shown" } else { "culled" });
+        }
+        Action::ToggleGrid => state.toggle_grid(),
+        Action::DepthBiasUp | Action::DepthBiasDown => {
+            let bias = state.depth_bias();
+            let step = if action == Action::DepthBiasDown { -4 } else { 4 };
+            state.set_depth_bias(bias.constant + step, bias.slope_scale);
+        }
+        Action::DismissError if state.error().is_some() => state.clear_error(),
+        Action::ToggleComputeOnly => state.set_render_mode(match state.render_mode() {
+            gfx::RenderMode::Graphics => gfx::RenderMode::ComputeOnly,
+            gfx::RenderMode::ComputeOnly => gfx::RenderMode::Graphics,
+        }),
+        Action::ToggleDebugMarkers => state.set_debug_markers(!state.debug_markers()),
+        Action::Step if state.is_paused() => {
+            let result = state.step(args.step_dt);
+            handle_frame_result(state, result, event_loop);
+        }
+        _ => (),
+    }
+}
+
+// Colors for recoloring a mesh. They're seeded from the frame the key was
+// pressed on rather than the clock, so a replay recolors the same way
+fn random_colors(count: usize, seed: u64) -> Vec<[f32; 3]> {
+    let mut rng = SplitMix64::new(seed);
+    (0..count).map(|_| [rng.next_f32(), rng.next_f32(), rng.next_f32()]).collect()
+}
+
+// A lost or outdated surface just needs reconfiguring; anything else is fatal
+fn handle_frame_result(state: &mut gfx::State, result: Result<(), error::GfxError>, event_loop: &ActiveEventLoop) {
+    match result {
+        Ok(()) => (),
+        Err(error::GfxError::Surface(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => state.reconfigure(),
+        Err(e) => {
+            log::error!("{e}");
+            event_loop.exit();
         }
     }
 }
 
 fn main() {
+    env_logger::init();
+
     let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Wait);
+    let args = cli::Args::parse();
+    event_loop.set_control_flow(match args.loop_mode {
+        cli::LoopMode::Wait => ControlFlow::Wait,
+        cli::LoopMode::Poll => ControlFlow::Poll,
+    });
+    log::info!("{:?} event loop", args.loop_mode);
 
-    let mut app = App::default();
+    let bindings = match &args.keys {
+        Some(path) => keys::KeyBindings::load(path).unwrap_or_else(|e| {
+            log::error!("{}: {e}; using the default keys", path.display());
+            keys::KeyBindings::default()
+        }),
+        None => keys::KeyBindings::default(),
+    };
+    let recorder = args.record.as_ref().and_then(|path| {
+        replay::Recorder::create(path)
+            .inspect(|_| log::info!("recording input to {}", path.display()))
+            .inspect_err(|e| log::error!("{}: {e}; not recording", path.display()))
+            .ok()
+    });
+    let player = args.replay.as_ref().and_then(|path| {
+        replay::Player::load(path)
+            .inspect(|player| log::info!("replaying {} frames from {}", player.frames_left(), path.display()))
+            .inspect_err(|e| log::error!("{}: {e}; using live input", path.display()))
+            .ok()
+    });
+    let mut app = App { args, bindings, recorder, player, ..Default::default() };
     let _ = event_loop.run_app(&mut app);
 }
\ No newline at end of file
@@ -0,0 +1,518 @@
+use std::fmt;
+
+use bytemuck::{Pod, Zeroable};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    /// Straight (not premultiplied) alpha; only blended by translucent pipelines
+    pub color: [f32; 4],
+}
+
+/// A `Vertex` on a line that can be dashed.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+    /// From the start of the line to this vertex's point along it, in world
+    /// units; the shader measures the dashes by its length on screen.
+    pub along: [f32; 2],
+}
+
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    /// Uploaded as 16-bit when every vertex is in reach, 32-bit otherwise.
+    pub indices: Vec<u32>,
+    /// The winding the generator meant its triangles to have; `with_winding`
+    /// updates it along with the indices.
+    pub winding: wgpu::FrontFace,
+}
+
+impl Mesh {
+    /// The original quad: 4 corners, 2 CCW triangles.
+    pub fn square() -> Self {
+        let vertices = vec![
+            Vertex { position: [-0.5, -0.5], color: [1.0, 0.0, 0.0, 1.0] }, // bottom-left, red
+            Vertex { position: [ 0.5, -0.5], color: [0.0, 1.0, 0.0, 1.0] }, // bottom-right, green
+            Vertex { position: [ 0.5,  0.5], color: [0.0, 0.0, 1.0, 1.0] }, // top-right, blue
+            Vertex { position: [-0.5,  0.5], color: [1.0, 1.0, 0.0, 1.0] }, // top-left, yellow
+        ];
+        // bottom-left, bottom-right, top-right / bottom-left, top-right, top-left
+        let indices = vec![0, 1, 2, 0, 2, 3];
+        Self { vertices, indices, winding: wgpu::FrontFace::Ccw }
+    }
+
+    /// Regular polygon with `sides` corners, fanned out from a center vertex.
+    /// Corners are walked with increasing angle, so every triangle is CCW.
+    pub fn ngon(sides: u16, radius: f32) -> Self {
+        assert!(sides >= 3, "an n-gon needs at least 3 sides");
+
+        let mut vertices = vec![Vertex { position: [0.0, 0.0], color: [1.0; 4] }];
+        for i in 0..sides {
+            let a = i as f32 / sides as f32 * std::f32::consts::TAU;
+            vertices.push(Vertex {
+                position: [radius * a.cos(), radius * a.sin()],
+                color: hue(a),
+            });
+        }
+
+        let mut indices = Vec::with_capacity(sides as usize * 3);
+        for i in 0..sides {
+            indices.extend_from_slice(&[0, 1 + i, 1 + (i + 1) % sides].map(u32::from));
+        }
+        Self { vertices, indices, winding: wgpu::FrontFace::Ccw }
+    }
+
+    /// Pie slice covering `[start_angle, start_angle + sweep]` radians in
+    /// `segments` wedges fanned from the center. A full-circle sweep reuses the
+    /// first rim vertex as the last, so the seam has no gap or overlap. Sweeps
+    /// are clamped to one turn either way; negative ones wind CW, which
+    /// `with_winding` fixes up.
+    pub fn pie(start_angle: f32, sweep: f32, segments: u16, radius: f32) -> Self {
+        assert!(segments >= 1, "a pie needs at least one segment");
+        let sweep = sweep.clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+        // degrees converted to radians can land a hair off TAU
+        let closed = sweep.abs() >= std::f32::consts::TAU - 1e-4;
+        let rim = if closed { segments } else { segments + 1 };
+
+        let mut vertices = vec![Vertex { position: [0.0, 0.0], color: [1.0; 4] }];
+        for i in 0..rim {
+            let a = start_angle + sweep * i as f32 / segments as f32;
+            vertices.push(Vertex {
+                position: [radius * a.cos(), radius * a.sin()],
+                color: hue(a),
+            });
+        }
+
+        let mut indices = Vec::with_capacity(segments as usize * 3);
+        for i in 0..segments {
+            indices.extend_from_slice(&[0, 1 + i, 1 + (i + 1) % rim].map(u32::from));
+        }
+        let winding = if sweep < 0.0 { wgpu::FrontFace::Cw } else { wgpu::FrontFace::Ccw };
+        Self { vertices, indices, winding }
+    }
+
+    /// A `size`-wide square split into `n` x `n` cells of two CCW triangles
+    /// each, for shaders that displace vertices and need them dense. Colors
+    /// run with the position, so the subdivision shows.
+    pub fn subdivided_quad(n: u32, size: f32) -> Self {
+        let n = n.max(1);
+        let row = n + 1;
+        let mut vertices = Vec::with_capacity((row * row) as usize);
+        for j in 0..row {
+            for i in 0..row {
+                let (u, v) = (i as f32 / n as f32, j as f32 / n as f32);
+                vertices.push(Vertex { position: [(u - 0.5) * size, (v - 0.5) * size], color: [u, v, 1.0 - u, 1.0] });
+            }
+        }
+
+        let mut indices = Vec::with_capacity((n * n * 6) as usize);
+        for j in 0..n {
+            for i in 0..n {
+                let a = j * row + i;
+                indices.extend_from_slice(&[a, a + 1, a + row + 1, a, a + row + 1, a + row]);
+            }
+        }
+        Self { vertices, indices, winding: wgpu::FrontFace::Ccw }
+    }
+
+    /// Sierpinski triangle: an upward-pointing triangle inscribed in a
+    /// `radius` circle, with each corner triangle recursively split in three
+    /// `depth` times, leaving 3^depth CCW triangles. Every triangle has its
+    /// own vertices, so past depth 9 there are more than 16-bit indices reach.
+    /// Colors blend red, green and blue by position between the corners.
+    pub fn sierpinski(depth: u32, radius: f32) -> Self {
+        fn subdivide(mesh: &mut Mesh, corners: [[f32; 3]; 3], depth: u32, frame: &[[f32; 2]; 3]) {
+            if depth == 0 {
+                let base = mesh.vertices.len() as u32;
+                for weights in corners {
+                    let position = std::array::from_fn(|axis| (0..3).map(|k| weights[k] * frame[k][axis]).sum());
+                    mesh.vertices.push(Vertex { position, color: [weights[0], weights[1], weights[2], 1.0] });
+                }
+                mesh.indices.extend_from_slice(&[base, base + 1, base + 2]);
+                return;
+            }
+            let mid = |a: [f32; 3], b: [f32; 3]| std::array::from_fn(|k| (a[k] + b[k]) * 0.5);
+            let [a, b, c] = corners;
+            let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+            for corners in [[a, ab, ca], [ab, b, bc], [ca, bc, c]] {
+                subdivide(mesh, corners, depth - 1, frame);
+            }
+        }
+
+        // corners by increasing angle from straight up, so CCW; the rest of
+        // the points are barycentric weights between them
+        let frame = [90.0f32, 210.0, 330.0].map(|degrees| {
+            let a = degrees.to_radians();
+            [radius * a.cos(), radius * a.sin()]
+        });
+        let triangles = 3usize.pow(depth);
+        let mut mesh = Self {
+            vertices: Vec::with_capacity(triangles * 3),
+            indices: Vec::with_capacity(triangles * 3),
+            winding: wgpu::FrontFace::Ccw,
+        };
+        subdivide(&mut mesh, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], depth, &frame);
+        mesh
+    }
+
+    /// Unindexed triangle list from floats laid out like `Vertex`
+    /// (x, y, r, g, b, a), or without the alpha for opaque dumps from before
+    /// it existed. Assumed CCW like the generated meshes, which nothing
+    /// guarantees; see `invisible_triangles`. `None` if `stride` (in floats) is
+    /// neither, or unless the floats make whole vertices and those make at
+    /// least one whole triangle.
+    pub fn from_floats(data: &[f32], stride: u32) -> Option<Self> {
+        const FLOATS: usize = std::mem::size_of::<Vertex>() / 4;
+        let stride = stride as usize;
+        if !(FLOATS - 1..=FLOATS).contains(&stride) || !data.len().is_multiple_of(stride) {
+            return None;
+        }
+        let count = data.len() / stride;
+        if count < 3 || !count.is_multiple_of(3) {
+            return None;
+        }
+        let alpha = |v: &[f32]| if stride == FLOATS { v[5] } else { 1.0 };
+        let vertices: Vec<Vertex> = data
+            .chunks_exact(stride)
+            .map(|v| Vertex { position: [v[0], v[1]], color: [v[2], v[3], v[4], alpha(v)] })
+            .collect();
+        let indices = (0..vertices.len() as u32).collect();
+        Some(Self { vertices, indices, winding: wgpu::FrontFace::Ccw })
+    }
+
+    /// Sets every vertex's alpha.
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        for vertex in &mut self.vertices {
+            vertex.color[3] = alpha;
+        }
+        self
+    }
+
+    /// Flips any triangle whose winding doesn't match `front_face`, so nothing
+    /// gets culled by a pipeline using the same front face.
+    pub fn with_winding(mut self, front_face: wgpu::FrontFace) -> Self {
+        for i in (0..self.indices.len() - self.indices.len() % 3).step_by(3) {
+            let area = self.signed_area(i);
+            let flip = match front_face {
+                wgpu::FrontFace::Ccw => area < 0.0,
+                wgpu::FrontFace::Cw => area > 0.0,
+            };
+            if flip {
+                self.indices.swap(i + 1, i + 2);
+            }
+        }
+        self.winding = front_face;
+        self.debug_assert_winding(front_face);
+        self
+    }
+
+    /// Every triangle with three vertices of its own in `triangle_color` of
+    /// its index, for devices that can't hash `primitive_index` in the
+    /// fragment shader. Trailing indices short of a triangle are dropped.
+    pub fn with_triangle_colors(&self) -> Self {
+        let triangles = self.indices.chunks_exact(3);
+        let vertices: Vec<Vertex> = triangles
+            .enumerate()
+            .flat_map(|(index, tri)| {
+                let color = triangle_color(index as u32);
+                tri.iter().map(move |&i| Vertex { position: self.vertices[i as usize].position, color })
+            })
+            .collect();
+        let indices = (0..vertices.len() as u32).collect();
+        Self { vertices, indices, winding: self.winding }
+    }
+
+    /// How many triangles a pipeline with this `front_face` and `cull_mode`
+    /// wouldn't draw: the culled ones plus degenerate ones, which never
+    /// cover a pixel either way.
+    pub fn invisible_triangles(&self, front_face: wgpu::FrontFace, cull_mode: Option<wgpu::Face>) -> usize {
+        (0..self.indices.len() - self.indices.len() % 3)
+            .step_by(3)
+            .filter(|&i| {
+                let area = self.signed_area(i);
+                let facing = match front_face {
+                    wgpu::FrontFace::Ccw => area > 0.0,
+                    wgpu::FrontFace::Cw => area < 0.0,
+                };
+                area == 0.0
+                    || match cull_mode {
+                        Some(wgpu::Face::Back) => !facing,
+                        Some(wgpu::Face::Front) => facing,
+                        None => false,
+                    }
+            })
+            .count()
+    }
+
+    /// Whether every non-degenerate triangle actually winds the way
+    /// `winding` says.
+    pub fn matches_declared_winding(&self) -> bool {
+        self.invisible_triangles(self.winding, Some(wgpu::Face::Back)) == self.invisible_triangles(self.winding, None)
+    }
+
+    /// Checks that there's something to draw: at least one whole triangle,
+    /// every index in range, and some area between all the triangles, so
+    /// not every one of them is collinear.
+    pub fn validate(&self) -> Result<(), MeshError> {
+        if self.vertices.len() < 3 {
+            return Err(MeshError::TooFewVertices(self.vertices.len()));
+        }
+        if self.indices.len() < 3 {
+            return Err(MeshError::NoTriangles { indices: self.indices.len() });
+        }
+        if let Some(&index) = self.indices.iter().find(|&&i| i as usize >= self.vertices.len()) {
+            return Err(MeshError::IndexOutOfRange { index, vertices: self.vertices.len() });
+        }
+        let triangles = self.indices.len() / 3;
+        let area: f32 = (0..triangles * 3).step_by(3).map(|i| self.signed_area(i).abs()).sum();
+        // NaN positions make it NaN, which counts as none
+        if area.is_nan() || area <= 0.0 {
+            return Err(MeshError::ZeroArea { triangles });
+        }
+        Ok(())
+    }
+
+    /// Twice the signed area of the triangle starting at `indices[first]`;
+    /// positive means CCW in a y-up space like NDC.
+    fn signed_area(&self, first: usize) -> f32 {
+        let [a, b, c] = [0, 1, 2].map(|k| self.vertices[self.indices[first + k] as usize].position);
+        (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])
+    }
+
+    fn debug_assert_winding(&self, front_face: wgpu::FrontFace) {
+        if cfg!(debug_assertions) {
+            for i in (0..self.indices.len() - self.indices.len() % 3).step_by(3) {
+                let area = self.signed_area(i);
+                debug_assert!(
+                    match front_face {
+                        wgpu::FrontFace::Ccw => area >= 0.0,
+                        wgpu::FrontFace::Cw => area <= 0.0,
+                    },
+                    "triangle {} winds against {:?} (signed area {})", i / 3, front_face, area
+                );
+            }
+        }
+    }
+}
+
+/// Why `Mesh::validate` thinks a mesh would draw nothing.
+#[derive(Debug)]
+pub enum MeshError {
+    TooFewVertices(usize),
+    /// Fewer indices than one triangle takes.
+    NoTriangles { indices: usize },
+    IndexOutOfRange { index: u32, vertices: usize },
+    /// Every triangle is degenerate, its corners collinear or coincident.
+    ZeroArea { triangles: usize },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewVertices(count) => write!(f, "mesh has {count} vertices, a triangle needs 3"),
+            Self::NoTriangles { indices } => write!(f, "mesh has {indices} indices, not enough for a triangle"),
+            Self::IndexOutOfRange { index, vertices } => {
+                write!(f, "mesh index {index} is out of range for its {vertices} vertices")
+            }
+            Self::ZeroArea { triangles: 1 } => write!(f, "the mesh's only triangle has zero area; are its points collinear?"),
+            Self::ZeroArea { triangles } => {
+                write!(f, "all {triangles} of the mesh's triangles have zero area; are its points collinear?")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// Lines as thin CCW quads, so they can take a depth bias (line topologies
+/// can't), and be dashed like line lists can.
+pub struct LineMesh {
+    pub vertices: Vec<LineVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl LineMesh {
+    /// Grid lines every `spacing` units across `[-half_extent, half_extent]`
+    /// on both axes, running bottom to top and left to right.
+    pub fn grid(half_extent: f32, spacing: f32, line_width: f32, color: [f32; 3]) -> Self {
+        let mut mesh = Self { vertices: Vec::new(), indices: Vec::new() };
+        let lines = (half_extent / spacing).floor() as i32;
+        let color = [color[0], color[1], color[2], 1.0];
+        for i in -lines..=lines {
+            let at = i as f32 * spacing;
+            mesh.push_line([at, -half_extent], [at, half_extent], line_width, color); // vertical
+            mesh.push_line([-half_extent, at], [half_extent, at], line_width, color); // horizontal
+        }
+        mesh
+    }
+
+    // `width` wide and centered on `a` to `b`; the corners either side of an
+    // end are both that far along
+    fn push_line(&mut self, a: [f32; 2], b: [f32; 2], width: f32, color: [f32; 4]) {
+        let d = [b[0] - a[0], b[1] - a[1]];
+        let scale = 0.5 * width / d[0].hypot(d[1]).max(f32::EPSILON);
+        let n = [-d[1] * scale, d[0] * scale];
+        let base = self.vertices.len() as u32;
+        for (end, side, along) in [(a, -1.0, [0.0; 2]), (b, -1.0, d), (b, 1.0, d), (a, 1.0, [0.0; 2])] {
+            let position = [end[0] + side * n[0], end[1] + side * n[1]];
+            self.vertices.push(LineVertex { position, color, along });
+        }
+        self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// An Archimedean spiral, `r = a + b * t` with `t` in radians running from 0
+/// through `turns` full turns, sampled at `points` evenly spaced `t`s for
+/// drawing as a line strip.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Spiral {
+    pub a: f32,
+    pub b: f32,
+    pub turns: f32,
+    pub points: u32,
+}
+
+impl Default for Spiral {
+    fn default() -> Self {
+        Self { a: 0.0, b: 0.03, turns: 5.0, points: 512 }
+    }
+}
+
+impl Spiral {
+    /// Line-strip vertices from the center out, shading from cyan to magenta.
+    pub fn vertices(&self) -> Vec<Vertex> {
+        let Self { a, b, .. } = *self;
+        line_strip(self.points, self.turns * std::f32::consts::TAU, [0.2, 0.8, 1.0, 1.0], [1.0, 0.3, 0.6, 1.0], |t| {
+            let r = a + b * t;
+            [r * t.cos(), r * t.sin()]
+        })
+    }
+}
+
+/// `curve` sampled at `points` (at least 2) evenly spaced parameters from 0
+/// to `t_end`, both ends included, as line-strip vertices whose color runs
+/// from `start` to `end`.
+pub fn line_strip(points: u32, t_end: f32, start: [f32; 4], end: [f32; 4], curve: impl Fn(f32) -> [f32; 2]) -> Vec<Vertex> {
+    let points = points.max(2);
+    (0..points)
+        .map(|i| {
+            let s = i as f32 / (points - 1) as f32;
+            Vertex { position: curve(s * t_end), color: std::array::from_fn(|k| start[k] + (end[k] - start[k]) * s) }
+        })
+        .collect()
+}
+
+/// The debug color of the `index`th triangle of a draw; the same hash as
+/// `triangle_ids.wgsl`.
+pub fn triangle_color(index: u32) -> [f32; 4] {
+    let mut h = index;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    let [r, g, b] = [0, 8, 16].map(|shift| 0.25 + 0.75 * ((h >> shift) & 255) as f32 / 255.0);
+    [r, g, b, 1.0]
+}
+
+/// Cheap opaque rainbow around `a` radians: three cosines 120 degrees apart.
+pub fn hue(a: f32) -> [f32; 4] {
+    let third = std::f32::consts::TAU / 3.0;
+    let [r, g, b] = [0.0, 1.0, 2.0].map(|k| 0.5 + 0.5 * (a - k * third).cos());
+    [r, g, b, 1.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE: [f32; 18] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0, 0.25];
+
+    #[test]
+    fn from_floats_reads_both_strides() {
+        let mesh = Mesh::from_floats(&TRIANGLE, 6).unwrap();
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.vertices[1].position, [1.0, 0.0]);
+        assert_eq!(mesh.vertices[2].color, [0.0, 0.0, 1.0, 0.25]);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+
+        let opaque: Vec<f32> = TRIANGLE.chunks_exact(6).flat_map(|v| v[..5].to_vec()).collect();
+        let mesh = Mesh::from_floats(&opaque, 5).unwrap();
+        assert_eq!(mesh.vertices[2].color, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn from_floats_rejects_partial_vertices_and_triangles() {
+        // a trailing partial vertex
+        assert!(Mesh::from_floats(&[&TRIANGLE[..], &[0.5, 0.5]].concat(), 6).is_none());
+        // four vertices, one past a whole triangle
+        assert!(Mesh::from_floats(&[&TRIANGLE[..], &TRIANGLE[..6]].concat(), 6).is_none());
+        assert!(Mesh::from_floats(&TRIANGLE[..12], 6).is_none());
+        assert!(Mesh::from_floats(&TRIANGLE, 4).is_none());
+        assert!(Mesh::from_floats(&TRIANGLE, 0).is_none());
+    }
+
+    #[test]
+    fn validate_accepts_a_drawable_mesh() {
+        for mesh in [Mesh::square(), Mesh::ngon(5, 0.5), Mesh::pie(0.0, 1.0, 8, 0.5), Mesh::sierpinski(3, 0.5)] {
+            assert!(mesh.validate().is_ok());
+        }
+        let mut mesh = Mesh::from_floats(&TRIANGLE, 6).unwrap();
+        assert!(mesh.validate().is_ok());
+        // one degenerate triangle is fine next to one with area
+        mesh.indices.extend([0, 0, 1]);
+        assert!(mesh.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_explains_what_would_draw_nothing() {
+        let triangle = || Mesh::from_floats(&TRIANGLE, 6).unwrap();
+
+        let mut mesh = triangle();
+        mesh.vertices.truncate(2);
+        mesh.indices = vec![0, 1, 1];
+        assert!(matches!(mesh.validate(), Err(MeshError::TooFewVertices(2))));
+
+        let mut mesh = triangle();
+        mesh.indices.truncate(2);
+        assert!(matches!(mesh.validate(), Err(MeshError::NoTriangles { indices: 2 })));
+
+        let mut mesh = triangle();
+        mesh.indices[2] = 3;
+        assert!(matches!(mesh.validate(), Err(MeshError::IndexOutOfRange { index: 3, vertices: 3 })));
+
+        let mut mesh = triangle();
+        mesh.vertices[2].position = [2.0, 0.0];
+        assert!(matches!(mesh.validate(), Err(MeshError::ZeroArea { triangles: 1 })));
+        mesh.indices.extend([2, 1, 0]);
+        assert!(matches!(mesh.validate(), Err(MeshError::ZeroArea { triangles: 2 })));
+
+        assert!(matches!(Mesh::pie(0.0, 0.0, 8, 0.5).validate(), Err(MeshError::ZeroArea { .. })));
+        assert!(matches!(Mesh::ngon(6, 0.0).validate(), Err(MeshError::ZeroArea { .. })));
+
+        let mut mesh = triangle();
+        mesh.vertices[0].position = [f32::NAN, 0.0];
+        assert!(matches!(mesh.validate(), Err(MeshError::ZeroArea { .. })));
+    }
+
+    #[test]
+    fn grid_lines_are_ccw_quads_measured_from_their_start() {
+        let grid = LineMesh::grid(2.0, 1.0, 0.1, [1.0; 3]);
+        // five lines each way through -2..2, four corners and two triangles a line
+        assert_eq!(grid.vertices.len(), 10 * 4);
+        assert_eq!(grid.indices.len(), 10 * 6);
+        for tri in grid.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| grid.vertices[tri[k] as usize].position);
+            assert!((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1]) > 0.0, "{tri:?} winds CW");
+        }
+        // the first line is the vertical one at x = -2, running up
+        let corners: Vec<_> = grid.vertices[..4].iter().map(|v| (v.position, v.along)).collect();
+        assert_eq!(
+            corners,
+            [([-1.95, -2.0], [0.0, 0.0]), ([-1.95, 2.0], [0.0, 4.0]), ([-2.05, 2.0], [0.0, 4.0]), ([-2.05, -2.0], [0.0, 0.0])]
+        );
+    }
+}
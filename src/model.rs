@@ -0,0 +1,432 @@
+// Loading triangle meshes from glTF files and drawing them in 3D
+
+use std::fmt;
+use std::path::Path;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::camera::OrbitCamera;
+use crate::error::GfxError;
+use crate::gfx::{create_shader_module, stage_write};
+use crate::resources::{ResourceKind, ResourceReport};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+}
+
+// A model vertex with the normal of the one triangle it belongs to, for
+// pushing triangles apart in the exploded view
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ExplodedVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    face_normal: [f32; 3],
+}
+
+/// One draw call's worth of geometry.
+pub struct Primitive {
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>,
+}
+
+pub struct Model {
+    pub primitives: Vec<Primitive>,
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Gltf(gltf::Error),
+    /// The file parsed but had nothing we can draw.
+    NoTriangles,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gltf(e) => write!(f, "couldn't read glTF: {e}"),
+            Self::NoTriangles => write!(f, "glTF has no triangle primitives"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<gltf::Error> for ModelError {
+    fn from(e: gltf::Error) -> Self {
+        Self::Gltf(e)
+    }
+}
+
+impl Primitive {
+    // Every triangle with vertices of its own, since shared ones can't move
+    // apart
+    fn exploded_vertices(&self) -> Vec<ExplodedVertex> {
+        self.indices
+            .chunks_exact(3)
+            .flat_map(|tri| {
+                let corners = [0, 1, 2].map(|k| self.vertices[tri[k] as usize]);
+                let [a, b, c] = corners.map(|v| Vec3::from(v.position));
+                let face_normal = (b - a).cross(c - a).normalize_or_zero().to_array();
+                corners.map(|v| ExplodedVertex { position: v.position, normal: v.normal, face_normal })
+            })
+            .collect()
+    }
+}
+
+impl Model {
+    /// Loads positions, normals and indices of the first mesh in a
+    /// `.gltf`/`.glb`. Everything else (materials, skins, node transforms,
+    /// further meshes) is logged and skipped.
+    pub fn load_gltf(path: impl AsRef<Path>) -> Result<Self, ModelError> {
+        let path = path.as_ref();
+        let (document, buffers, _images) = gltf::import(path)?;
+
+        let meshes = document.meshes().len();
+        if meshes > 1 {
+            log::warn!("{}: {meshes} meshes, only the first is loaded", path.display());
+        }
+        if document.skins().len() > 0 {
+            log::warn!("{}: skins are not supported", path.display());
+        }
+        if document.animations().len() > 0 {
+            log::warn!("{}: animations are not supported", path.display());
+        }
+        if document.textures().len() > 0 {
+            log::warn!("{}: textures and materials are ignored", path.display());
+        }
+
+        let mut primitives = Vec::new();
+        for primitive in document.meshes().next().into_iter().flat_map(|mesh| mesh.primitives()) {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!("{}: skipping {:?} primitive", path.display(), primitive.mode());
+                continue;
+            }
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                log::warn!("{}: skipping primitive without positions", path.display());
+                continue;
+            };
+            let positions: Vec<[f32; 3]> = positions.collect();
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            let normals: Vec<[f32; 3]> = match reader.read_normals() {
+                Some(normals) => normals.collect(),
+                None => {
+                    log::info!("{}: primitive has no normals, computing smooth ones", path.display());
+                    smooth_normals(&positions, &indices)
+                }
+            };
+
+            let vertices = positions
+                .into_iter()
+                .zip(normals)
+                .map(|(position, normal)| ModelVertex { position, normal })
+                .collect();
+            primitives.push(Primitive { vertices, indices });
+        }
+
+        if primitives.is_empty() {
+            return Err(ModelError::NoTriangles);
+        }
+        Ok(Self { primitives })
+    }
+
+    /// Center and radius of a sphere around every vertex, for framing the camera.
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        let positions = || self.primitives.iter().flat_map(|p| &p.vertices).map(|v| Vec3::from(v.position));
+        let (min, max) = positions().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), p| {
+            (min.min(p), max.max(p))
+        });
+        let center = (min + max) * 0.5;
+        let radius = positions().map(|p| p.distance(center)).fold(0.0, f32::max);
+        (center, radius)
+    }
+}
+
+// Area-weighted average of the face normals touching each vertex
+fn smooth_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|k| Vec3::from(positions[tri[k] as usize]));
+        let face = (b - a).cross(c - a);
+        for &i in tri {
+            normals[i as usize] += face;
+        }
+    }
+    normals.into_iter().map(|n| n.normalize_or_zero().to_array()).collect()
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Pod, Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    model: [[f32; 4]; 4],
+    // how far each triangle moves along its face normal, in model units
+    explode: f32,
+    _pad: [f32; 3],
+}
+
+struct GpuPrimitive {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+}
+
+// A primitive's `ExplodedVertex`es, drawn without an index buffer
+struct ExplodedPrimitive {
+    vertex_buffer: wgpu::Buffer,
+    num_vertices: u32,
+}
+
+/// Draws a loaded model with depth testing through a camera framing it.
+pub struct ModelRenderer {
+    // standard depth, then reverse-Z
+    pipelines: [wgpu::RenderPipeline; 2],
+    exploded_pipelines: [wgpu::RenderPipeline; 2],
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    primitives: Vec<GpuPrimitive>,
+    exploded: Vec<ExplodedPrimitive>,
+    // fraction of `radius` the triangles are pushed out by
+    explode: f32,
+    radius: f32,
+    camera: OrbitCamera,
+    // what the buffer holds, so unchanged frames skip the upload
+    uploaded: Option<CameraUniform>,
+}
+
+impl ModelRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        model: &Model,
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        sample_count: u32,
+        reverse_z: bool,
+    ) -> Result<Self, GfxError> {
+        let (center, radius) = model.bounding_sphere();
+        let mut camera = OrbitCamera::framing(center, radius);
+        camera.reverse_z = reverse_z;
+
+        let primitives = model
+            .primitives
+            .iter()
+            .map(|primitive| GpuPrimitive {
+                vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Model Vertex Buffer"),
+                    contents: bytemuck::cast_slice(&primitive.vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                }),
+                index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Model Index Buffer"),
+                    contents: bytemuck::cast_slice(&primitive.indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                }),
+                num_indices: primitive.indices.len() as u32,
+            })
+            .collect();
+        let exploded = model
+            .primitives
+            .iter()
+            .map(|primitive| {
+                let vertices = primitive.exploded_vertices();
+                ExplodedPrimitive {
+                    vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Exploded Model Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    }),
+                    num_vertices: vertices.len() as u32,
+                }
+            })
+            .collect();
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera UBO"),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CameraUniform>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera BG"),
+            layout: &camera_bgl,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: camera_buffer.as_entire_binding() }],
+        });
+
+        let shader = create_shader_module(device, "Model Shader", include_str!("model.wgsl"))?;
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Model Pipeline Layout"),
+            bind_group_layouts: &[&camera_bgl],
+            push_constant_ranges: &[],
+        });
+
+        let model_pipeline = |label, vs_entry, buffer, reverse_z| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some(vs_entry),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[buffer],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                // glTF allows either winding via negative scales, so don't cull
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: if reverse_z { wgpu::CompareFunction::Greater } else { wgpu::CompareFunction::Less },
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState { count: sample_count, ..Default::default() },
+            multiview: None,
+            cache: None,
+        });
+        let attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+        let pipelines = [false, true].map(|reverse_z| {
+            let layout = wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<ModelVertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &attributes,
+            };
+            model_pipeline("Model Pipeline", "vs_main", layout, reverse_z)
+        });
+        let exploded_attributes = wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x3];
+        let exploded_pipelines = [false, true].map(|reverse_z| {
+            let layout = wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<ExplodedVertex>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &exploded_attributes,
+            };
+            model_pipeline("Exploded Model Pipeline", "vs_exploded", layout, reverse_z)
+        });
+
+        Ok(Self {
+            pipelines,
+            exploded_pipelines,
+            camera_buffer,
+            camera_bind_group,
+            primitives,
+            exploded,
+            explode: 0.0,
+            radius,
+            camera,
+            uploaded: None,
+        })
+    }
+
+    pub fn camera(&self) -> &OrbitCamera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut OrbitCamera {
+        &mut self.camera
+    }
+
+    /// Switches to a `Greater` depth test and a camera with the clip planes
+    /// swapped, or back; the depth buffer has to clear to 0 under reverse-Z.
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.camera.reverse_z = reverse_z;
+    }
+
+    /// Pushes every triangle out along its face normal by `amount` times
+    /// the model's bounding radius; 0 draws it whole.
+    pub fn set_explode(&mut self, amount: f32) {
+        self.explode = amount;
+    }
+
+    pub fn explode(&self) -> f32 {
+        self.explode
+    }
+
+    /// Records an upload of the camera for the current aspect, spinning the
+    /// model by `angle` around y, unless the buffer already has it.
+    pub fn update(
+        &mut self,
+        belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        aspect: f32,
+        angle: f32,
+    ) {
+        let uniform = CameraUniform {
+            view_proj: self.camera.camera().view_proj(aspect).to_cols_array_2d(),
+            model: Mat4::from_rotation_y(angle).to_cols_array_2d(),
+            explode: self.explode * self.radius,
+            _pad: [0.0; 3],
+        };
+        if self.uploaded != Some(uniform) {
+            stage_write(belt, encoder, device, &self.camera_buffer, bytemuck::bytes_of(&uniform));
+            self.uploaded = Some(uniform);
+        }
+    }
+
+    pub fn report_resources(&self, report: &mut ResourceReport) {
+        for primitive in &self.primitives {
+            report.buffer(ResourceKind::VertexBuffer, &primitive.vertex_buffer);
+            report.buffer(ResourceKind::IndexBuffer, &primitive.index_buffer);
+        }
+        for primitive in &self.exploded {
+            report.buffer(ResourceKind::VertexBuffer, &primitive.vertex_buffer);
+        }
+        report.buffer(ResourceKind::UniformBuffer, &self.camera_buffer);
+    }
+
+    /// Draws every primitive; the pass needs a depth attachment.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        if self.explode > 0.0 {
+            render_pass.set_pipeline(&self.exploded_pipelines[self.camera.reverse_z as usize]);
+            for primitive in &self.exploded {
+                render_pass.set_vertex_buffer(0, primitive.vertex_buffer.slice(..));
+                render_pass.draw(0..primitive.num_vertices, 0..1);
+            }
+            return;
+        }
+        render_pass.set_pipeline(&self.pipelines[self.camera.reverse_z as usize]);
+        for primitive in &self.primitives {
+            render_pass.set_vertex_buffer(0, primitive.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(primitive.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..primitive.num_indices, 0, 0..1);
+        }
+    }
+}
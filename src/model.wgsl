@@ -0,0 +1,53 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+}
+
+struct CameraUniform {
+    view_proj : mat4x4<f32>,
+    model : mat4x4<f32>,
+    // model units each triangle moves along its face normal
+    explode : f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera : CameraUniform;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    return transform(in.position, in.normal);
+}
+
+fn transform(position: vec3<f32>, normal: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * camera.model * vec4<f32>(position, 1.0);
+    // the model matrix is a pure rotation, so it can transform normals directly
+    out.normal = (camera.model * vec4<f32>(normal, 0.0)).xyz;
+    return out;
+}
+
+struct ExplodedInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) face_normal: vec3<f32>,
+}
+
+// vs_main with the vertex's triangle pushed out along its own normal; every
+// triangle has its own three vertices, so they all move the same way
+@vertex
+fn vs_exploded(in: ExplodedInput) -> VertexOutput {
+    return transform(in.position + in.face_normal * camera.explode, in.normal);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light = normalize(vec3<f32>(0.4, 0.8, 0.6));
+    let diffuse = max(dot(normalize(in.normal), light), 0.0);
+    let base = vec3<f32>(0.8, 0.8, 0.85);
+    return vec4<f32>(base * (0.15 + 0.85 * diffuse), 1.0);
+}
@@ -0,0 +1,863 @@
+// Offscreen scene target and the fullscreen passes that turn it into the
+// final image
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::error::GfxError;
+use crate::font::{self, BannerText};
+use crate::gfx::create_shader_module;
+use crate::resources::{ResourceKind, ResourceReport};
+use crate::texture::Lut;
+
+/// The scene is drawn into this float format so post passes see values past 1.0.
+pub const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// Steps per channel in the do-nothing LUT bound until a real one is loaded
+const IDENTITY_LUT_SIZE: u32 = 16;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PostParams {
+    direction: [f32; 2],
+    radius: f32,
+    threshold: f32,
+    intensity: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    depth_near: f32,
+    depth_far: f32,
+    depth_perspective: f32,
+    depth_reverse: f32,
+    lut_size: f32,
+    lut_intensity: f32,
+    dither_step: f32,
+    output_srgb: f32,
+    pixel_size: f32,
+    channel_mask: [f32; 4],
+}
+
+/// How the depth buffer maps to distance, for showing it as grayscale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthRange {
+    pub near: f32,
+    pub far: f32,
+    /// Perspective depth gets linearized between `near` and `far`;
+    /// orthographic depth is shown as it is.
+    pub perspective: bool,
+    /// Reverse-Z, where the near plane is at 1.
+    pub reverse: bool,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    /// Luminance above which pixels start to glow
+    pub threshold: f32,
+    /// How strongly the blurred highlights are added back
+    pub intensity: f32,
+    /// Gaussian radius in half-resolution texels
+    pub radius: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self { enabled: false, threshold: 0.8, intensity: 1.0, radius: 4.0 }
+    }
+}
+
+/// Remapping the final colors through a lookup table; see `Lut`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ColorGradeSettings {
+    pub enabled: bool,
+    /// How much of the graded color replaces the original, 0 to 1
+    pub intensity: f32,
+}
+
+impl Default for ColorGradeSettings {
+    fn default() -> Self {
+        Self { enabled: false, intensity: 1.0 }
+    }
+}
+
+/// A cheaper, flatter blur than bloom's: the whole scene box-blurred in
+/// place of the sharp one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BoxBlurSettings {
+    pub enabled: bool,
+    /// Texels on each side of the center, at full resolution
+    pub radius: u32,
+    /// Horizontal-then-vertical passes run back to back; a few
+    /// approximate a gaussian
+    pub iterations: u32,
+}
+
+impl Default for BoxBlurSettings {
+    fn default() -> Self {
+        Self { enabled: false, radius: 2, iterations: 1 }
+    }
+}
+
+/// Darkening toward the edges, applied as the composite writes the output.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VignetteSettings {
+    pub enabled: bool,
+    /// How dark the corners get, 0 (untouched) to 1 (black)
+    pub intensity: f32,
+    /// Distance from the center where darkening starts, in half-heights of
+    /// the shorter side, so it stays round at any aspect ratio
+    pub radius: f32,
+}
+
+impl Default for VignetteSettings {
+    fn default() -> Self {
+        Self { enabled: false, intensity: 0.4, radius: 0.6 }
+    }
+}
+
+// A texture a pass renders into and later passes sample from
+struct Target {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, label: &str, width: u32, height: u32) -> Self {
+        Self::with_samples(device, label, width, height, 1)
+    }
+
+    // multisampled targets can't be sampled, only resolved or loaded from
+    // one sample at a time, which the MSAA coverage view does
+    fn with_samples(device: &wgpu::Device, label: &str, width: u32, height: u32, sample_count: u32) -> Self {
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: SCENE_FORMAT,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+        Self { texture, view }
+    }
+}
+
+// What the passes that write the final image are built from, kept for
+// rebuilding them when the output format changes
+struct OutputLayouts {
+    shader: wgpu::ShaderModule,
+    composite: wgpu::PipelineLayout,
+    banner: wgpu::PipelineLayout,
+    depth: wgpu::PipelineLayout,
+    // which of the depth view's entry points fits the scene's sample count
+    depth_entry: &'static str,
+    coverage: wgpu::PipelineLayout,
+}
+
+// The passes that write the final image, built for its format
+struct OutputPipelines {
+    composite: wgpu::RenderPipeline,
+    banner: wgpu::RenderPipeline,
+    depth: wgpu::RenderPipeline,
+    coverage: wgpu::RenderPipeline,
+}
+
+impl OutputLayouts {
+    fn build(&self, device: &wgpu::Device, format: wgpu::TextureFormat) -> OutputPipelines {
+        let shader = &self.shader;
+        OutputPipelines {
+            composite: fullscreen_pipeline(device, &self.composite, shader, "fs_composite", format),
+            banner: fullscreen_pipeline(device, &self.banner, shader, "fs_error_banner", format),
+            depth: fullscreen_pipeline(device, &self.depth, shader, self.depth_entry, format),
+            // built either way, but only ever bound with MSAA on
+            coverage: fullscreen_pipeline(device, &self.coverage, shader, "fs_msaa_coverage", format),
+        }
+    }
+}
+
+// Size-dependent resources, rebuilt on resize
+struct Targets {
+    scene: Target,
+    // what the scene pass draws into with MSAA on; resolved into `scene`
+    msaa: Option<Target>,
+    // `msaa` for the coverage view
+    msaa_bg: Option<wgpu::BindGroup>,
+    // bloom ping-pongs between these at half resolution
+    bloom_a: Target,
+    bloom_b: Target,
+    // the box blur ping-pongs between these at full resolution
+    box_a: Target,
+    box_b: Target,
+    scene_bg: wgpu::BindGroup,
+    bloom_a_bg: wgpu::BindGroup,
+    bloom_b_bg: wgpu::BindGroup,
+    box_a_bg: wgpu::BindGroup,
+    box_b_bg: wgpu::BindGroup,
+}
+
+pub struct PostProcess {
+    texture_bgl: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bright_pipeline: wgpu::RenderPipeline,
+    blur_pipeline: wgpu::RenderPipeline,
+    box_blur_pipeline: wgpu::RenderPipeline,
+    depth_bgl: wgpu::BindGroupLayout,
+    coverage_bgl: wgpu::BindGroupLayout,
+    output_layouts: OutputLayouts,
+    output: OutputPipelines,
+    // set by `set_depth_source`, and rebuilt whenever the depth target is
+    depth_bg: Option<wgpu::BindGroup>,
+    depth_range: Option<DepthRange>,
+    bright_params: wgpu::Buffer,
+    blur_h_params: wgpu::Buffer,
+    blur_v_params: wgpu::Buffer,
+    box_h_params: wgpu::Buffer,
+    box_v_params: wgpu::Buffer,
+    composite_params: wgpu::Buffer,
+    depth_params: wgpu::Buffer,
+    bright_bg: wgpu::BindGroup,
+    blur_h_bg: wgpu::BindGroup,
+    blur_v_bg: wgpu::BindGroup,
+    box_h_bg: wgpu::BindGroup,
+    box_v_bg: wgpu::BindGroup,
+    composite_bg: wgpu::BindGroup,
+    depth_params_bg: wgpu::BindGroup,
+    banner_text: wgpu::Buffer,
+    banner_bg: wgpu::BindGroup,
+    lut: Lut,
+    lut_bg: wgpu::BindGroup,
+    targets: Targets,
+    sample_count: u32,
+    bloom: BloomSettings,
+    box_blur: BoxBlurSettings,
+    vignette: VignetteSettings,
+    color_grade: ColorGradeSettings,
+    dither: bool,
+    channel_mask: [bool; 4],
+    pixelation: Option<u32>,
+    // one quantization step of the output, `None` for float formats that
+    // don't band
+    output_step: Option<f32>,
+    output_srgb: bool,
+}
+
+impl PostProcess {
+    /// `sample_count` above 1 draws the scene multisampled and resolves it
+    /// into the target the post chain reads.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        output_format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Result<Self, GfxError> {
+        let texture_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Texture BGL"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let params_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Params BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<PostParams>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let shader = create_shader_module(device, "Post Shader", include_str!("post.wgsl"))?;
+
+        let single_input = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&texture_bgl, &params_bgl],
+            push_constant_ranges: &[],
+        });
+        let dual_input = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Composite Pipeline Layout"),
+            bind_group_layouts: &[&texture_bgl, &params_bgl, &texture_bgl, &texture_bgl],
+            push_constant_ranges: &[],
+        });
+
+        let bright_pipeline = fullscreen_pipeline(device, &single_input, &shader, "fs_bright", SCENE_FORMAT);
+        let blur_pipeline = fullscreen_pipeline(device, &single_input, &shader, "fs_blur", SCENE_FORMAT);
+        let box_blur_pipeline = fullscreen_pipeline(device, &single_input, &shader, "fs_box_blur", SCENE_FORMAT);
+
+        // the font is uploaded once; the text is rewritten by `set_error_text`
+        let uniform_entry = |binding, size: usize| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(size as u64),
+            },
+            count: None,
+        };
+        let banner_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Banner BGL"),
+            entries: &[
+                uniform_entry(5, std::mem::size_of::<[[u32; 4]; 48]>()),
+                uniform_entry(6, std::mem::size_of::<BannerText>()),
+            ],
+        });
+        let banner_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Banner Pipeline Layout"),
+            bind_group_layouts: &[&banner_bgl],
+            push_constant_ranges: &[],
+        });
+        let banner_font = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Banner Font"),
+            contents: bytemuck::cast_slice(&font::packed_font()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let banner_text = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Banner Text"),
+            contents: bytemuck::bytes_of(&BannerText::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let banner_bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Banner BG"),
+            layout: &banner_bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 5, resource: banner_font.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 6, resource: banner_text.as_entire_binding() },
+            ],
+        });
+
+        // depth formats only bind as unfilterable floats (or as depth, which
+        // GL can't load from), so it's read with loads and needs no sampler
+        let multisampled = sample_count > 1;
+        let depth_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth View BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: if multisampled { 3 } else { 2 },
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled,
+                },
+                count: None,
+            }],
+        });
+        let depth_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth View Pipeline Layout"),
+            bind_group_layouts: &[&depth_bgl, &params_bgl],
+            push_constant_ranges: &[],
+        });
+        let depth_entry = if multisampled { "fs_depth_multisampled" } else { "fs_depth" };
+
+        let coverage_bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("MSAA Coverage BGL"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: true,
+                },
+                count: None,
+            }],
+        });
+        let coverage_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("MSAA Coverage Pipeline Layout"),
+            bind_group_layouts: &[&coverage_bgl],
+            push_constant_ranges: &[],
+        });
+        let output_layouts = OutputLayouts {
+            shader,
+            composite: dual_input,
+            banner: banner_layout,
+            depth: depth_layout,
+            depth_entry,
+            coverage: coverage_layout,
+        };
+        let output = output_layouts.build(device, output_format);
+
+        let params_buffer = |label| device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::bytes_of(&PostParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bright_params = params_buffer("Bright Params");
+        let blur_h_params = params_buffer("Blur H Params");
+        let blur_v_params = params_buffer("Blur V Params");
+        let box_h_params = params_buffer("Box Blur H Params");
+        let box_v_params = params_buffer("Box Blur V Params");
+        let composite_params = params_buffer("Composite Params");
+        let depth_params = params_buffer("Depth View Params");
+
+        let params_bg = |buffer: &wgpu::Buffer| device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Params BG"),
+            layout: &params_bgl,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() }],
+        });
+        let bright_bg = params_bg(&bright_params);
+        let blur_h_bg = params_bg(&blur_h_params);
+        let blur_v_bg = params_bg(&blur_v_params);
+        let box_h_bg = params_bg(&box_h_params);
+        let box_v_bg = params_bg(&box_v_params);
+        let composite_bg = params_bg(&composite_params);
+        let depth_params_bg = params_bg(&depth_params);
+
+        let targets = Targets::new(device, &texture_bgl, &coverage_bgl, &sampler, width, height, sample_count);
+        let lut = Lut::identity(device, queue, IDENTITY_LUT_SIZE);
+        let lut_bg = texture_bind_group(device, &texture_bgl, &lut.view, &sampler);
+
+        Ok(Self {
+            texture_bgl,
+            sampler,
+            bright_pipeline,
+            blur_pipeline,
+            box_blur_pipeline,
+            depth_bgl,
+            coverage_bgl,
+            output_layouts,
+            output,
+            depth_bg: None,
+            depth_range: None,
+            bright_params,
+            blur_h_params,
+            blur_v_params,
+            box_h_params,
+            box_v_params,
+            composite_params,
+            depth_params,
+            bright_bg,
+            blur_h_bg,
+            blur_v_bg,
+            box_h_bg,
+            box_v_bg,
+            composite_bg,
+            depth_params_bg,
+            banner_text,
+            banner_bg,
+            lut,
+            lut_bg,
+            targets,
+            sample_count,
+            bloom: BloomSettings::default(),
+            box_blur: BoxBlurSettings::default(),
+            vignette: VignetteSettings::default(),
+            color_grade: ColorGradeSettings::default(),
+            dither: false,
+            channel_mask: [true; 4],
+            pixelation: None,
+            output_step: quantization_step(output_format),
+            output_srgb: output_format.is_srgb(),
+        })
+    }
+
+    /// Rebuilds the passes that write the final image for `format`, which
+    /// the views given to the encode calls have to have from then on. The
+    /// dither and sRGB handling follow it.
+    pub fn set_output_format(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) {
+        self.output = self.output_layouts.build(device, format);
+        self.output_step = quantization_step(format);
+        self.output_srgb = format.is_srgb();
+        self.write_composite_params(queue);
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.targets =
+            Targets::new(device, &self.texture_bgl, &self.coverage_bgl, &self.sampler, width, height, self.sample_count);
+    }
+
+    /// Where the scene pass should render, and what it resolves into with
+    /// MSAA on. The resolve always lands in the post chain's input, never in
+    /// the final output, which only the composite pass writes.
+    pub fn scene_attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.targets.msaa {
+            Some(msaa) => (&msaa.view, Some(&self.targets.scene.view)),
+            None => (&self.targets.scene.view, None),
+        }
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn report_resources(&self, report: &mut ResourceReport) {
+        let t = &self.targets;
+        for target in [&t.scene, &t.bloom_a, &t.bloom_b, &t.box_a, &t.box_b] {
+            report.texture(ResourceKind::OffscreenTexture, &target.texture);
+        }
+        if let Some(msaa) = &t.msaa {
+            report.texture(ResourceKind::MsaaTexture, &msaa.texture);
+        }
+        report.texture(ResourceKind::ColorTexture, &self.lut.texture);
+        for buffer in [
+            &self.bright_params,
+            &self.blur_h_params,
+            &self.blur_v_params,
+            &self.box_h_params,
+            &self.box_v_params,
+            &self.composite_params,
+            &self.depth_params,
+        ] {
+            report.buffer(ResourceKind::UniformBuffer, buffer);
+        }
+    }
+
+    pub fn bloom(&self) -> BloomSettings {
+        self.bloom
+    }
+
+    pub fn set_bloom(&mut self, queue: &wgpu::Queue, bloom: BloomSettings) {
+        self.bloom = bloom;
+        let write = |buffer, params: PostParams| queue.write_buffer(buffer, 0, bytemuck::bytes_of(&params));
+        let base = PostParams { threshold: bloom.threshold, radius: bloom.radius, ..PostParams::zeroed() };
+        write(&self.bright_params, base);
+        write(&self.blur_h_params, PostParams { direction: [1.0, 0.0], ..base });
+        write(&self.blur_v_params, PostParams { direction: [0.0, 1.0], ..base });
+        self.write_composite_params(queue);
+    }
+
+    pub fn box_blur(&self) -> BoxBlurSettings {
+        self.box_blur
+    }
+
+    pub fn set_box_blur(&mut self, queue: &wgpu::Queue, box_blur: BoxBlurSettings) {
+        self.box_blur = box_blur;
+        let base = PostParams { radius: box_blur.radius as f32, ..PostParams::zeroed() };
+        let h = PostParams { direction: [1.0, 0.0], ..base };
+        let v = PostParams { direction: [0.0, 1.0], ..base };
+        queue.write_buffer(&self.box_h_params, 0, bytemuck::bytes_of(&h));
+        queue.write_buffer(&self.box_v_params, 0, bytemuck::bytes_of(&v));
+    }
+
+    pub fn vignette(&self) -> VignetteSettings {
+        self.vignette
+    }
+
+    pub fn set_vignette(&mut self, queue: &wgpu::Queue, vignette: VignetteSettings) {
+        self.vignette = vignette;
+        self.write_composite_params(queue);
+    }
+
+    pub fn color_grade(&self) -> ColorGradeSettings {
+        self.color_grade
+    }
+
+    pub fn set_color_grade(&mut self, queue: &wgpu::Queue, color_grade: ColorGradeSettings) {
+        self.color_grade = color_grade;
+        self.write_composite_params(queue);
+    }
+
+    /// Grades through `lut` from now on, or through one that changes nothing
+    /// when it's `None`.
+    pub fn set_lut(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lut: Option<Lut>) {
+        self.lut = lut.unwrap_or_else(|| Lut::identity(device, queue, IDENTITY_LUT_SIZE));
+        self.lut_bg = texture_bind_group(device, &self.texture_bgl, &self.lut.view, &self.sampler);
+        self.write_composite_params(queue);
+    }
+
+    pub fn dither(&self) -> bool {
+        self.dither
+    }
+
+    /// Adds an ordered dither of under one output step to the composite, so
+    /// smooth gradients don't quantize into bands. Nothing changes on float
+    /// outputs, which have steps to spare.
+    pub fn set_dither(&mut self, queue: &wgpu::Queue, dither: bool) {
+        self.dither = dither;
+        self.write_composite_params(queue);
+    }
+
+    pub fn channel_mask(&self) -> [bool; 4] {
+        self.channel_mask
+    }
+
+    /// Zeroes the output's red, green, blue or alpha wherever `mask` is
+    /// false, after everything else the composite does, for looking at one
+    /// channel at a time.
+    pub fn set_channel_mask(&mut self, queue: &wgpu::Queue, mask: [bool; 4]) {
+        self.channel_mask = mask;
+        self.write_composite_params(queue);
+    }
+
+    pub fn pixelation(&self) -> Option<u32> {
+        self.pixelation
+    }
+
+    /// Has the composite read one texel per `pixelation` by `pixelation`
+    /// block of the output, so the image comes out as a coarser grid of
+    /// flat squares; `None` reads every texel.
+    pub fn set_pixelation(&mut self, queue: &wgpu::Queue, pixelation: Option<u32>) {
+        self.pixelation = pixelation;
+        self.write_composite_params(queue);
+    }
+
+    // The composite applies pixelation, bloom, the vignette, the color
+    // grade, the dither and the channel mask; with all of them off it's a
+    // plain copy
+    fn write_composite_params(&self, queue: &wgpu::Queue) {
+        let params = PostParams {
+            intensity: if self.bloom.enabled { self.bloom.intensity } else { 0.0 },
+            vignette_intensity: if self.vignette.enabled { self.vignette.intensity } else { 0.0 },
+            vignette_radius: self.vignette.radius,
+            lut_size: self.lut.size as f32,
+            lut_intensity: if self.color_grade.enabled { self.color_grade.intensity } else { 0.0 },
+            dither_step: self.output_step.filter(|_| self.dither).unwrap_or(0.0),
+            output_srgb: self.output_srgb as u32 as f32,
+            channel_mask: self.channel_mask.map(|on| on as u32 as f32),
+            pixel_size: self.pixelation.unwrap_or(1) as f32,
+            ..PostParams::zeroed()
+        };
+        queue.write_buffer(&self.composite_params, 0, bytemuck::bytes_of(&params));
+    }
+
+    /// Runs the post chain over the scene target, writing the result to `output`.
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let t = &self.targets;
+        // the composite's base image: the scene, or its blurred copy in box_a
+        let mut base = &t.scene_bg;
+        if self.box_blur.enabled {
+            for _ in 0..self.box_blur.iterations {
+                fullscreen_pass(encoder, "Box Blur H Pass", &t.box_b.view, &self.box_blur_pipeline, &[base, &self.box_h_bg]);
+                fullscreen_pass(encoder, "Box Blur V Pass", &t.box_a.view, &self.box_blur_pipeline, &[&t.box_b_bg, &self.box_v_bg]);
+                base = &t.box_a_bg;
+            }
+        }
+        if self.bloom.enabled {
+            fullscreen_pass(encoder, "Bloom Bright Pass", &t.bloom_a.view, &self.bright_pipeline, &[&t.scene_bg, &self.bright_bg]);
+            fullscreen_pass(encoder, "Bloom Blur H Pass", &t.bloom_b.view, &self.blur_pipeline, &[&t.bloom_a_bg, &self.blur_h_bg]);
+            fullscreen_pass(encoder, "Bloom Blur V Pass", &t.bloom_a.view, &self.blur_pipeline, &[&t.bloom_b_bg, &self.blur_v_bg]);
+        }
+        fullscreen_pass(
+            encoder,
+            "Composite Pass",
+            output,
+            &self.output.composite,
+            &[base, &self.composite_bg, &t.bloom_a_bg, &self.lut_bg],
+        );
+    }
+
+    /// Reads `depth` for the depth view through its own depth-only view. It
+    /// has to have been created with `TEXTURE_BINDING` and the sample count
+    /// this was.
+    pub fn set_depth_source(&mut self, device: &wgpu::Device, depth: &wgpu::Texture) {
+        let view = depth.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Depth Read View"),
+            aspect: wgpu::TextureAspect::DepthOnly,
+            ..Default::default()
+        });
+        self.depth_bg = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth View BG"),
+            layout: &self.depth_bgl,
+            entries: &[wgpu::BindGroupEntry {
+                binding: if self.sample_count > 1 { 3 } else { 2 },
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        }));
+    }
+
+    /// Writes the depth buffer to `output` as grayscale in place of the post
+    /// chain, linearized through `range`. Does nothing until
+    /// `set_depth_source` has been called.
+    pub fn encode_depth(&mut self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView, range: DepthRange) {
+        let Some(depth_bg) = &self.depth_bg else { return };
+        if self.depth_range != Some(range) {
+            let params = PostParams {
+                depth_near: range.near,
+                depth_far: range.far,
+                depth_perspective: range.perspective as u32 as f32,
+                depth_reverse: range.reverse as u32 as f32,
+                ..PostParams::zeroed()
+            };
+            queue.write_buffer(&self.depth_params, 0, bytemuck::bytes_of(&params));
+            self.depth_range = Some(range);
+        }
+        fullscreen_pass(encoder, "Depth View Pass", output, &self.output.depth, &[depth_bg, &self.depth_params_bg]);
+    }
+
+    /// Writes a heatmap of which pixels MSAA splits to `output` in place of
+    /// the post chain: edge pixels whose samples disagree, colored by how
+    /// many do, over the rest of the scene in dim gray. Does nothing
+    /// without MSAA, where there are no samples to compare.
+    pub fn encode_msaa_coverage(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let Some(msaa_bg) = &self.targets.msaa_bg else { return };
+        fullscreen_pass(encoder, "MSAA Coverage Pass", output, &self.output.coverage, &[msaa_bg]);
+    }
+
+    /// Lays `message` out for the error banner over an output `width`
+    /// pixels wide, which it needs redoing for when the width changes.
+    pub fn set_error_text(&self, queue: &wgpu::Queue, message: &str, width: u32) {
+        queue.write_buffer(&self.banner_text, 0, bytemuck::bytes_of(&BannerText::layout(message, width)));
+    }
+
+    /// Draws the red error banner with the text from `set_error_text` across
+    /// the top of `output`, keeping the rest of whatever is already there.
+    pub fn encode_error_banner(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Error Banner Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: wgpu::StoreOp::Store },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.output.banner);
+        pass.set_bind_group(0, &self.banner_bg, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+impl Targets {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        coverage_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+    ) -> Self {
+        let scene = Target::new(device, "Scene Target", width, height);
+        let msaa = (sample_count > 1).then(|| Target::with_samples(device, "Scene MSAA Target", width, height, sample_count));
+        let msaa_bg = msaa.as_ref().map(|msaa| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("MSAA Coverage BG"),
+                layout: coverage_layout,
+                entries: &[wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(&msaa.view) }],
+            })
+        });
+        let bloom_a = Target::new(device, "Bloom Target A", width / 2, height / 2);
+        let bloom_b = Target::new(device, "Bloom Target B", width / 2, height / 2);
+        let box_a = Target::new(device, "Box Blur Target A", width, height);
+        let box_b = Target::new(device, "Box Blur Target B", width, height);
+        let bind = |target: &Target| texture_bind_group(device, layout, &target.view, sampler);
+        let scene_bg = bind(&scene);
+        let bloom_a_bg = bind(&bloom_a);
+        let bloom_b_bg = bind(&bloom_b);
+        let box_a_bg = bind(&box_a);
+        let box_b_bg = bind(&box_b);
+        Self { scene, msaa, msaa_bg, bloom_a, bloom_b, box_a, box_b, scene_bg, bloom_a_bg, bloom_b_bg, box_a_bg, box_b_bg }
+    }
+}
+
+// The gap between neighbouring values of a fixed-point output format, in
+// its own encoding
+fn quantization_step(format: wgpu::TextureFormat) -> Option<f32> {
+    use wgpu::TextureFormat::*;
+    match format {
+        Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb => Some(1.0 / 255.0),
+        Rgb10a2Unorm => Some(1.0 / 1023.0),
+        _ => None,
+    }
+}
+
+fn texture_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Post Texture BG"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(view) },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+        ],
+    })
+}
+
+pub fn fullscreen_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    entry_point: &str,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(entry_point),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_fullscreen"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(entry_point),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub fn fullscreen_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    label: &str,
+    target: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    bind_groups: &[&wgpu::BindGroup],
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    pass.set_pipeline(pipeline);
+    for (index, bind_group) in bind_groups.iter().enumerate() {
+        pass.set_bind_group(index as u32, *bind_group, &[]);
+    }
+    pass.draw(0..3, 0..1);
+}
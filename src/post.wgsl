@@ -0,0 +1,317 @@
+// Fullscreen post-processing passes over the offscreen scene
+
+struct FullscreenOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One oversized triangle covering the screen, no vertex buffer needed
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: FullscreenOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Every pass gets its own copy, reading only the fields it needs
+struct PostParams {
+    direction : vec2<f32>,
+    radius : f32,
+    threshold : f32,
+    intensity : f32,
+    vignette_intensity : f32,
+    vignette_radius : f32,
+    depth_near : f32,
+    depth_far : f32,
+    // booleans, as 0 or 1
+    depth_perspective : f32,
+    depth_reverse : f32,
+    // texels per side of the color LUT's slices
+    lut_size : f32,
+    lut_intensity : f32,
+    // one output quantization step, 0 for no dithering
+    dither_step : f32,
+    // boolean: the output encodes to sRGB as it's written
+    output_srgb : f32,
+    // side of the blocks the composite reads one texel for, 1 for all of them
+    pixel_size : f32,
+    // 1 for each channel the composite keeps, 0 for the ones it zeroes
+    channel_mask : vec4<f32>,
+};
+
+@group(0) @binding(0)
+var src : texture_2d<f32>;
+@group(0) @binding(1)
+var src_sampler : sampler;
+
+@group(1) @binding(0)
+var<uniform> params : PostParams;
+
+// second input for passes that combine two textures
+@group(2) @binding(0)
+var overlay : texture_2d<f32>;
+@group(2) @binding(1)
+var overlay_sampler : sampler;
+
+// the composite's color LUT; see `grade`
+@group(3) @binding(0)
+var lut : texture_2d<f32>;
+@group(3) @binding(1)
+var lut_sampler : sampler;
+
+// the scene's depth buffer, for the depth view; only one of these is bound,
+// depending on whether the scene is multisampled. They're plain float
+// textures because GLSL has no loads from depth textures.
+@group(0) @binding(2)
+var scene_depth : texture_2d<f32>;
+@group(0) @binding(3)
+var scene_depth_ms : texture_multisampled_2d<f32>;
+
+// the multisampled scene before its resolve, for the MSAA coverage view
+@group(0) @binding(4)
+var scene_ms : texture_multisampled_2d<f32>;
+
+// The error banner's message on a grid of glyph cells; see `font.rs`
+struct BannerText {
+    // glyph indices, one a byte and lowest byte first, line after line
+    glyphs : array<vec4<u32>, 32>,
+    columns : u32,
+    lines : u32,
+    // screen pixels per font pixel
+    scale : u32,
+    padding : u32,
+};
+
+// 8x8 glyphs for printable ASCII, two u32s each, top four rows first
+@group(0) @binding(5)
+var<uniform> banner_font : array<vec4<u32>, 48>;
+@group(0) @binding(6)
+var<uniform> banner_text : BannerText;
+
+// Keeps only the part of each pixel brighter than the threshold
+@fragment
+fn fs_bright(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(src, src_sampler, in.uv).rgb;
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let excess = max(luminance - params.threshold, 0.0) / max(luminance, 1e-4);
+    return vec4<f32>(color * excess, 1.0);
+}
+
+// One direction of a separable gaussian
+@fragment
+fn fs_blur(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = params.direction / vec2<f32>(textureDimensions(src));
+    let sigma = max(params.radius * 0.5, 0.5);
+    let taps = i32(ceil(params.radius));
+    var sum = vec3<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var i = -taps; i <= taps; i++) {
+        let x = f32(i);
+        let weight = exp(-x * x / (2.0 * sigma * sigma));
+        sum += textureSampleLevel(src, src_sampler, in.uv + texel * x, 0.0).rgb * weight;
+        weight_sum += weight;
+    }
+    return vec4<f32>(sum / weight_sum, 1.0);
+}
+
+// One direction of a box blur: every tap within the radius counts the same
+@fragment
+fn fs_box_blur(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = params.direction / vec2<f32>(textureDimensions(src));
+    let taps = i32(params.radius);
+    var sum = vec3<f32>(0.0);
+    for (var i = -taps; i <= taps; i++) {
+        sum += textureSampleLevel(src, src_sampler, in.uv + texel * f32(i), 0.0).rgb;
+    }
+    let alpha = textureSampleLevel(src, src_sampler, in.uv, 0.0).a;
+    return vec4<f32>(sum / f32(2 * taps + 1), alpha);
+}
+
+// Scene plus the blurred highlights, darkened toward the edges and graded;
+// also the plain blit when all of those are off
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let uv = pixelate(in.uv);
+    let scene = textureSample(src, src_sampler, uv);
+    let glow = textureSample(overlay, overlay_sampler, uv).rgb;
+    let color = (scene.rgb + glow * params.intensity) * vignette(uv);
+    let graded = mix(color, grade(color), params.lut_intensity);
+    return vec4<f32>(dither(graded, vec2<u32>(in.clip_position.xy)), scene.a) * params.channel_mask;
+}
+
+// The center of the texel in the middle of the pixel_size block `uv` falls
+// in, so the whole block reads that one texel as if filtered nearest. The
+// blocks start at the top-left corner; partial ones along the right and
+// bottom edges whose middle is past the edge read the edge texel.
+fn pixelate(uv: vec2<f32>) -> vec2<f32> {
+    let n = params.pixel_size;
+    if (n <= 1.0) {
+        return uv;
+    }
+    let size = vec2<f32>(textureDimensions(src));
+    let texel = min(floor(uv * size / n) * n + floor(n * 0.5), size - 1.0);
+    return (texel + 0.5) / size;
+}
+
+// 0..15, the order pixels in a 4x4 tile cross a threshold: each 2x2 block
+// is [0 2; 3 1], nested
+fn bayer4(pixel: vec2<u32>) -> f32 {
+    let fine = pixel & vec2<u32>(1u);
+    let coarse = (pixel >> vec2<u32>(1u)) & vec2<u32>(1u);
+    let m = 4u * (2u * (fine.x ^ fine.y) + fine.y) + 2u * (coarse.x ^ coarse.y) + coarse.y;
+    return f32(m);
+}
+
+// Offsets the color by less than half an output step either way, so a
+// gradient between two steps rounds to a pattern that averages out to it
+// rather than to a band. The step is in the output's encoding, so with an
+// sRGB output the offset goes on the encoded value.
+fn dither(color: vec3<f32>, pixel: vec2<u32>) -> vec3<f32> {
+    if (params.dither_step == 0.0) {
+        return color;
+    }
+    let offset = ((bayer4(pixel) + 0.5) / 16.0 - 0.5) * params.dither_step;
+    if (params.output_srgb != 0.0) {
+        return srgb_to_linear(max(linear_to_srgb(max(color, vec3<f32>(0.0))) + offset, vec3<f32>(0.0)));
+    }
+    return color + offset;
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+// Looks the color up in the LUT strip. LUTs are authored against encoded
+// colors, so the lookup happens in sRGB and anything past 1 is clipped.
+// Red and green are filtered by the sampler; blue falls between two slices,
+// which are blended by hand so the filtering never bleeds across a seam.
+fn grade(color: vec3<f32>) -> vec3<f32> {
+    let n = params.lut_size;
+    let scaled = linear_to_srgb(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0))) * (n - 1.0);
+    let slice = floor(scaled.b);
+    let next = min(slice + 1.0, n - 1.0);
+    let v = (scaled.g + 0.5) / n;
+    let a = textureSampleLevel(lut, lut_sampler, vec2<f32>((slice * n + scaled.r + 0.5) / (n * n), v), 0.0).rgb;
+    let b = textureSampleLevel(lut, lut_sampler, vec2<f32>((next * n + scaled.r + 0.5) / (n * n), v), 0.0).rgb;
+    return srgb_to_linear(mix(a, b, scaled.b - slice));
+}
+
+// 1 inside the radius, falling to 1 - intensity at the corners. Distances
+// are in half-heights of the shorter side, so the falloff stays circular.
+fn vignette(uv: vec2<f32>) -> f32 {
+    let size = vec2<f32>(textureDimensions(src));
+    let scale = size / min(size.x, size.y);
+    let corner = length(scale);
+    let d = length((uv * 2.0 - 1.0) * scale);
+    return 1.0 - params.vignette_intensity * smoothstep(min(params.vignette_radius, corner - 1e-3), corner, d);
+}
+
+// Plain bilinear copy, used to downsample mip levels
+@fragment
+fn fs_copy(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return textureSample(src, src_sampler, in.uv);
+}
+
+// Whether font pixel `texel` of glyph `glyph` is set
+fn glyph_pixel(glyph : u32, texel : vec2<u32>) -> bool {
+    let word = glyph * 2u + texel.y / 4u;
+    let row = (banner_font[word / 4u][word % 4u] >> ((texel.y % 4u) * 8u)) & 0xffu;
+    return ((row >> texel.x) & 1u) != 0u;
+}
+
+// Red strip across the top of the screen with the message in white, as tall
+// as its lines; everything below it is left alone
+@fragment
+fn fs_error_banner(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let red = vec4<f32>(0.8, 0.05, 0.05, 1.0);
+    let cell = 8u * banner_text.scale;
+    let pixel = vec2<u32>(in.clip_position.xy);
+    if (pixel.y >= banner_text.lines * cell + 2u * banner_text.padding) {
+        discard;
+    }
+    if (any(pixel < vec2<u32>(banner_text.padding))) {
+        return red;
+    }
+    let inside = pixel - vec2<u32>(banner_text.padding);
+    let column = inside.x / cell;
+    let line = inside.y / cell;
+    if (column >= banner_text.columns || line >= banner_text.lines) {
+        return red;
+    }
+    let index = line * banner_text.columns + column;
+    let glyph = (banner_text.glyphs[index / 16u][(index / 4u) % 4u] >> ((index % 4u) * 8u)) & 0xffu;
+    if (glyph_pixel(glyph, (inside % cell) / banner_text.scale)) {
+        return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    }
+    return red;
+}
+
+// Depth as grayscale, black at the near plane and white at the far plane.
+// Perspective depth is mostly crowded up against 1, so it's turned back into
+// distance first; orthographic depth is already linear.
+fn depth_gray(depth: f32) -> vec4<f32> {
+    var z = depth;
+    if (params.depth_reverse != 0.0) {
+        z = 1.0 - z;
+    }
+    if (params.depth_perspective != 0.0) {
+        let near = params.depth_near;
+        let far = params.depth_far;
+        let distance = near * far / (far - z * (far - near));
+        z = (distance - near) / (far - near);
+    }
+    return vec4<f32>(vec3<f32>(clamp(z, 0.0, 1.0)), 1.0);
+}
+
+fn depth_texel(uv: vec2<f32>, size: vec2<u32>) -> vec2<u32> {
+    return min(vec2<u32>(uv * vec2<f32>(size)), size - 1u);
+}
+
+@fragment
+fn fs_depth(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = depth_texel(in.uv, textureDimensions(scene_depth));
+    return depth_gray(textureLoad(scene_depth, texel, 0).r);
+}
+
+// The first sample stands in for the pixel; depth can't be resolved
+@fragment
+fn fs_depth_multisampled(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = depth_texel(in.uv, textureDimensions(scene_depth_ms));
+    return depth_gray(textureLoad(scene_depth_ms, texel, 0).r);
+}
+
+// Blue through cyan, green and yellow to red as t goes from 0 to 1
+fn heat(t: f32) -> vec3<f32> {
+    let x = 4.0 * clamp(t, 0.0, 1.0);
+    return clamp(vec3<f32>(1.5) - abs(vec3<f32>(x) - vec3<f32>(3.0, 2.0, 1.0)), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Which pixels MSAA actually splits. Every sample is compared with the
+// first; where some differ, the pixel is on an edge only partly covered by
+// a triangle, and is painted by how many do, from blue for one to red for
+// all of them. Pixels whose samples all agree show the scene in dim gray.
+@fragment
+fn fs_msaa_coverage(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(scene_ms);
+    let texel = min(vec2<u32>(in.uv * vec2<f32>(size)), size - 1u);
+    let count = textureNumSamples(scene_ms);
+    let first = textureLoad(scene_ms, texel, 0);
+    var differing = 0u;
+    for (var i = 1; i < i32(count); i++) {
+        let d = abs(textureLoad(scene_ms, texel, i) - first);
+        if (max(max(d.r, d.g), max(d.b, d.a)) > 1.0 / 256.0) {
+            differing++;
+        }
+    }
+    if (differing == 0u) {
+        let luminance = dot(clamp(first.rgb, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(0.2126, 0.7152, 0.0722));
+        return vec4<f32>(vec3<f32>(luminance * 0.3), 1.0);
+    }
+    return vec4<f32>(heat(f32(differing - 1u) / f32(max(count, 3u) - 2u)), 1.0);
+}
@@ -0,0 +1,294 @@
+// Named snapshots of the runtime settings, for switching between looks
+//
+// A preset is a JSON file in `presets/` holding `Settings`:
+//
+//   {
+//     "bloom": { "enabled": true, "threshold": 0.8, "intensity": 1.5, "radius": 4 },
+//     "border": [0.04, [1.0, 1.0, 1.0, 1.0]],
+//     "ghost": null,
+//     "depth_compare": "less",
+//     ...
+//   }
+//
+// Loading a preset reads it over the current settings, so anything the
+// file leaves out stays as it is, down to single fields of the grouped
+// ones, and a hand-written preset can be a line long.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::background::{CheckerboardConfig, GradientStop, SkyConfig};
+use crate::gfx::ScaleMode;
+use crate::mesh::Spiral;
+use crate::post::{BloomSettings, BoxBlurSettings, ColorGradeSettings, VignetteSettings};
+
+/// Where presets are saved and looked for, relative to the working directory.
+pub const PRESET_DIR: &str = "presets";
+const EXTENSION: &str = "json";
+
+/// The runtime settings a preset holds: the look, not the window, device
+/// or animation state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Settings {
+    pub scale_mode: ScaleMode,
+    pub content_aspect: Option<f32>,
+    pub bloom: BloomSettings,
+    pub box_blur: BoxBlurSettings,
+    pub vignette: VignetteSettings,
+    pub color_grade: ColorGradeSettings,
+    pub lut: Option<PathBuf>,
+    pub dither: bool,
+    pub channel_mask: [bool; 4],
+    pub pixelation: Option<u32>,
+    pub show_grid: bool,
+    /// Dash and gap in pixels, for the grid and debug lines.
+    pub line_dash: Option<(f32, f32)>,
+    pub show_depth: bool,
+    pub msaa_debug: bool,
+    pub spiral: Option<Spiral>,
+    pub depth_compare: wgpu::CompareFunction,
+    /// Constant and slope scale.
+    pub depth_bias: (i32, f32),
+    pub blend: wgpu::BlendState,
+    pub alpha_to_coverage: bool,
+    pub srgb_vertex_colors: bool,
+    pub angle_snap: Option<f32>,
+    pub clear_each_frame: bool,
+    pub clear_depth: bool,
+    pub textured: bool,
+    pub mipmaps: bool,
+    pub sprites: bool,
+    pub filter: wgpu::FilterMode,
+    pub radial_gradient: Option<([f32; 3], [f32; 3])>,
+    pub corner_radius: f32,
+    pub border: Option<(f32, [f32; 4])>,
+    pub back_face_color: Option<[f32; 4]>,
+    pub triangle_debug_colors: bool,
+    pub cutout: bool,
+    pub checkerboard: Option<CheckerboardConfig>,
+    pub sky: Option<SkyConfig>,
+    pub gradient_stops: Vec<GradientStop>,
+    pub instancing: bool,
+    pub instance_culling: bool,
+    pub gpu_culling: bool,
+    pub translucent_quads: bool,
+    pub transparency_sort: bool,
+    pub ghost: Option<f32>,
+    pub wave: bool,
+    pub wave_subdivisions: u32,
+    pub sierpinski_depth: u32,
+}
+
+impl Settings {
+    pub fn to_json(&self) -> Result<String, PresetError> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    /// Overwrites the settings `json` names. Unknown names are skipped with
+    /// a warning, so presets from a newer build still load.
+    pub fn read(&mut self, json: &str) -> Result<(), PresetError> {
+        let file: Map<String, Value> = serde_json::from_str(json)?;
+        let Value::Object(mut merged) = serde_json::to_value(&*self)? else {
+            unreachable!("settings serialize as an object");
+        };
+        for (name, value) in file {
+            match merged.get_mut(&name) {
+                Some(current) => merge(current, value),
+                None => log::warn!("preset: skipping unknown setting `{name}`"),
+            }
+        }
+        *self = serde_json::from_value(Value::Object(merged))?;
+        Ok(())
+    }
+}
+
+/// The file a preset called `name` lives in. Names that are empty, hidden
+/// or would reach outside `PRESET_DIR` are refused.
+pub fn path(name: &str) -> Result<PathBuf, PresetError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(PresetError::Name(name.to_owned()));
+    }
+    Ok(PathBuf::from(PRESET_DIR).join(format!("{name}.{EXTENSION}")))
+}
+
+/// The saved presets' names in sorted order; none while `PRESET_DIR`
+/// doesn't exist.
+pub fn names() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(PRESET_DIR) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// `preset-1`, `preset-2` and so on, whichever comes first that isn't
+/// saved yet.
+pub fn next_free_name() -> String {
+    let taken = names();
+    (1..)
+        .map(|n| format!("preset-{n}"))
+        .find(|name| !taken.contains(name))
+        .expect("ran out of preset numbers")
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(std::io::Error),
+    /// A name `path` won't turn into a file.
+    Name(String),
+    /// Not JSON, or a setting that doesn't hold what it should.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read or write the preset: {e}"),
+            Self::Name(name) => write!(f, "`{name}` can't be a preset name"),
+            Self::Json(e) => write!(f, "couldn't parse the preset: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Name(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PresetError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+// Writes `value` over `current`, keeping the fields of objects it leaves out
+fn merge(current: &mut Value, value: Value) {
+    match (current, value) {
+        (Value::Object(current), Value::Object(value)) => {
+            for (name, value) in value {
+                match current.get_mut(&name) {
+                    Some(field) => merge(field, value),
+                    None => {
+                        current.insert(name, value);
+                    }
+                }
+            }
+        }
+        (current, value) => *current = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> Settings {
+        Settings {
+            scale_mode: ScaleMode::default(),
+            content_aspect: Some(16.0 / 9.0),
+            bloom: BloomSettings::default(),
+            box_blur: BoxBlurSettings::default(),
+            vignette: VignetteSettings::default(),
+            color_grade: ColorGradeSettings::default(),
+            lut: Some(PathBuf::from("assets/teal orange.png")),
+            dither: true,
+            channel_mask: [true, false, true, true],
+            pixelation: None,
+            show_grid: false,
+            line_dash: Some((6.0, 3.0)),
+            show_depth: false,
+            msaa_debug: false,
+            spiral: Some(Spiral::default()),
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            depth_bias: (-2, 1.5),
+            blend: wgpu::BlendState::ALPHA_BLENDING,
+            alpha_to_coverage: false,
+            srgb_vertex_colors: true,
+            angle_snap: None,
+            clear_each_frame: true,
+            clear_depth: true,
+            textured: false,
+            mipmaps: true,
+            sprites: false,
+            filter: wgpu::FilterMode::Nearest,
+            radial_gradient: Some(([1.0, 0.5, 0.0], [0.0, 0.0, 0.25])),
+            corner_radius: 0.1,
+            border: None,
+            back_face_color: Some([1.0, 0.0, 1.0, 1.0]),
+            triangle_debug_colors: false,
+            cutout: false,
+            checkerboard: Some(CheckerboardConfig::default()),
+            sky: None,
+            gradient_stops: vec![(0.0, [0.0, 0.0, 0.0]), (1.0, [1.0, 1.0, 1.0])],
+            instancing: true,
+            instance_culling: false,
+            gpu_culling: false,
+            translucent_quads: true,
+            transparency_sort: true,
+            ghost: Some(0.3),
+            wave: false,
+            wave_subdivisions: 64,
+            sierpinski_depth: 3,
+        }
+    }
+
+    #[test]
+    fn saved_settings_read_back_the_same() {
+        let saved = settings();
+        let json = saved.to_json().unwrap();
+        let mut read = settings();
+        read.dither = false;
+        read.blend = wgpu::BlendState::REPLACE;
+        read.gradient_stops.clear();
+        read.read(&json).unwrap();
+        assert_eq!(read.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn partial_presets_keep_what_they_leave_out() {
+        let mut read = settings();
+        read.read(r#"{ "ghost": null, "bloom": { "intensity": 2.5 }, "from_a_newer_build": 1 }"#).unwrap();
+        assert_eq!(read.ghost, None);
+        assert_eq!(read.bloom.intensity, 2.5);
+        assert_eq!(read.bloom.threshold, BloomSettings::default().threshold);
+        assert_eq!(read.depth_compare, wgpu::CompareFunction::LessEqual);
+        assert_eq!(read.gradient_stops.len(), 2);
+    }
+
+    #[test]
+    fn bad_presets_change_nothing() {
+        let mut read = settings();
+        assert!(matches!(read.read(r#"{ "ghost": 0.5, "wave_subdivisions": "many" }"#), Err(PresetError::Json(_))));
+        assert!(matches!(read.read("ghost = 0.5"), Err(PresetError::Json(_))));
+        assert!(matches!(read.read("[]"), Err(PresetError::Json(_))));
+        assert_eq!(read.ghost, Some(0.3));
+    }
+
+    #[test]
+    fn names_stay_inside_the_preset_dir() {
+        assert_eq!(path("dusk").unwrap(), PathBuf::from(PRESET_DIR).join("dusk.json"));
+        for name in ["", ".hidden", "../up", "a/b", "a\\b"] {
+            assert!(matches!(path(name), Err(PresetError::Name(_))), "{name:?}");
+        }
+    }
+}
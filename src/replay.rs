@@ -0,0 +1,218 @@
+// Recording input to a file and playing it back, for reproducible sessions
+//
+// One entry per line: seconds since recording started, then what happened.
+// `frame` lines carry the time step that frame advanced the simulation by,
+// and the input lines between two of them arrived between those frames:
+//
+//   0.016 frame 0.016
+//   0.532 key KeyN
+//   0.538 key Shift+KeyS
+//   0.541 button left down
+//   0.550 cursor 412.5 300
+//   0.551 frame 0.017
+//
+// Playback applies each frame's input and then steps by the recorded time
+// step, so the simulation sees exactly what it saw while recording whatever
+// the frame rate is now. Keys use the same names as the bindings file.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{ModifiersState, PhysicalKey};
+
+use crate::keys::{self, KeyChord};
+
+// Pixels of a touchpad's smooth scrolling that count as one wheel line
+const PIXELS_PER_SCROLL_LINE: f64 = 40.0;
+
+/// The input `App` reacts to, reduced to what a recording keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    /// A fresh press, with the modifiers held; releases and repeats don't
+    /// trigger anything.
+    Key(KeyChord),
+    Button { button: MouseButton, pressed: bool },
+    CursorMoved(PhysicalPosition<f64>),
+    CursorLeft,
+    /// Wheel lines, with touchpad pixels already converted.
+    Wheel(f32),
+}
+
+impl InputEvent {
+    /// `None` for events that aren't input, or that nothing reacts to (keys
+    /// a bindings file couldn't name, buttons past the middle one).
+    /// `modifiers` are the ones held as of the last `ModifiersChanged`.
+    pub fn from_window_event(event: &WindowEvent, modifiers: ModifiersState) -> Option<Self> {
+        match *event {
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { physical_key: PhysicalKey::Code(code), state: ElementState::Pressed, repeat: false, .. },
+                ..
+            } => Some(Self::Key(KeyChord::new(modifiers, code))),
+            WindowEvent::MouseInput { state, button: button @ (MouseButton::Left | MouseButton::Right | MouseButton::Middle), .. } => {
+                Some(Self::Button { button, pressed: state == ElementState::Pressed })
+            }
+            WindowEvent::CursorMoved { position, .. } => Some(Self::CursorMoved(position)),
+            WindowEvent::CursorLeft { .. } => Some(Self::CursorLeft),
+            WindowEvent::MouseWheel { delta, .. } => Some(Self::Wheel(match delta {
+                MouseScrollDelta::LineDelta(_, y) => y,
+                MouseScrollDelta::PixelDelta(position) => (position.y / PIXELS_PER_SCROLL_LINE) as f32,
+            })),
+            _ => None,
+        }
+    }
+
+    // Everything after the timestamp on a line
+    fn parse(words: &[&str]) -> Option<Self> {
+        match words {
+            ["key", name] => keys::parse_chord(name).map(Self::Key),
+            ["button", button, state] => {
+                let button = match *button {
+                    "left" => MouseButton::Left,
+                    "right" => MouseButton::Right,
+                    "middle" => MouseButton::Middle,
+                    _ => return None,
+                };
+                let pressed = match *state {
+                    "down" => true,
+                    "up" => false,
+                    _ => return None,
+                };
+                Some(Self::Button { button, pressed })
+            }
+            ["cursor", x, y] => Some(Self::CursorMoved(PhysicalPosition::new(x.parse().ok()?, y.parse().ok()?))),
+            ["cursor_left"] => Some(Self::CursorLeft),
+            ["wheel", lines] => lines.parse().ok().map(Self::Wheel),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(chord) => write!(f, "key {chord}"),
+            Self::Button { button, pressed } => {
+                let button = match button {
+                    MouseButton::Left => "left",
+                    MouseButton::Right => "right",
+                    _ => "middle",
+                };
+                write!(f, "button {button} {}", if *pressed { "down" } else { "up" })
+            }
+            Self::CursorMoved(position) => write!(f, "cursor {} {}", position.x, position.y),
+            Self::CursorLeft => write!(f, "cursor_left"),
+            Self::Wheel(lines) => write!(f, "wheel {lines}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    /// A line that isn't a timestamp followed by a frame or an input event.
+    Syntax { line: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read the recording: {e}"),
+            Self::Syntax { line } => write!(f, "recording line {line}: expected `seconds frame dt` or `seconds event ...`"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Appends input and frames to a recording as they happen. A failed write
+/// is logged once and ends the recording; the session carries on.
+pub struct Recorder {
+    file: Option<BufWriter<File>>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self { file: Some(BufWriter::new(File::create(path)?)), start: Instant::now() })
+    }
+
+    pub fn input(&mut self, event: InputEvent) {
+        self.line(format_args!("{event}"));
+    }
+
+    /// A frame that advanced the simulation by `dt` seconds.
+    pub fn frame(&mut self, dt: f32) {
+        self.line(format_args!("frame {dt}"));
+    }
+
+    fn line(&mut self, entry: fmt::Arguments<'_>) {
+        let Some(file) = &mut self.file else { return };
+        let seconds = self.start.elapsed().as_secs_f64();
+        if let Err(e) = writeln!(file, "{seconds:.3} {entry}") {
+            log::error!("couldn't write the input recording: {e}; recording stopped");
+            self.file = None;
+        }
+    }
+}
+
+/// One recorded frame: the input that came in before it, then its time step.
+pub struct ReplayFrame {
+    pub input: Vec<InputEvent>,
+    pub dt: f32,
+}
+
+/// A loaded recording, handed out a frame at a time.
+pub struct Player {
+    frames: VecDeque<ReplayFrame>,
+}
+
+impl Player {
+    /// Input after the last frame is dropped, since no frame would show it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let mut frames = VecDeque::new();
+        let mut input = Vec::new();
+        for (index, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let syntax = ReplayError::Syntax { line: index + 1 };
+            match words.as_slice() {
+                [] => (),
+                [seconds, rest @ ..] if seconds.parse::<f64>().is_ok() => match rest {
+                    ["frame", dt] => {
+                        let dt = dt.parse().map_err(|_| syntax)?;
+                        frames.push_back(ReplayFrame { input: std::mem::take(&mut input), dt });
+                    }
+                    rest => input.push(InputEvent::parse(rest).ok_or(syntax)?),
+                },
+                _ => return Err(syntax),
+            }
+        }
+        Ok(Self { frames })
+    }
+
+    pub fn frames_left(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        self.frames.pop_front()
+    }
+}
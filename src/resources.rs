@@ -0,0 +1,113 @@
+// Tallying what the renderer holds on the GPU, for spotting leaks and bloat
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceKind {
+    /// Sampled color images: the checkerboard and the sprite frames.
+    ColorTexture,
+    DepthTexture,
+    /// Multisampled attachments, resolved into an offscreen target.
+    MsaaTexture,
+    /// The scene target and the post-processing ping-pong targets.
+    OffscreenTexture,
+    VertexBuffer,
+    IndexBuffer,
+    UniformBuffer,
+    InstanceBuffer,
+    /// Compute-only data.
+    StorageBuffer,
+    /// Arguments for indirect draws.
+    IndirectBuffer,
+}
+
+impl ResourceKind {
+    const ALL: [ResourceKind; 10] = [
+        Self::ColorTexture,
+        Self::DepthTexture,
+        Self::MsaaTexture,
+        Self::OffscreenTexture,
+        Self::VertexBuffer,
+        Self::IndexBuffer,
+        Self::UniformBuffer,
+        Self::InstanceBuffer,
+        Self::StorageBuffer,
+        Self::IndirectBuffer,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::ColorTexture => "color textures",
+            Self::DepthTexture => "depth textures",
+            Self::MsaaTexture => "MSAA textures",
+            Self::OffscreenTexture => "offscreen textures",
+            Self::VertexBuffer => "vertex buffers",
+            Self::IndexBuffer => "index buffers",
+            Self::UniformBuffer => "uniform buffers",
+            Self::InstanceBuffer => "instance buffers",
+            Self::StorageBuffer => "storage buffers",
+            Self::IndirectBuffer => "indirect buffers",
+        }
+    }
+}
+
+/// Counts and estimated sizes of resources, grouped by kind. Texture sizes
+/// assume tightly packed texels, so drivers' padding and compression aren't
+/// in it; the surface's own images and staging memory aren't either, since
+/// wgpu doesn't hand those out.
+#[derive(Default)]
+pub struct ResourceReport {
+    entries: Vec<(ResourceKind, u64)>,
+}
+
+impl ResourceReport {
+    pub fn texture(&mut self, kind: ResourceKind, texture: &wgpu::Texture) {
+        let size = texture.size();
+        let texel = texture.format().block_copy_size(None).unwrap_or(4) as u64;
+        let bytes: u64 = (0..texture.mip_level_count())
+            .map(|level| {
+                let mip = size.mip_level_size(level, texture.dimension());
+                mip.width as u64 * mip.height as u64 * mip.depth_or_array_layers as u64
+            })
+            .sum();
+        self.entries.push((kind, bytes * texel * texture.sample_count() as u64));
+    }
+
+    pub fn buffer(&mut self, kind: ResourceKind, buffer: &wgpu::Buffer) {
+        self.entries.push((kind, buffer.size()));
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|&(_, bytes)| bytes).sum()
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for kind in ResourceKind::ALL {
+            let (count, bytes) = self
+                .entries
+                .iter()
+                .filter(|&&(k, _)| k == kind)
+                .fold((0, 0), |(count, total), &(_, bytes)| (count + 1, total + bytes));
+            if count > 0 {
+                writeln!(f, "  {:<19} {:>4}  {:>10}", kind.name(), count, Size(bytes))?;
+            }
+        }
+        write!(f, "  {:<19} {:>4}  {:>10}", "total", self.entries.len(), Size(self.total_bytes()))
+    }
+}
+
+// KiB below a MiB, so small uniforms don't all print as zero
+struct Size(u64);
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = if self.0 < 1 << 20 {
+            format!("{:.1} KiB", self.0 as f64 / 1024.0)
+        } else {
+            format!("{:.2} MiB", self.0 as f64 / (1024.0 * 1024.0))
+        };
+        f.pad(&text)
+    }
+}
@@ -0,0 +1,55 @@
+// A small retained hierarchy of 2D meshes, each placed relative to its parent
+
+use glam::{Affine2, Vec2};
+
+/// Scale, then rotation, then translation, in the parent's space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: [f32; 2],
+    /// Radians, counterclockwise
+    pub rotation: f32,
+    pub scale: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self { translation: [0.0; 2], rotation: 0.0, scale: 1.0 }
+    }
+}
+
+impl Transform {
+    pub fn to_affine(self) -> Affine2 {
+        Affine2::from_scale_angle_translation(Vec2::splat(self.scale), self.rotation, self.translation.into())
+    }
+}
+
+/// One of the preloaded meshes (by its index in `State`'s list) and the
+/// nodes that move with it.
+#[derive(Clone, Debug)]
+pub struct SceneNode {
+    pub mesh: usize,
+    pub local_transform: Transform,
+    pub children: Vec<SceneNode>,
+}
+
+impl SceneNode {
+    pub fn new(mesh: usize, local_transform: Transform) -> Self {
+        Self { mesh, local_transform, children: Vec::new() }
+    }
+
+    pub fn with_child(mut self, child: SceneNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Every node's mesh and world transform, parents before their
+    /// children. `spin` is turned through after each local transform, so a
+    /// turning parent carries its children around with it.
+    pub fn world_transforms(&self, parent: Affine2, spin: f32, out: &mut Vec<(usize, Affine2)>) {
+        let world = parent * self.local_transform.to_affine() * Affine2::from_angle(spin);
+        out.push((self.mesh, world));
+        for child in &self.children {
+            child.world_transforms(world, spin, out);
+        }
+    }
+}
@@ -1,35 +1,418 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
-    @location(1) color: vec3<f32>,
+    @location(1) color: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
-    @location(0) color: vec3<f32>,
+    @location(0) color: vec4<f32>,
 }
 
 struct AngleUniform {
     angle : f32,
+    // last frame's angle, and how opaque the ghost drawn at it is
+    prev_angle : f32,
+    ghost_alpha : f32,
+    // which layer of the sprite array to show
+    layer : u32,
 };
 
+struct ProjectionUniform {
+    ortho : mat4x4<f32>,
+};
+
+// >1.0 on HDR swapchains, where values past 1.0 are brighter than SDR white
+override output_scale: f32 = 1.0;
+
+// Cutouts hand their alpha to MSAA coverage instead of discarding; only
+// set on pipelines that have alpha-to-coverage enabled
+override alpha_to_coverage: bool = false;
+
+// Vertex colors are sRGB-encoded and get decoded before they're interpolated
+// and blended, which is all linear
+override srgb_vertex_colors: bool = false;
+
+// Group 0 is written every frame, group 1 on resize or when a setting
+// changes, and group 2 is whatever a draw binds for itself
+
 @group(0) @binding(0)
 var<uniform> u : AngleUniform;
 
+@group(1) @binding(0)
+var<uniform> projection : ProjectionUniform;
+
+struct GradientUniform {
+    inner : vec3<f32>,
+    outer : vec3<f32>,
+};
+
+@group(1) @binding(1)
+var<uniform> gradient : GradientUniform;
+
+struct RoundedRectUniform {
+    size : vec2<f32>,
+    corner_radius : f32,
+    // 0 draws no border
+    border_width : f32,
+    border_color : vec4<f32>,
+};
+
+@group(1) @binding(2)
+var<uniform> rounded_rect : RoundedRectUniform;
+
+@group(2) @binding(0)
+var tex : texture_2d<f32>;
+
+@group(2) @binding(1)
+var tex_sampler : sampler;
+
+// bound in place of `tex`, next to the same sampler
+@group(2) @binding(3)
+var sprites : texture_2d_array<f32>;
+
+fn rotate_by(p: vec2<f32>, angle: f32) -> vec2<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2<f32>(p.x * c - p.y * s, p.x * s + p.y * c);
+}
+
+fn rotate(p: vec2<f32>) -> vec2<f32> {
+    return rotate_by(p, u.angle);
+}
+
+// A scene node's world transform, picked per draw by dynamic offset
+struct NodeUniform {
+    model : mat4x4<f32>,
+};
+
+@group(2) @binding(4)
+var<uniform> node : NodeUniform;
+
+// Raw `Vertex` structs for vertex pulling: x, y, r, g, b, a with no padding,
+// which a WGSL struct with a vec4 member after a vec2 can't express
+@group(2) @binding(2)
+var<storage, read> pulled_vertices : array<f32>;
+
+// The exact sRGB transfer function, not the 2.2 gamma approximation
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + vec3<f32>(0.055)) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+// A mesh's own color as the rest of the pipeline wants it; alpha is linear either way
+fn vertex_color(c: vec4<f32>) -> vec4<f32> {
+    if srgb_vertex_colors {
+        return vec4<f32>(srgb_to_linear(c.rgb), c.a);
+    }
+    return c;
+}
+
+// Colors follow the rotated local position, so every copy of a shape looks the same
+fn shape_vertex(position: vec2<f32>, offset: vec2<f32>, phase: f32) -> VertexOutput {
+    var out: VertexOutput;
+    let rotated = rotate_by(position, u.angle + phase);
+    out.clip_position = projection.ortho * vec4<f32>(rotated + offset, 0.0, 1.0);
+    out.color = vec4<f32>(-rotated, 0.5, 1.0);
+    return out;
+}
+
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
+    return shape_vertex(in.position, vec2<f32>(0.0), 0.0);
+}
+
+struct InstanceInput {
+    @location(2) offset: vec2<f32>,
+    @location(3) scale: f32,
+    @location(4) depth: f32,
+    // radians ahead of the shared angle
+    @location(5) phase: f32,
+    // straight RGBA the instanced shapes' colors are multiplied by
+    @location(6) tint: vec4<f32>,
+}
+
+struct InstancedOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // the same for the whole instance, so nothing to interpolate
+    @location(1) @interpolate(flat) tint: vec4<f32>,
+};
+
+// Each instance spins in place, scaled down and moved to its offset
+@vertex
+fn vs_instanced(in: VertexInput, instance: InstanceInput) -> InstancedOutput {
+    let shape = shape_vertex(in.position * instance.scale, instance.offset, instance.phase);
+    var out: InstancedOutput;
+    out.clip_position = shape.clip_position;
+    out.color = shape.color;
+    out.tint = instance.tint;
+    return out;
+}
+
+// Spinning like the shapes, but at the instance's own depth and in the
+// mesh's own colors, alpha included
+@vertex
+fn vs_translucent(in: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
-    let c = cos(u.angle);
-    let s = sin(u.angle);
-    let rotated = vec2<f32>(
-        in.position.x * c - in.position.y * s,
-        in.position.x * s + in.position.y * c,
-    );
-    out.clip_position = vec4<f32>(rotated, 0.0, 1.0);
-    out.color = vec3<f32>(-rotated, 0.5);
+    let world = rotate(in.position * instance.scale) + instance.offset;
+    out.clip_position = projection.ortho * vec4<f32>(world, instance.depth, 1.0);
+    out.color = vertex_color(in.color);
     return out;
 }
 
+// Same as vs_main, fetching the vertex itself; with an indexed draw,
+// vertex_index is the index buffer value
+@vertex
+fn vs_pulled(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let base = index * 6u;
+    return shape_vertex(vec2<f32>(pulled_vertices[base], pulled_vertices[base + 1u]), vec2<f32>(0.0), 0.0);
+}
+
+// The shapes' own coloring of the rotated position shape_vertex passes on
+fn shape_color(c: vec4<f32>) -> vec3<f32> {
+    return vec3<f32>(cos(c.x), sin(c.y), tan(c.z)) * output_scale;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(cos(in.color.x), sin(in.color.y), tan(in.color.z), 1.0);
+    return vec4<f32>(shape_color(in.color), 1.0);
+}
+
+struct BackFaceUniform {
+    color : vec4<f32>,
+};
+
+@group(1) @binding(3)
+var<uniform> back_face : BackFaceUniform;
+
+// fs_main for front faces; back faces, which this pipeline doesn't cull,
+// are filled flat so the winding shows
+@fragment
+fn fs_back_faces(in: VertexOutput, @builtin(front_facing) front: bool) -> @location(0) vec4<f32> {
+    if front {
+        return vec4<f32>(shape_color(in.color), 1.0);
+    }
+    return vec4<f32>(back_face.color.rgb * output_scale, back_face.color.a);
+}
+
+// The shapes' coloring times the instance's tint; the pipeline doesn't
+// blend, so alpha below 1 only reaches the target's alpha channel
+@fragment
+fn fs_instanced(in: InstancedOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(shape_color(in.color), 1.0) * in.tint;
+}
+
+struct RoundedOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // unrotated mesh position, which the rectangle's distance is measured in
+    @location(1) local: vec2<f32>,
+};
+
+@vertex
+fn vs_rounded(in: VertexInput) -> RoundedOutput {
+    let shape = shape_vertex(in.position, vec2<f32>(0.0), 0.0);
+    var out: RoundedOutput;
+    out.clip_position = shape.clip_position;
+    out.color = shape.color;
+    out.local = in.position;
+    return out;
+}
+
+// Signed distance to a `size` rectangle centered on the origin, its corners
+// rounded by `radius`; negative inside
+fn rounded_rect_distance(p: vec2<f32>, size: vec2<f32>, radius: f32) -> f32 {
+    let q = abs(p) - 0.5 * size + vec2<f32>(radius);
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+// The shape's own colors, with the border color over them within
+// `border_width` of the edge; both edges fade over about a pixel
+@fragment
+fn fs_rounded(in: RoundedOutput) -> @location(0) vec4<f32> {
+    let d = rounded_rect_distance(in.local, rounded_rect.size, rounded_rect.corner_radius);
+    let pixel = max(fwidth(d), 1e-6);
+    let coverage = clamp(0.5 - d / pixel, 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    var color = shape_color(in.color);
+    if rounded_rect.border_width > 0.0 {
+        let border = clamp(0.5 + (d + rounded_rect.border_width) / pixel, 0.0, 1.0);
+        color = mix(color, rounded_rect.border_color.rgb * output_scale, border * rounded_rect.border_color.a);
+    }
+    return vec4<f32>(color, coverage);
+}
+
+// The shape again at last frame's angle, carrying the ghost's alpha along
+@vertex
+fn vs_ghost(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let rotated = rotate_by(in.position, u.prev_angle);
+    out.clip_position = projection.ortho * vec4<f32>(rotated, 0.0, 1.0);
+    out.color = vec4<f32>(-rotated, 0.5, u.ghost_alpha);
+    return out;
+}
+
+@fragment
+fn fs_ghost(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(shape_color(in.color), in.color.a);
+}
+
+// Overlay drawn straight in world space, no rotation
+@vertex
+fn vs_grid(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = projection.ortho * vec4<f32>(in.position, 0.0, 1.0);
+    out.color = vertex_color(in.color);
+    return out;
+}
+
+struct LineInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) along: vec2<f32>,
+}
+
+struct LineOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // from the start of the line, in pixels
+    @location(1) along: vec2<f32>,
+}
+
+struct LineDashUniform {
+    viewport : vec2<f32>,
+    // 0 draws the lines solid
+    dash : f32,
+    gap : f32,
+};
+
+@group(1) @binding(4)
+var<uniform> line_dash : LineDashUniform;
+
+// vs_grid for lines that can be dashed. The offset along the line is taken
+// through the projection without its translation and on into pixels, which
+// keeps it linear in the position, so it interpolates to the exact pixel
+// offset whatever the line's direction or the view's aspect
+@vertex
+fn vs_line(in: LineInput) -> LineOutput {
+    var out: LineOutput;
+    out.clip_position = projection.ortho * vec4<f32>(in.position, 0.0, 1.0);
+    out.color = vertex_color(in.color);
+    out.along = (projection.ortho * vec4<f32>(in.along, 0.0, 0.0)).xy * 0.5 * line_dash.viewport;
+    return out;
+}
+
+// fs_vertex_color, minus the gaps between dashes
+@fragment
+fn fs_dashed(in: LineOutput) -> @location(0) vec4<f32> {
+    let period = line_dash.dash + line_dash.gap;
+    if line_dash.dash > 0.0 && length(in.along) % period >= line_dash.dash {
+        discard;
+    }
+    return vec4<f32>(in.color.rgb * output_scale, in.color.a);
+}
+
+// A scene node placed by its world transform, in the mesh's own colors; the
+// spin is already part of the transform
+@vertex
+fn vs_node(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = projection.ortho * node.model * vec4<f32>(in.position, 0.0, 1.0);
+    out.color = vertex_color(in.color);
+    return out;
+}
+
+// The subdivided quad rippling along x as time passes, shaded by slope so
+// the wave reads; too few vertices and it shows as straight segments
+@vertex
+fn vs_wave(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let phase = in.position.x * 10.0 - u.angle * 3.0;
+    let position = vec2<f32>(in.position.x, in.position.y + 0.08 * sin(phase));
+    out.clip_position = projection.ortho * vec4<f32>(position, 0.0, 1.0);
+    let color = vertex_color(in.color);
+    out.color = vec4<f32>(color.rgb * (0.75 + 0.25 * cos(phase)), color.a);
+    return out;
+}
+
+// Vertex colors as they are; the alpha only matters where the pipeline blends
+@fragment
+fn fs_vertex_color(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color.rgb * output_scale, in.color.a);
+}
+
+struct TexturedOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// UVs come from the unrotated position, so the texture turns with the shape;
+// the unit square covers the texture exactly once
+@vertex
+fn vs_textured(in: VertexInput) -> TexturedOutput {
+    var out: TexturedOutput;
+    out.clip_position = projection.ortho * vec4<f32>(rotate(in.position), 0.0, 1.0);
+    out.uv = vec2<f32>(in.position.x + 0.5, 0.5 - in.position.y);
+    return out;
+}
+
+@fragment
+fn fs_textured(in: TexturedOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(tex, tex_sampler, in.uv).rgb;
+    return vec4<f32>(color * output_scale, 1.0);
+}
+
+struct SpriteOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) layer: u32,
+}
+
+// vs_textured's mapping, plus the layer, which is only visible to the vertex stage
+@vertex
+fn vs_sprite(in: VertexInput) -> SpriteOutput {
+    var out: SpriteOutput;
+    out.clip_position = projection.ortho * vec4<f32>(rotate(in.position), 0.0, 1.0);
+    out.uv = vec2<f32>(in.position.x + 0.5, 0.5 - in.position.y);
+    out.layer = u.layer;
+    return out;
+}
+
+@fragment
+fn fs_sprite(in: SpriteOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(sprites, tex_sampler, in.uv, in.layer).rgb;
+    return vec4<f32>(color * output_scale, 1.0);
+}
+
+// uv is 0.5 at the center; scaled so the unit square's corners reach 1
+@fragment
+fn fs_radial(in: TexturedOutput) -> @location(0) vec4<f32> {
+    let t = clamp(length(in.uv - vec2<f32>(0.5)) * sqrt(2.0), 0.0, 1.0);
+    return vec4<f32>(mix(gradient.inner, gradient.outer, t) * output_scale, 1.0);
+}
+
+// Alpha below this is cut away
+const CUTOUT_THRESHOLD: f32 = 0.5;
+
+// A six-lobed leaf, alpha falling off toward its scalloped rim. Without
+// alpha-to-coverage it's a hard alpha test; with it the alpha is sharpened
+// to about a pixel around the threshold, so coverage does the antialiasing.
+@fragment
+fn fs_cutout(in: TexturedOutput) -> @location(0) vec4<f32> {
+    let p = in.uv - vec2<f32>(0.5);
+    let r = length(p) * 2.0;
+    let rim = 0.65 + 0.3 * cos(6.0 * atan2(p.y, p.x));
+    let alpha = 1.0 - 0.5 * r / rim;
+    let color = vec3<f32>(0.15, 0.45 + 0.4 * (1.0 - r), 0.1) * output_scale;
+    if alpha_to_coverage {
+        let edge = clamp((alpha - CUTOUT_THRESHOLD) / max(fwidth(alpha), 1e-4) + 0.5, 0.0, 1.0);
+        return vec4<f32>(color, edge);
+    }
+    if alpha < CUTOUT_THRESHOLD {
+        discard;
+    }
+    return vec4<f32>(color, 1.0);
 }
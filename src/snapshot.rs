@@ -0,0 +1,182 @@
+// Everything the 2D and model uniforms are computed from, as JSON
+//
+// A snapshot freezes one frame's inputs rather than a look: the clock and
+// angle, the colors and shapes the styling uniforms hold, the view the
+// projection comes from and the model camera. Importing one puts all of
+// it back, so together with a paused (or held) angle the next frame draws
+// what the exported one did:
+//
+//   {
+//     "time": 12.5,
+//     "angle": 1.5707964,
+//     "spin_held": true,
+//     "scale_mode": "fit",
+//     "radial_gradient": null,
+//     "camera": { "target": [0.0, 0.5, 0.0], "distance": 4.2, ... },
+//     ...
+//   }
+//
+// Settings that pick pipelines or passes rather than uniform values are a
+// preset's; see `preset`.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::camera::ProjectionMode;
+use crate::gfx::ScaleMode;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UniformSnapshot {
+    /// Simulation seconds, which the sprite layer and wave follow.
+    pub time: f32,
+    pub angle: f32,
+    /// Last frame's angle, where the ghost is drawn.
+    pub prev_angle: f32,
+    /// Whether the angle was held by `rotate_to` rather than spinning.
+    pub spin_held: bool,
+    pub paused: bool,
+    pub time_scale: f32,
+    pub content_aspect: Option<f32>,
+    pub scale_mode: ScaleMode,
+    pub radial_gradient: Option<([f32; 3], [f32; 3])>,
+    pub corner_radius: f32,
+    pub border: Option<(f32, [f32; 4])>,
+    /// Missing from older files.
+    #[serde(default)]
+    pub line_dash: Option<(f32, f32)>,
+    pub back_face_color: Option<[f32; 4]>,
+    pub ghost: Option<f32>,
+    /// Only with a model loaded.
+    pub camera: Option<CameraSnapshot>,
+}
+
+/// An `OrbitCamera`'s placement.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct CameraSnapshot {
+    pub target: [f32; 3],
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub projection: ProjectionMode,
+    /// `None` when they're fitted to the model; missing from older files.
+    #[serde(default)]
+    pub clip_planes: Option<(f32, f32)>,
+}
+
+impl UniformSnapshot {
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read or write the uniform snapshot: {e}"),
+            Self::Json(e) => write!(f, "bad uniform snapshot: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> UniformSnapshot {
+        UniformSnapshot {
+            time: 12.5,
+            angle: std::f32::consts::FRAC_PI_2,
+            prev_angle: 1.5,
+            spin_held: true,
+            paused: false,
+            time_scale: -0.25,
+            content_aspect: Some(4.0 / 3.0),
+            scale_mode: ScaleMode::Fill,
+            radial_gradient: None,
+            corner_radius: 0.2,
+            border: Some((0.05, [1.0, 0.5, 0.0, 1.0])),
+            line_dash: Some((8.0, 4.0)),
+            back_face_color: None,
+            ghost: Some(0.4),
+            camera: Some(CameraSnapshot {
+                target: [0.0, 0.5, 0.0],
+                distance: 4.2,
+                yaw: -0.3,
+                pitch: 0.6,
+                projection: ProjectionMode::Orthographic { height: 3.0 },
+                clip_planes: Some((0.1, 50.0)),
+            }),
+        }
+    }
+
+    // A file of its own in the temp directory, so tests running at once don't share one
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("gfx_1-{}-{name}.json", std::process::id()))
+    }
+
+    #[test]
+    fn saved_snapshots_load_back_the_same() {
+        let path = temp_path("round-trip");
+        let saved = snapshot();
+        saved.save(&path).unwrap();
+        let loaded = UniformSnapshot::load(&path);
+        let _ = std::fs::remove_file(&path);
+        // through JSON values, since the snapshot has no PartialEq
+        assert_eq!(serde_json::to_value(loaded.unwrap()).unwrap(), serde_json::to_value(saved).unwrap());
+    }
+
+    #[test]
+    fn older_snapshots_load_without_the_newer_fields() {
+        let mut json = serde_json::to_value(snapshot()).unwrap();
+        json.as_object_mut().unwrap().remove("line_dash");
+        json["camera"].as_object_mut().unwrap().remove("clip_planes");
+        let loaded: UniformSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.line_dash, None);
+        assert_eq!(loaded.camera.unwrap().clip_planes, None);
+    }
+
+    #[test]
+    fn unreadable_snapshots_say_why() {
+        assert!(matches!(UniformSnapshot::load(&temp_path("missing")), Err(SnapshotError::Io(_))));
+        let path = temp_path("truncated");
+        std::fs::write(&path, r#"{ "time": 12.5, "angle": "#).unwrap();
+        let loaded = UniformSnapshot::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(loaded, Err(SnapshotError::Json(_))));
+    }
+}
@@ -0,0 +1,374 @@
+// Sampled textures with full mip chains generated on the GPU
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::GfxError;
+use crate::gfx::create_shader_module;
+use crate::post::{fullscreen_pass, fullscreen_pipeline};
+
+/// Color textures are sRGB, so filtering and downsampling happen in linear space.
+pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// LUT texels are sampled by their sRGB-encoded inputs and hold encoded
+// outputs, so they're read raw and the shader does the conversions
+const LUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+}
+
+impl Texture {
+    /// A `width` x `height` checkerboard of `cell`-pixel squares with every mip
+    /// level filled in. Neither side has to be a power of two.
+    pub fn checkerboard(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        cell: u32,
+    ) -> Result<Self, GfxError> {
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let on = (x / cell + y / cell).is_multiple_of(2);
+                pixels.extend_from_slice(if on { &[240, 240, 240, 255] } else { &[20, 20, 20, 255] });
+            }
+        }
+
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Checkerboard Texture"),
+            size,
+            mip_level_count: mip_level_count(width, height),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        // only level 0 is uploaded, the rest are rendered from it
+        queue.write_texture(
+            texture.as_image_copy(),
+            &pixels,
+            wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(width * 4), rows_per_image: None },
+            size,
+        );
+        generate_mipmaps(device, queue, &texture)?;
+
+        let view = texture.create_view(&Default::default());
+        Ok(Self { texture, view })
+    }
+}
+
+/// Same-sized images stacked as the layers of one texture, so the shader
+/// can switch between them by index without a rebind. No mip chain; these
+/// are meant for sprites drawn near their own size.
+pub struct TextureArray {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub layers: u32,
+}
+
+/// Why a set of images couldn't become a texture array.
+#[derive(Debug)]
+pub enum TextureError {
+    NoImages,
+    Decode { path: PathBuf, error: image::ImageError },
+    /// Every layer has to match the first image's size and pixel format.
+    Mismatch { path: PathBuf, expected: (u32, u32, image::ColorType), found: (u32, u32, image::ColorType) },
+    TooManyLayers { layers: usize, max: u32 },
+    /// A color LUT has to be an `n * n` x `n` strip.
+    NotALut { path: PathBuf, width: u32, height: u32 },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoImages => write!(f, "a texture array needs at least one image"),
+            Self::Decode { path, error } => write!(f, "couldn't load {}: {error}", path.display()),
+            Self::Mismatch { path, expected: (w, h, color), found: (fw, fh, fcolor) } => write!(
+                f,
+                "{} is {fw}x{fh} {fcolor:?}, but the first layer is {w}x{h} {color:?}",
+                path.display()
+            ),
+            Self::TooManyLayers { layers, max } => write!(f, "{layers} layers exceed this GPU's limit of {max}"),
+            Self::NotALut { path, width, height } => write!(
+                f,
+                "{} is {width}x{height}, but a LUT strip is n squared by n (256x16 for 16 steps)",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+/// Image files decoded to RGBA8 for a `TextureArray`, one layer each, so
+/// the decoding can happen away from the device.
+pub struct ArrayImages {
+    width: u32,
+    height: u32,
+    layers: Vec<Vec<u8>>,
+}
+
+impl ArrayImages {
+    /// One layer per image file, in order.
+    pub fn decode(paths: &[impl AsRef<Path>]) -> Result<Self, TextureError> {
+        let mut first = None;
+        let mut layers = Vec::with_capacity(paths.len());
+        for path in paths {
+            let path = path.as_ref();
+            let image = image::open(path).map_err(|error| TextureError::Decode { path: path.to_owned(), error })?;
+            let found = (image.width(), image.height(), image.color());
+            let expected = *first.get_or_insert(found);
+            if found != expected {
+                return Err(TextureError::Mismatch { path: path.to_owned(), expected, found });
+            }
+            layers.push(image.into_rgba8().into_raw());
+        }
+        let (width, height, _) = first.ok_or(TextureError::NoImages)?;
+        Ok(Self { width, height, layers })
+    }
+}
+
+impl TextureArray {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, images: &ArrayImages) -> Result<Self, GfxError> {
+        Self::from_layers(device, queue, images.width, images.height, &images.layers)
+    }
+
+    /// A few frames of a dot circling a small dark tile, for when no images
+    /// are given.
+    pub fn sprite_frames(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self, GfxError> {
+        const SIZE: u32 = 16;
+        const FRAMES: u32 = 8;
+        let layers: Vec<Vec<u8>> = (0..FRAMES)
+            .map(|frame| {
+                let a = frame as f32 / FRAMES as f32 * std::f32::consts::TAU;
+                let center = SIZE as f32 * 0.5;
+                let (dot_x, dot_y) = (center + 4.0 * a.cos(), center + 4.0 * a.sin());
+                let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+                for y in 0..SIZE {
+                    for x in 0..SIZE {
+                        let (dx, dy) = (x as f32 + 0.5 - dot_x, y as f32 + 0.5 - dot_y);
+                        let on = dx * dx + dy * dy <= 6.25;
+                        pixels.extend_from_slice(if on { &[255, 200, 40, 255] } else { &[30, 30, 60, 255] });
+                    }
+                }
+                pixels
+            })
+            .collect();
+        Self::from_layers(device, queue, SIZE, SIZE, &layers)
+    }
+
+    // Each layer is `width * height` tightly packed RGBA8 pixels
+    fn from_layers(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        layers: &[Vec<u8>],
+    ) -> Result<Self, GfxError> {
+        let max = device.limits().max_texture_array_layers;
+        if layers.is_empty() {
+            return Err(TextureError::NoImages.into());
+        }
+        if layers.len() > max as usize {
+            return Err(TextureError::TooManyLayers { layers: layers.len(), max }.into());
+        }
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: layers.len() as u32 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Sprite Texture Array"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            &layers.concat(),
+            wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(width * 4), rows_per_image: Some(height) },
+            size,
+        );
+        // explicit, since a one-layer array would otherwise get a plain 2D view
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        Ok(Self { texture, view, layers: size.depth_or_array_layers })
+    }
+}
+
+/// A 3D color lookup table unrolled into a strip: `size` square slices of
+/// `size` x `size` texels side by side, blue picking the slice, red running
+/// right and green down within it. Rows start at the top of the image.
+pub struct Lut {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: u32,
+}
+
+/// A LUT strip image decoded to RGBA8, checked for its shape but not yet
+/// against the device's texture size limit.
+pub struct LutImage {
+    path: PathBuf,
+    size: u32,
+    pixels: Vec<u8>,
+}
+
+impl LutImage {
+    pub fn decode(path: impl AsRef<Path>) -> Result<Self, TextureError> {
+        let path = path.as_ref();
+        let image = image::open(path).map_err(|error| TextureError::Decode { path: path.to_owned(), error })?;
+        let (width, height) = (image.width(), image.height());
+        if height < 2 || height.checked_mul(height) != Some(width) {
+            return Err(TextureError::NotALut { path: path.to_owned(), width, height });
+        }
+        Ok(Self { path: path.to_owned(), size: height, pixels: image.into_rgba8().into_raw() })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Lut {
+    pub fn load(device: &wgpu::Device, queue: &wgpu::Queue, path: impl AsRef<Path>) -> Result<Self, GfxError> {
+        Self::new(device, queue, &LutImage::decode(path)?)
+    }
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, image: &LutImage) -> Result<Self, GfxError> {
+        let (width, height) = (image.size * image.size, image.size);
+        if width > device.limits().max_texture_dimension_2d {
+            return Err(TextureError::NotALut { path: image.path.clone(), width, height }.into());
+        }
+        Ok(Self::from_strip(device, queue, image.size, &image.pixels))
+    }
+
+    /// The table that maps every color to itself, for when no LUT is loaded.
+    pub fn identity(device: &wgpu::Device, queue: &wgpu::Queue, size: u32) -> Self {
+        let step = |i: u32| (i * 255 / (size - 1)) as u8;
+        let mut pixels = Vec::with_capacity((size * size * size * 4) as usize);
+        for green in 0..size {
+            for x in 0..size * size {
+                pixels.extend_from_slice(&[step(x % size), step(green), step(x / size), 255]);
+            }
+        }
+        Self::from_strip(device, queue, size, &pixels)
+    }
+
+    fn from_strip(device: &wgpu::Device, queue: &wgpu::Queue, size: u32, pixels: &[u8]) -> Self {
+        let extent = wgpu::Extent3d { width: size * size, height: size, depth_or_array_layers: 1 };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color LUT"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: LUT_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            pixels,
+            wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(extent.width * 4), rows_per_image: None },
+            extent,
+        );
+        let view = texture.create_view(&Default::default());
+        Self { texture, view, size }
+    }
+}
+
+/// Levels down to 1x1, halving (and rounding down) each step; non-power-of-two
+/// sizes just end up with some levels that aren't exactly half the last.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// `filter` within a level: `Nearest` keeps pixel art's hard edges when
+/// magnified, `Linear` smooths them. Trilinear across levels when `mipmaps`
+/// is set; otherwise pinned to level 0, which is what shimmers when the
+/// texture is drawn small.
+pub fn create_sampler(device: &wgpu::Device, filter: wgpu::FilterMode, mipmaps: bool) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some(if mipmaps { "Mipmapped Sampler" } else { "Base Level Sampler" }),
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: if mipmaps { wgpu::FilterMode::Linear } else { wgpu::FilterMode::Nearest },
+        lod_min_clamp: 0.0,
+        lod_max_clamp: if mipmaps { 32.0 } else { 0.0 },
+        ..Default::default()
+    })
+}
+
+// wgpu has no automatic mip generation, so render each level from the one
+// above it with a bilinear fullscreen blit. Odd sizes lose their last
+// row/column's worth of weighting, which isn't visible at these scales.
+fn generate_mipmaps(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) -> Result<(), GfxError> {
+    let shader = create_shader_module(device, "Mipmap Shader", include_str!("post.wgsl"))?;
+    let bgl = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Mipmap BGL"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Mipmap Pipeline Layout"),
+        bind_group_layouts: &[&bgl],
+        push_constant_ranges: &[],
+    });
+    let pipeline = fullscreen_pipeline(device, &layout, &shader, "fs_copy", texture.format());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Mipmap Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let level_view = |level| {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    };
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("mipmap encoder") });
+    for level in 1..texture.mip_level_count() {
+        let src = level_view(level - 1);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mipmap BG"),
+            layout: &bgl,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&src) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&sampler) },
+            ],
+        });
+        fullscreen_pass(&mut encoder, &format!("mip {level}"), &level_view(level), &pipeline, &[&bind_group]);
+    }
+    queue.submit(Some(encoder.finish()));
+    Ok(())
+}
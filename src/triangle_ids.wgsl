@@ -0,0 +1,24 @@
+// Each triangle in a color of its own, hashed from its index in the draw.
+// A module of its own because `primitive_index` needs a device feature, and
+// using it anywhere in `shader.wgsl` would fail that whole module without
+// one. `Mesh::with_triangle_colors` bakes the same colors into vertices for
+// devices that don't have it.
+
+override output_scale: f32 = 1.0;
+
+// lowbias32, then a byte per channel kept off black
+fn triangle_color(index: u32) -> vec3<f32> {
+    var h = index;
+    h ^= h >> 16u;
+    h *= 0x7feb352du;
+    h ^= h >> 15u;
+    h *= 0x846ca68bu;
+    h ^= h >> 16u;
+    let bytes = vec3<u32>(h, h >> 8u, h >> 16u) & vec3<u32>(255u);
+    return 0.25 + 0.75 * vec3<f32>(bytes) / 255.0;
+}
+
+@fragment
+fn fs_triangle_ids(@builtin(primitive_index) index: u32) -> @location(0) vec4<f32> {
+    return vec4<f32>(triangle_color(index) * output_scale, 1.0);
+}
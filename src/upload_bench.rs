@@ -0,0 +1,163 @@
+// Timing the ways a small uniform can be rewritten every frame
+
+use std::fmt;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+// Mapped staging buffers cycled through, so a map in flight doesn't stall the next frame
+const MAPPED_RING: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadMethod {
+    /// `Queue::write_buffer`, which stages internally.
+    WriteBuffer,
+    /// `MAP_WRITE` buffers written through `get_mapped_range_mut` and copied
+    /// over. wgpu can't keep a buffer mapped while the GPU uses it, so each
+    /// one is remapped after its copy is submitted.
+    MappedBuffer,
+    /// `StagingBelt`, recalled after each submit.
+    StagingBelt,
+}
+
+impl UploadMethod {
+    pub const ALL: [UploadMethod; 3] = [Self::WriteBuffer, Self::MappedBuffer, Self::StagingBelt];
+}
+
+pub struct UploadTiming {
+    pub method: UploadMethod,
+    pub frames: u32,
+    /// Separate writes made each frame.
+    pub uploads: u32,
+    /// Wall time for every upload and submit plus waiting for the GPU at the end.
+    pub total: Duration,
+}
+
+impl UploadTiming {
+    pub fn per_frame(&self) -> Duration {
+        self.total / self.frames.max(1)
+    }
+}
+
+impl fmt::Display for UploadTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}: {:.2} us/frame ({:.1} ms over {} frames x {} uploads)",
+            self.method,
+            self.per_frame().as_secs_f64() * 1e6,
+            self.total.as_secs_f64() * 1e3,
+            self.frames,
+            self.uploads
+        )
+    }
+}
+
+// A staging buffer and word of when it's mapped again
+struct MappedSlot {
+    buffer: wgpu::Buffer,
+    mapped: mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// Rewrites `target` (which needs `COPY_DST`) `frames` times with each
+/// method, submitting once per frame like a real frame would, and times it.
+/// Each frame splits `target` into `uploads` equal slots and writes each
+/// one separately, the way a frame full of small uniforms would; the slot
+/// size has to stay a multiple of `COPY_BUFFER_ALIGNMENT`. Only fails if a
+/// staging buffer can't be mapped.
+pub fn time_uploads(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    target: &wgpu::Buffer,
+    uploads: u32,
+    frames: u32,
+) -> Result<Vec<UploadTiming>, wgpu::BufferAsyncError> {
+    let size = target.size();
+    let uploads = uploads.max(1);
+    let slot_size = size / uploads as u64;
+    assert!(
+        slot_size > 0 && slot_size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+        "{size} bytes can't be split into {uploads} aligned uploads"
+    );
+    // the first float of each slot changes every frame, like the angle does
+    let data = |frame: u32| {
+        let mut bytes = vec![0u8; size as usize];
+        for (i, chunk) in bytes.chunks_exact_mut(slot_size as usize).enumerate() {
+            chunk[..4].copy_from_slice(&(frame as f32 + i as f32).to_ne_bytes());
+        }
+        bytes
+    };
+    let encoder = || device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("upload bench encoder") });
+
+    let mut timings = Vec::new();
+    for method in UploadMethod::ALL {
+        // set up outside the timed loop
+        let mut belt = wgpu::util::StagingBelt::new(size.max(256));
+        let mut ring: Vec<MappedSlot> = match method {
+            UploadMethod::MappedBuffer => (0..MAPPED_RING)
+                .map(|_| {
+                    let (tx, rx) = mpsc::channel();
+                    let _ = tx.send(Ok(()));
+                    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Upload Bench Staging"),
+                        size,
+                        usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: true,
+                    });
+                    MappedSlot { buffer, mapped: rx }
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let _ = device.poll(wgpu::PollType::Wait);
+
+        let start = Instant::now();
+        for frame in 0..frames {
+            let bytes = data(frame);
+            let mut encoder = encoder();
+            match method {
+                UploadMethod::WriteBuffer => {
+                    for (offset, chunk) in (0..size).step_by(slot_size as usize).zip(bytes.chunks_exact(slot_size as usize)) {
+                        queue.write_buffer(target, offset, chunk);
+                    }
+                    queue.submit(Some(encoder.finish()));
+                }
+                UploadMethod::MappedBuffer => {
+                    let slot = &mut ring[frame as usize % MAPPED_RING];
+                    let mapped = match slot.mapped.try_recv() {
+                        Ok(result) => result,
+                        // still mapping; only the wait is counted against this method
+                        Err(_) => {
+                            let _ = device.poll(wgpu::PollType::Wait);
+                            slot.mapped.recv().expect("map callback dropped")
+                        }
+                    };
+                    mapped?;
+                    slot.buffer.slice(..).get_mapped_range_mut().copy_from_slice(&bytes);
+                    slot.buffer.unmap();
+                    for offset in (0..size).step_by(slot_size as usize) {
+                        encoder.copy_buffer_to_buffer(&slot.buffer, offset, target, offset, slot_size);
+                    }
+                    queue.submit(Some(encoder.finish()));
+
+                    let (tx, rx) = mpsc::channel();
+                    slot.buffer.slice(..).map_async(wgpu::MapMode::Write, move |result| {
+                        let _ = tx.send(result);
+                    });
+                    slot.mapped = rx;
+                }
+                UploadMethod::StagingBelt => {
+                    for (offset, chunk) in (0..size).step_by(slot_size as usize).zip(bytes.chunks_exact(slot_size as usize)) {
+                        let len = wgpu::BufferSize::new(chunk.len() as u64).expect("empty upload");
+                        belt.write_buffer(&mut encoder, target, offset, len, device).copy_from_slice(chunk);
+                    }
+                    belt.finish();
+                    queue.submit(Some(encoder.finish()));
+                    belt.recall();
+                }
+            }
+        }
+        let _ = device.poll(wgpu::PollType::Wait);
+        timings.push(UploadTiming { method, frames, uploads, total: start.elapsed() });
+    }
+    Ok(timings)
+}
@@ -0,0 +1,143 @@
+// Raw vertex dumps: a small header followed by little-endian f32s
+//
+//   bytes 0..4    magic "GFXV"
+//   bytes 4..8    vertex count, u32 LE
+//   bytes 8..12   stride in bytes, u32 LE (a non-zero multiple of 4)
+//   bytes 12..    count * stride bytes of f32 LE vertex data
+
+use std::fmt;
+use std::path::Path;
+
+pub const MAGIC: [u8; 4] = *b"GFXV";
+const HEADER_LEN: usize = 12;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// Shorter than the 12-byte header.
+    NoHeader { len: usize },
+    BadMagic([u8; 4]),
+    /// Zero, or not a whole number of f32s.
+    BadStride(u32),
+    /// The header promised more vertex data than the file holds.
+    Truncated { expected: usize, actual: usize },
+    /// Extra bytes after the last vertex, usually a wrong count or stride.
+    TrailingData { expected: usize, actual: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read vertex file: {e}"),
+            Self::NoHeader { len } => write!(f, "vertex file is {len} bytes, too short for a header"),
+            Self::BadMagic(magic) => write!(f, "not a vertex file (magic {magic:02x?}, expected \"GFXV\")"),
+            Self::BadStride(stride) => write!(f, "vertex stride {stride} isn't a positive multiple of 4 bytes"),
+            Self::Truncated { expected, actual } => {
+                write!(f, "vertex file truncated: header needs {expected} data bytes, found {actual}")
+            }
+            Self::TrailingData { expected, actual } => {
+                write!(f, "vertex file has {actual} data bytes but the header accounts for {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Reads a vertex dump, returning its floats and the number of floats per
+/// vertex. The whole file is read in one go and reinterpreted, with no
+/// per-value parsing.
+pub fn load_vertices_bin(path: impl AsRef<Path>) -> Result<(Vec<f32>, u32), LoadError> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < HEADER_LEN {
+        return Err(LoadError::NoHeader { len: bytes.len() });
+    }
+    let (header, data) = bytes.split_at(HEADER_LEN);
+    let word = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+
+    let magic: [u8; 4] = header[0..4].try_into().unwrap();
+    if magic != MAGIC {
+        return Err(LoadError::BadMagic(magic));
+    }
+    let count = word(4);
+    let stride = word(8);
+    if stride == 0 || stride % 4 != 0 {
+        return Err(LoadError::BadStride(stride));
+    }
+
+    let expected = count as usize * stride as usize;
+    match data.len() {
+        actual if actual < expected => return Err(LoadError::Truncated { expected, actual }),
+        actual if actual > expected => return Err(LoadError::TrailingData { expected, actual }),
+        _ => {}
+    }
+
+    // the data after a 12-byte header isn't necessarily 4-aligned, so copy out
+    let floats = data.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+    Ok((floats, stride / 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(count: u32, stride: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(stride.to_le_bytes());
+        bytes
+    }
+
+    // Writes `bytes` to a file of its own in the temp directory and loads it back
+    fn load(name: &str, bytes: &[u8]) -> Result<(Vec<f32>, u32), LoadError> {
+        let path = std::env::temp_dir().join(format!("gfx_1-{}-{name}.bin", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let loaded = load_vertices_bin(&path);
+        let _ = std::fs::remove_file(&path);
+        loaded
+    }
+
+    #[test]
+    fn vertex_files_load_their_floats_and_stride() {
+        let mut bytes = header(2, 8);
+        for v in [1.0f32, -2.5, 0.25, 1e6] {
+            bytes.extend(v.to_le_bytes());
+        }
+        let (floats, per_vertex) = load("valid", &bytes).unwrap();
+        assert_eq!(floats, [1.0, -2.5, 0.25, 1e6]);
+        assert_eq!(per_vertex, 2);
+    }
+
+    #[test]
+    fn malformed_vertex_files_say_why() {
+        assert!(matches!(load("no-header", b"GFXV\x01\0"), Err(LoadError::NoHeader { len: 6 })));
+
+        let mut bytes = header(1, 4);
+        bytes[..4].copy_from_slice(b"GFXW");
+        assert!(matches!(load("bad-magic", &bytes), Err(LoadError::BadMagic(m)) if &m == b"GFXW"));
+
+        assert!(matches!(load("zero-stride", &header(1, 0)), Err(LoadError::BadStride(0))));
+        assert!(matches!(load("odd-stride", &header(1, 6)), Err(LoadError::BadStride(6))));
+
+        let mut bytes = header(3, 8);
+        bytes.extend([0; 20]);
+        assert!(matches!(load("truncated", &bytes), Err(LoadError::Truncated { expected: 24, actual: 20 })));
+
+        let mut bytes = header(1, 8);
+        bytes.extend([0; 12]);
+        assert!(matches!(load("trailing", &bytes), Err(LoadError::TrailingData { expected: 8, actual: 12 })));
+    }
+}